@@ -0,0 +1,339 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::github::{ApiUsage, GitHubApi, GitHubError, RepositoryStatus};
+
+const CACHE_ENV: &str = "THANKS_STARS_CACHE_DIR";
+const CACHE_FILE_PREFIX: &str = "starred";
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("unable to determine cache directory")]
+    MissingDirectory,
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to serialize cache: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CacheEntry {
+    starred: bool,
+    checked_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawCache {
+    #[serde(default)]
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+/// On-disk record of repositories the viewer has starred, keyed by
+/// `owner/name`. Only positive answers are kept: a repository someone else
+/// starred, or one starred since, must still be checked.
+#[derive(Debug)]
+pub struct StarCache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl StarCache {
+    /// Load the cache of the GitHub user `login` from the default location,
+    /// honoring `THANKS_STARS_CACHE_DIR`. Every account gets its own file, so
+    /// switching tokens or profiles never reuses another account's answers.
+    pub fn load_default(login: &str) -> Result<Self, CacheError> {
+        let dir = determine_cache_dir()?;
+        Self::load(dir.join(cache_file_name(login)))
+    }
+
+    /// Load the cache stored at `path`. A missing or unreadable cache file
+    /// simply yields an empty cache.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, CacheError> {
+        let path = path.into();
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<RawCache>(&contents)
+                .map(|raw| raw.entries)
+                .unwrap_or_default(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(source) => {
+                return Err(CacheError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        Ok(Self {
+            path,
+            ttl: DEFAULT_TTL,
+            entries,
+        })
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether `owner/repo` was recently seen starred. Entries written by
+    /// older versions may record `false`; those are not trusted.
+    fn is_starred(&self, owner: &str, repo: &str) -> bool {
+        self.entries
+            .get(&cache_key(owner, repo))
+            .is_some_and(|entry| {
+                entry.starred && now_secs().saturating_sub(entry.checked_at) < self.ttl.as_secs()
+            })
+    }
+
+    /// Remember a starred repository, or forget one that no longer is.
+    fn record(&mut self, owner: &str, repo: &str, starred: bool) {
+        let key = cache_key(owner, repo);
+        if starred {
+            self.entries.insert(
+                key,
+                CacheEntry {
+                    starred,
+                    checked_at: now_secs(),
+                },
+            );
+        } else {
+            self.entries.remove(&key);
+        }
+    }
+
+    /// Write the cache back to disk, dropping expired entries.
+    pub fn save(&self) -> Result<(), CacheError> {
+        let now = now_secs();
+        let entries = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.starred && now.saturating_sub(entry.checked_at) < self.ttl.as_secs()
+            })
+            .map(|(key, entry)| (key.clone(), *entry))
+            .collect();
+        let contents = serde_json::to_string_pretty(&RawCache { entries })?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|source| CacheError::Io {
+                path: parent.display().to_string(),
+                source,
+            })?;
+        }
+        fs::write(&self.path, contents).map_err(|source| CacheError::Io {
+            path: self.path.display().to_string(),
+            source,
+        })
+    }
+}
+
+/// [`GitHubApi`] decorator that answers `viewer_has_starred` from a
+/// [`StarCache`] when a repository is known to be starred, and records
+/// fresh positive answers for later runs.
+pub struct CachedGitHubApi<'a, T: GitHubApi> {
+    inner: &'a T,
+    cache: RefCell<StarCache>,
+}
+
+impl<'a, T: GitHubApi> CachedGitHubApi<'a, T> {
+    pub fn new(inner: &'a T, cache: StarCache) -> Self {
+        Self {
+            inner,
+            cache: RefCell::new(cache),
+        }
+    }
+
+    pub fn persist(&self) -> Result<(), CacheError> {
+        self.cache.borrow().save()
+    }
+}
+
+impl<'a, T: GitHubApi> GitHubApi for CachedGitHubApi<'a, T> {
+    fn viewer_has_starred(&self, owner: &str, repo: &str) -> Result<bool, GitHubError> {
+        if self.cache.borrow().is_starred(owner, repo) {
+            return Ok(true);
+        }
+        let starred = self.inner.viewer_has_starred(owner, repo)?;
+        self.cache.borrow_mut().record(owner, repo, starred);
+        Ok(starred)
+    }
+
+    /// Answers cached repositories without asking GitHub. They are already
+    /// starred, so they are reported as not archived and without a
+    /// stargazer count.
+    fn repository_status(&self, owner: &str, repo: &str) -> Result<RepositoryStatus, GitHubError> {
+        if self.cache.borrow().is_starred(owner, repo) {
+            return Ok(RepositoryStatus {
+                viewer_has_starred: true,
+                is_archived: false,
+                stargazer_count: None,
+            });
+        }
+        let status = self.inner.repository_status(owner, repo)?;
+        self.cache
+            .borrow_mut()
+            .record(owner, repo, status.viewer_has_starred);
+        Ok(status)
+    }
+
+    fn star(&self, owner: &str, repo: &str) -> Result<(), GitHubError> {
+        self.inner.star(owner, repo)?;
+        self.cache.borrow_mut().record(owner, repo, true);
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<ApiUsage> {
+        self.inner.usage()
+    }
+}
+
+fn cache_file_name(login: &str) -> String {
+    format!("{CACHE_FILE_PREFIX}-{}.json", login.to_ascii_lowercase())
+}
+
+fn cache_key(owner: &str, repo: &str) -> String {
+    format!("{owner}/{repo}")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn determine_cache_dir() -> Result<PathBuf, CacheError> {
+    if let Ok(path) = env::var(CACHE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let dirs = ProjectDirs::from("dev", "thanks-stars", "thanks-stars")
+        .ok_or(CacheError::MissingDirectory)?;
+    Ok(dirs.cache_dir().to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::tempdir;
+
+    struct CountingGitHub {
+        queries: Cell<usize>,
+        starred: bool,
+    }
+
+    impl CountingGitHub {
+        fn new(starred: bool) -> Self {
+            Self {
+                queries: Cell::new(0),
+                starred,
+            }
+        }
+    }
+
+    impl GitHubApi for CountingGitHub {
+        fn viewer_has_starred(&self, _owner: &str, _repo: &str) -> Result<bool, GitHubError> {
+            self.queries.set(self.queries.get() + 1);
+            Ok(self.starred)
+        }
+
+        fn star(&self, _owner: &str, _repo: &str) -> Result<(), GitHubError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn second_run_skips_cached_queries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(cache_file_name("octocat"));
+        let api = CountingGitHub::new(true);
+
+        let first = CachedGitHubApi::new(&api, StarCache::load(&path).unwrap());
+        assert!(first.viewer_has_starred("owner", "repo").unwrap());
+        first.persist().unwrap();
+        assert_eq!(api.queries.get(), 1);
+
+        let second = CachedGitHubApi::new(&api, StarCache::load(&path).unwrap());
+        assert!(second.viewer_has_starred("owner", "repo").unwrap());
+        assert!(second.viewer_has_starred("owner", "other").unwrap());
+        assert_eq!(api.queries.get(), 2);
+    }
+
+    #[test]
+    fn only_starred_repositories_are_cached() {
+        let dir = tempdir().unwrap();
+        let api = CountingGitHub::new(false);
+        let cached =
+            CachedGitHubApi::new(&api, StarCache::load(dir.path().join("c.json")).unwrap());
+
+        assert!(!cached.viewer_has_starred("owner", "repo").unwrap());
+        assert!(!cached.viewer_has_starred("owner", "repo").unwrap());
+        assert_eq!(api.queries.get(), 2);
+
+        cached.star("owner", "repo").unwrap();
+        assert!(cached.viewer_has_starred("owner", "repo").unwrap());
+        assert_eq!(api.queries.get(), 2);
+    }
+
+    #[test]
+    fn repository_status_uses_the_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("c.json");
+        let api = CountingGitHub::new(true);
+
+        let first = CachedGitHubApi::new(&api, StarCache::load(&path).unwrap());
+        assert!(
+            first
+                .repository_status("owner", "repo")
+                .unwrap()
+                .viewer_has_starred
+        );
+        first.persist().unwrap();
+
+        let second = CachedGitHubApi::new(&api, StarCache::load(&path).unwrap());
+        assert!(
+            second
+                .repository_status("owner", "repo")
+                .unwrap()
+                .viewer_has_starred
+        );
+        assert_eq!(api.queries.get(), 1);
+    }
+
+    #[test]
+    fn accounts_use_separate_cache_files() {
+        assert_eq!(cache_file_name("Octocat"), "starred-octocat.json");
+        assert_ne!(cache_file_name("octocat"), cache_file_name("hubot"));
+    }
+
+    #[test]
+    fn expired_entries_are_queried_again() {
+        let dir = tempdir().unwrap();
+        let api = CountingGitHub::new(true);
+        let cache = StarCache::load(dir.path().join("c.json"))
+            .unwrap()
+            .with_ttl(Duration::ZERO);
+        let cached = CachedGitHubApi::new(&api, cache);
+
+        cached.viewer_has_starred("owner", "repo").unwrap();
+        cached.viewer_has_starred("owner", "repo").unwrap();
+
+        assert_eq!(api.queries.get(), 2);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -7,6 +8,7 @@ use serde::{Deserialize, Serialize};
 
 const CONFIG_ENV: &str = "THANKS_STARS_CONFIG_DIR";
 const CONFIG_FILE: &str = "config.toml";
+pub const DEFAULT_PROFILE: &str = "default";
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -18,55 +20,172 @@ pub enum ConfigError {
     TomlSer(#[from] toml::ser::Error),
     #[error("{0}")]
     TomlDe(#[from] toml::de::Error),
+    #[error("no token stored for profile `{0}`")]
+    MissingProfile(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct RawConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, RawProfile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    owners: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawProfile {
     token: String,
 }
 
+/// The non-secret parts of the stored configuration, for display.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSummary {
+    /// The profile `run` uses when `--profile` is not given.
+    pub default_profile: Option<String>,
+    /// Whether a flat top-level token is stored.
+    pub has_token: bool,
+    /// Names of the stored profiles, in sorted order.
+    pub profiles: Vec<String>,
+    /// Owners `run` is restricted to; empty means every owner.
+    pub owners: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigManager {
-    base_dir: PathBuf,
+    config_file: PathBuf,
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self, ConfigError> {
         let dir = determine_base_dir()?;
-        Ok(Self { base_dir: dir })
+        Ok(Self::with_base_dir(dir))
     }
 
     pub fn with_base_dir<P: Into<PathBuf>>(base_dir: P) -> Self {
+        Self::with_config_file(base_dir.into().join(CONFIG_FILE))
+    }
+
+    /// Use `path` itself as the configuration file instead of `config.toml`
+    /// inside a directory.
+    pub fn with_config_file<P: Into<PathBuf>>(path: P) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            config_file: path.into(),
         }
     }
 
+    /// Save the token of the default profile, so that [`load_token`] returns
+    /// it: into the profile named by `default_profile` when one is set, and
+    /// as the flat top-level `token` otherwise.
+    ///
+    /// [`load_token`]: Self::load_token
     pub fn save_token(&self, token: &str) -> Result<(), ConfigError> {
-        fs::create_dir_all(&self.base_dir)?;
-        let config = RawConfig {
-            token: token.to_string(),
-        };
-        let contents = toml::to_string(&config)?;
-        fs::write(self.config_file(), contents)?;
-        Ok(())
+        let mut config = self.read_existing()?;
+        match config.default_profile.clone() {
+            Some(name) => {
+                config.profiles.insert(
+                    name,
+                    RawProfile {
+                        token: token.to_string(),
+                    },
+                );
+            }
+            None => config.token = Some(token.to_string()),
+        }
+        self.write(&config)
     }
 
+    /// Load the token of the default profile.
+    ///
+    /// When `default_profile` is set the named profile is used; otherwise the
+    /// flat top-level `token` acts as the implicit default profile.
     pub fn load_token(&self) -> Result<String, ConfigError> {
-        let contents = fs::read_to_string(self.config_file())?;
-        let config: RawConfig = toml::from_str(&contents)?;
-        Ok(config.token)
+        let config = self.read()?;
+        match config.default_profile.as_deref() {
+            Some(name) => token_for_profile(&config, name),
+            None => token_for_profile(&config, DEFAULT_PROFILE),
+        }
+    }
+
+    pub fn save_token_for_profile(&self, name: &str, token: &str) -> Result<(), ConfigError> {
+        let mut config = self.read_existing()?;
+        config.profiles.insert(
+            name.to_string(),
+            RawProfile {
+                token: token.to_string(),
+            },
+        );
+        self.write(&config)
+    }
+
+    pub fn load_token_for_profile(&self, name: &str) -> Result<String, ConfigError> {
+        let config = self.read()?;
+        token_for_profile(&config, name)
+    }
+
+    /// Describe the stored configuration without exposing any token.
+    pub fn summary(&self) -> Result<ConfigSummary, ConfigError> {
+        let config = self.read_existing()?;
+        Ok(ConfigSummary {
+            default_profile: config.default_profile,
+            has_token: config.token.is_some(),
+            profiles: config.profiles.into_keys().collect(),
+            owners: config.owners,
+        })
+    }
+
+    /// The `owners = [...]` allowlist; empty when unset or when there is no
+    /// configuration file yet.
+    pub fn owners(&self) -> Result<Vec<String>, ConfigError> {
+        Ok(self.read_existing()?.owners)
     }
 
     pub fn config_file(&self) -> PathBuf {
-        self.base_dir.join(CONFIG_FILE)
+        self.config_file.clone()
     }
 
     pub fn base_dir(&self) -> &Path {
-        &self.base_dir
+        self.config_file.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    fn read(&self) -> Result<RawConfig, ConfigError> {
+        let contents = fs::read_to_string(self.config_file())?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    fn read_existing(&self) -> Result<RawConfig, ConfigError> {
+        match self.read() {
+            Ok(config) => Ok(config),
+            Err(ConfigError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(RawConfig::default())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn write(&self, config: &RawConfig) -> Result<(), ConfigError> {
+        fs::create_dir_all(self.base_dir())?;
+        let contents = toml::to_string(config)?;
+        fs::write(self.config_file(), contents)?;
+        Ok(())
     }
 }
 
+fn token_for_profile(config: &RawConfig, name: &str) -> Result<String, ConfigError> {
+    if let Some(profile) = config.profiles.get(name) {
+        return Ok(profile.token.clone());
+    }
+    if name == DEFAULT_PROFILE {
+        if let Some(token) = &config.token {
+            return Ok(token.clone());
+        }
+    }
+    Err(ConfigError::MissingProfile(name.to_string()))
+}
+
 fn determine_base_dir() -> Result<PathBuf, ConfigError> {
     if let Ok(path) = env::var(CONFIG_ENV) {
         return Ok(PathBuf::from(path));
@@ -106,4 +225,134 @@ mod tests {
             other => panic!("unexpected error: {other:?}"),
         }
     }
+
+    #[test]
+    fn saves_and_loads_multiple_profiles() {
+        let dir = tempdir().unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+
+        manager
+            .save_token_for_profile("personal", "personal-token")
+            .unwrap();
+        manager
+            .save_token_for_profile("work", "work-token")
+            .unwrap();
+
+        assert_eq!(
+            manager.load_token_for_profile("personal").unwrap(),
+            "personal-token"
+        );
+        assert_eq!(
+            manager.load_token_for_profile("work").unwrap(),
+            "work-token"
+        );
+        assert!(matches!(
+            manager.load_token_for_profile("missing").unwrap_err(),
+            ConfigError::MissingProfile(name) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn flat_token_acts_as_default_profile() {
+        let dir = tempdir().unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+
+        manager.save_token("flat-token").unwrap();
+        manager
+            .save_token_for_profile("work", "work-token")
+            .unwrap();
+
+        assert_eq!(manager.load_token().unwrap(), "flat-token");
+        assert_eq!(
+            manager.load_token_for_profile(DEFAULT_PROFILE).unwrap(),
+            "flat-token"
+        );
+        assert_eq!(
+            manager.load_token_for_profile("work").unwrap(),
+            "work-token"
+        );
+    }
+
+    #[test]
+    fn default_profile_selects_named_token() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+token = "flat-token"
+default_profile = "work"
+
+[profiles.work]
+token = "work-token"
+"#,
+        )
+        .unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+
+        assert_eq!(manager.load_token().unwrap(), "work-token");
+    }
+
+    #[test]
+    fn saved_token_goes_to_the_default_profile() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+token = "flat-token"
+default_profile = "work"
+
+[profiles.work]
+token = "old-work-token"
+"#,
+        )
+        .unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+
+        manager.save_token("new-work-token").unwrap();
+
+        assert_eq!(manager.load_token().unwrap(), "new-work-token");
+        assert_eq!(
+            manager.load_token_for_profile("work").unwrap(),
+            "new-work-token"
+        );
+        assert_eq!(
+            manager.load_token_for_profile(DEFAULT_PROFILE).unwrap(),
+            "flat-token"
+        );
+    }
+
+    #[test]
+    fn summary_lists_profiles_without_tokens() {
+        let dir = tempdir().unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+
+        assert_eq!(manager.summary().unwrap(), ConfigSummary::default());
+
+        manager.save_token("flat-token").unwrap();
+        manager
+            .save_token_for_profile("work", "work-token")
+            .unwrap();
+
+        let summary = manager.summary().unwrap();
+        assert!(summary.has_token);
+        assert_eq!(summary.default_profile, None);
+        assert_eq!(summary.profiles, vec!["work".to_string()]);
+    }
+
+    #[test]
+    fn reads_owner_allowlist() {
+        let dir = tempdir().unwrap();
+        let manager = ConfigManager::with_base_dir(dir.path());
+        assert!(manager.owners().unwrap().is_empty());
+
+        fs::write(
+            manager.config_file(),
+            "token = \"abc\"\nowners = [\"rust-lang\", \"tokio-rs\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(manager.owners().unwrap(), ["rust-lang", "tokio-rs"]);
+        manager.save_token("def").unwrap();
+        assert_eq!(manager.owners().unwrap(), ["rust-lang", "tokio-rs"]);
+    }
 }
@@ -0,0 +1,204 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::discovery::{Framework, ProjectDirectory};
+
+const CONFIG_PATHS: &[&str] = &[".github/dependabot.yml", ".github/dependabot.yaml"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum DependabotError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Yaml {
+        path: String,
+        #[source]
+        source: serde_yaml::Error,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(default)]
+    updates: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    #[serde(rename = "package-ecosystem")]
+    package_ecosystem: String,
+    directory: Option<String>,
+    #[serde(default)]
+    directories: Vec<String>,
+}
+
+/// Read the directories and ecosystems listed under `updates` in
+/// `.github/dependabot.yml`.
+///
+/// Entries for ecosystems without a matching [`Framework`] in this build, for
+/// directories that do not exist, and glob patterns in `directories` are
+/// skipped. Frameworks whose manifests are missing from a listed directory are
+/// dropped so discovery does not fail on them.
+pub fn directories(project_root: &Path) -> Result<Vec<ProjectDirectory>, DependabotError> {
+    let (path, contents) = read_config(project_root)?;
+    let config: Config =
+        serde_yaml::from_str(&contents).map_err(|source| DependabotError::Yaml {
+            path: path.display().to_string(),
+            source,
+        })?;
+
+    let mut result: Vec<ProjectDirectory> = Vec::new();
+    for update in config.updates {
+        let Some(framework) = framework_for(&update.package_ecosystem) else {
+            debug!(ecosystem = %update.package_ecosystem, "unsupported dependabot ecosystem");
+            continue;
+        };
+        let listed = update.directory.iter().chain(&update.directories);
+        for directory in listed {
+            let Some(relative_path) = relative_directory(directory) else {
+                debug!(%directory, "skipping dependabot directory");
+                continue;
+            };
+            if !framework.is_present(&project_root.join(&relative_path)) {
+                debug!(%directory, ?framework, "no manifest in dependabot directory");
+                continue;
+            }
+            match result
+                .iter_mut()
+                .find(|existing| existing.relative_path == relative_path)
+            {
+                Some(existing) if existing.frameworks.contains(&framework) => {}
+                Some(existing) => existing.frameworks.push(framework),
+                None => result.push(ProjectDirectory {
+                    relative_path,
+                    frameworks: vec![framework],
+                }),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn read_config(project_root: &Path) -> Result<(PathBuf, String), DependabotError> {
+    for candidate in CONFIG_PATHS {
+        let path = project_root.join(candidate);
+        match fs::read_to_string(&path) {
+            Ok(contents) => return Ok((path, contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => {
+                return Err(DependabotError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        }
+    }
+    Err(DependabotError::Io {
+        path: project_root.join(CONFIG_PATHS[0]).display().to_string(),
+        source: io::ErrorKind::NotFound.into(),
+    })
+}
+
+/// `directory` relative to the project root, or `None` for globs and paths
+/// that would leave it. Dependabot writes the root as `/`.
+fn relative_directory(directory: &str) -> Option<PathBuf> {
+    if directory.contains(['*', '?', '[']) {
+        return None;
+    }
+    let path = Path::new(directory.trim_start_matches('/'));
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    Some(path.components().collect())
+}
+
+fn framework_for(ecosystem: &str) -> Option<Framework> {
+    let framework = match ecosystem {
+        #[cfg(feature = "ecosystem-node")]
+        "npm" | "bun" => Framework::Node,
+        #[cfg(feature = "ecosystem-cargo")]
+        "cargo" => Framework::Cargo,
+        #[cfg(feature = "ecosystem-go")]
+        "gomod" => Framework::Go,
+        #[cfg(feature = "ecosystem-dart")]
+        "pub" => Framework::Dart,
+        #[cfg(feature = "ecosystem-composer")]
+        "composer" => Framework::Composer,
+        #[cfg(feature = "ecosystem-ruby")]
+        "bundler" => Framework::Ruby,
+        #[cfg(feature = "ecosystem-python")]
+        "pip" | "uv" => Framework::Python,
+        #[cfg(feature = "ecosystem-gradle")]
+        "gradle" => Framework::Gradle,
+        #[cfg(feature = "ecosystem-maven")]
+        "maven" => Framework::Maven,
+        _ => return None,
+    };
+    Some(framework)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reads_directories_and_ecosystems_from_dependabot_config() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".github")).unwrap();
+        fs::create_dir_all(dir.path().join("web")).unwrap();
+        fs::create_dir_all(dir.path().join("services/api")).unwrap();
+        fs::write(dir.path().join("web/package.json"), "{}").unwrap();
+        fs::write(
+            dir.path().join("services/api/Cargo.toml"),
+            "[package]\nname = \"api\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".github/dependabot.yml"),
+            r#"
+version: 2
+updates:
+  - package-ecosystem: npm
+    directory: /web
+    schedule:
+      interval: weekly
+  - package-ecosystem: cargo
+    directories:
+      - /services/api
+      - /services/*
+  - package-ecosystem: github-actions
+    directory: /
+"#,
+        )
+        .unwrap();
+
+        let directories = directories(dir.path()).unwrap();
+
+        assert_eq!(
+            directories,
+            vec![
+                ProjectDirectory {
+                    relative_path: PathBuf::from("web"),
+                    frameworks: vec![Framework::Node],
+                },
+                ProjectDirectory {
+                    relative_path: PathBuf::from("services/api"),
+                    frameworks: vec![Framework::Cargo],
+                },
+            ]
+        );
+    }
+}
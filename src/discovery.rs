@@ -1,38 +1,169 @@
-use std::path::Path;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
 
+#[cfg(any(
+    feature = "ecosystem-dart",
+    feature = "ecosystem-haskell",
+    feature = "ecosystem-maven",
+    feature = "ecosystem-node",
+    feature = "ecosystem-python",
+    feature = "ecosystem-ruby"
+))]
+use crate::ecosystems::CachingFetcher;
+#[cfg(any(
+    feature = "ecosystem-cargo",
+    feature = "ecosystem-dart",
+    feature = "ecosystem-python",
+    feature = "ecosystem-ruby"
+))]
+use crate::ecosystems::FileBackedFetcher;
+#[cfg(any(feature = "ecosystem-deno", feature = "ecosystem-node"))]
+use crate::ecosystems::HttpNpmRegistryClient;
+#[cfg(feature = "ecosystem-maven")]
+use crate::ecosystems::{
+    configured_maven_repositories, maven_user_settings_path, CachedMavenClient, HttpMavenClient,
+    MavenDiscoverer, MavenDiscoveryError, MavenRepositoryChain,
+};
+#[cfg(feature = "ecosystem-haskell")]
+use crate::ecosystems::{
+    CachedHackageClient, HaskellDiscoverer, HaskellDiscoveryError, HttpHackageClient,
+};
+#[cfg(feature = "ecosystem-node")]
+use crate::ecosystems::{CachedNpmRegistryClient, NodeDiscoverer, NodeDiscoveryError};
+#[cfg(feature = "ecosystem-dart")]
+use crate::ecosystems::{CachedPubDevClient, DartDiscoverer, DartDiscoveryError, HttpPubDevClient};
+#[cfg(feature = "ecosystem-python")]
+use crate::ecosystems::{CachedPyPiClient, HttpPyPiClient, PythonDiscoverer, PythonDiscoveryError};
+#[cfg(feature = "ecosystem-ruby")]
+use crate::ecosystems::{
+    CachedRubyGemsClient, HttpRubyGemsClient, RubyDiscoverer, RubyDiscoveryError,
+};
 #[cfg(feature = "ecosystem-cargo")]
-use crate::ecosystems::{CargoDiscoverer, CargoDiscoveryError, CommandMetadataFetcher};
+use crate::ecosystems::{
+    CargoDiscoverer, CargoDiscoveryError, CargoManifestDiscoverer, CommandMetadataFetcher,
+    HttpCratesIoClient,
+};
 #[cfg(feature = "ecosystem-composer")]
-use crate::ecosystems::{ComposerDiscoverer, ComposerDiscoveryError};
-#[cfg(feature = "ecosystem-dart")]
-use crate::ecosystems::{DartDiscoverer, DartDiscoveryError};
+use crate::ecosystems::{ComposerDiscoverer, ComposerDiscoveryError, HttpPackagistClient};
 #[cfg(feature = "ecosystem-deno")]
-use crate::ecosystems::{DenoDiscoverer, DenoDiscoveryError};
+use crate::ecosystems::{DenoDiscoverer, DenoDiscoveryError, HttpDenoLandClient, HttpJsrClient};
 #[cfg(feature = "ecosystem-go")]
 use crate::ecosystems::{GoDiscoverer, GoDiscoveryError};
 #[cfg(feature = "ecosystem-gradle")]
 use crate::ecosystems::{GradleDiscoverer, GradleDiscoveryError};
-#[cfg(feature = "ecosystem-haskell")]
-use crate::ecosystems::{HaskellDiscoverer, HaskellDiscoveryError};
-#[cfg(feature = "ecosystem-maven")]
-use crate::ecosystems::{MavenDiscoverer, MavenDiscoveryError};
-#[cfg(feature = "ecosystem-node")]
-use crate::ecosystems::{NodeDiscoverer, NodeDiscoveryError};
-#[cfg(feature = "ecosystem-python")]
-use crate::ecosystems::{PythonDiscoverer, PythonDiscoveryError};
 #[cfg(feature = "ecosystem-renv")]
-use crate::ecosystems::{RenvDiscoverer, RenvDiscoveryError};
-#[cfg(feature = "ecosystem-ruby")]
-use crate::ecosystems::{RubyDiscoverer, RubyDiscoveryError};
+use crate::ecosystems::{HttpRUniverseClient, RenvDiscoverer, RenvDiscoveryError};
+#[cfg(feature = "ecosystem-sbom")]
+use crate::ecosystems::{SbomDiscoverer, SbomDiscoveryError};
+use reqwest::blocking::Client;
 use url::Url;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+use crate::http::HttpOptions;
+
+/// A GitHub repository discovered from a dependency manifest.
+///
+/// Equality and hashing only consider `owner` and `name`, so the same
+/// repository reached through different manifests or URL spellings compares
+/// equal.
+#[derive(Debug, Clone)]
 pub struct Repository {
     pub owner: String,
     pub name: String,
+    /// Canonical `https://github.com/{owner}/{name}` URL.
     pub url: String,
+    /// The manifest the repository was first found through.
     pub via: Option<String>,
+    /// Further manifests that led to the same repository, in discovery order.
+    pub also_via: Vec<String>,
+    /// The metadata value the repository was parsed from, as written in the
+    /// manifest or registry (e.g. an SSH or `git+` URL), even when it already
+    /// equals `url`. Set by [`parse_github_repository`]; `None` for
+    /// repositories built by hand.
+    pub original_url: Option<String>,
+    /// Subdirectory of the repository the package lives in, when the manifest
+    /// declares one separately from the URL (package.json
+    /// `repository.directory`).
+    pub directory: Option<String>,
+    /// How many users have starred the repository, filled in during the star
+    /// check when [`RunOptions::fetch_stargazers`](crate::RunOptions::fetch_stargazers)
+    /// is set.
+    pub stargazer_count: Option<u64>,
+    /// The ecosystem whose built-in discoverer found the repository; `None`
+    /// for discoverers registered by hand. When several ecosystems find the
+    /// same repository, the first one is kept.
+    pub framework: Option<Framework>,
+}
+
+impl Repository {
+    /// Record that the repository was also reached through `via`. The first
+    /// source becomes [`via`](Self::via); repeated sources are ignored.
+    pub fn add_via(&mut self, via: impl Into<String>) {
+        let via = via.into();
+        match &self.via {
+            None => self.via = Some(via),
+            Some(first) if *first == via || self.also_via.contains(&via) => {}
+            Some(_) => self.also_via.push(via),
+        }
+    }
+
+    /// Every manifest the repository was found through, first one first.
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.via
+            .as_deref()
+            .into_iter()
+            .chain(self.also_via.iter().map(String::as_str))
+    }
+
+    /// The sources joined for display, e.g. `pyproject.toml, requirements.txt`.
+    pub fn via_label(&self) -> Option<String> {
+        self.via.as_ref()?;
+        Some(self.sources().collect::<Vec<_>>().join(", "))
+    }
+
+    /// The monorepo subdirectory the package lives in: [`directory`](Self::directory)
+    /// if set, otherwise the path `original_url` pointed into, e.g.
+    /// `packages/foo` for `https://github.com/org/monorepo/tree/main/packages/foo`.
+    /// The repository itself is always the root `org/monorepo`.
+    pub fn subdirectory(&self) -> Option<String> {
+        if let Some(directory) = &self.directory {
+            return Some(directory.trim_matches('/').to_string());
+        }
+        let original = self.original_url.as_deref()?;
+        let url = Url::parse(original.strip_prefix("git+").unwrap_or(original)).ok()?;
+        if !matches!(url.host_str(), Some("github.com" | "www.github.com")) {
+            return None;
+        }
+        let segments: Vec<&str> = url
+            .path_segments()?
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        match segments.as_slice() {
+            [_, _, "tree" | "blob", _, rest @ ..] if !rest.is_empty() => Some(rest.join("/")),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Repository {
+    fn eq(&self, other: &Self) -> bool {
+        self.owner == other.owner && self.name == other.name
+    }
+}
+
+impl Eq for Repository {}
+
+impl Hash for Repository {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.owner.hash(state);
+        self.name.hash(state);
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -61,6 +192,8 @@ pub enum Framework {
     Renv,
     #[cfg(feature = "ecosystem-haskell")]
     Haskell,
+    #[cfg(feature = "ecosystem-sbom")]
+    Sbom,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -101,6 +234,9 @@ pub enum DiscoveryError {
     #[cfg(feature = "ecosystem-haskell")]
     #[error(transparent)]
     Haskell(Box<HaskellDiscoveryError>),
+    #[cfg(feature = "ecosystem-sbom")]
+    #[error(transparent)]
+    Sbom(Box<SbomDiscoveryError>),
 }
 
 macro_rules! impl_from_discovery_error {
@@ -137,117 +273,869 @@ impl_from_discovery_error!(Maven, MavenDiscoveryError);
 impl_from_discovery_error!(Renv, RenvDiscoveryError);
 #[cfg(feature = "ecosystem-haskell")]
 impl_from_discovery_error!(Haskell, HaskellDiscoveryError);
+#[cfg(feature = "ecosystem-sbom")]
+impl_from_discovery_error!(Sbom, SbomDiscoveryError);
 
-pub trait Discoverer {
-    fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DiscoveryError>;
+/// Counts of the dependencies a discovery pass looked at. Shared between the
+/// discoverers of one run, so the counters are atomic.
+#[derive(Debug, Default)]
+pub struct DiscoveryStats {
+    examined: AtomicUsize,
+    unresolved: AtomicUsize,
+    unresolved_dependencies: Mutex<Vec<String>>,
+    warnings: Mutex<Vec<String>>,
 }
 
-pub fn detect_frameworks(project_root: &Path) -> Vec<Framework> {
-    let mut frameworks = Vec::new();
-    #[cfg(feature = "ecosystem-node")]
-    if project_root.join("package.json").exists() {
-        frameworks.push(Framework::Node);
+impl DiscoveryStats {
+    /// Record a dependency that was considered for starring.
+    pub fn record_examined(&self) {
+        self.examined.fetch_add(1, Ordering::Relaxed);
     }
-    #[cfg(feature = "ecosystem-deno")]
-    if ["deno.lock", "deno.json", "deno.jsonc", "jsr.json"]
-        .iter()
-        .any(|file| project_root.join(file).exists())
-    {
-        frameworks.push(Framework::Deno);
+
+    /// Record a considered dependency that did not lead to a GitHub repository.
+    pub fn record_unresolved(&self, dependency: impl fmt::Display) {
+        self.unresolved.fetch_add(1, Ordering::Relaxed);
+        self.unresolved_dependencies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(dependency.to_string());
     }
-    #[cfg(feature = "ecosystem-cargo")]
-    if project_root.join("Cargo.toml").exists() {
-        frameworks.push(Framework::Cargo);
+
+    pub fn examined(&self) -> usize {
+        self.examined.load(Ordering::Relaxed)
     }
-    #[cfg(feature = "ecosystem-go")]
-    if project_root.join("go.mod").exists() {
-        frameworks.push(Framework::Go);
+
+    pub fn unresolved(&self) -> usize {
+        self.unresolved.load(Ordering::Relaxed)
     }
-    #[cfg(feature = "ecosystem-dart")]
-    if project_root.join("pubspec.yaml").exists() {
-        frameworks.push(Framework::Dart);
+
+    /// Names of the unresolved dependencies, in the order they were recorded.
+    pub fn unresolved_dependencies(&self) -> Vec<String> {
+        self.unresolved_dependencies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
-    #[cfg(feature = "ecosystem-composer")]
-    if project_root.join("composer.lock").exists() || project_root.join("composer.json").exists() {
-        frameworks.push(Framework::Composer);
+
+    /// Record a problem that did not stop discovery but may have made it miss
+    /// dependencies.
+    pub fn record_warning(&self, message: impl Into<String>) {
+        self.warnings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(message.into());
     }
-    #[cfg(feature = "ecosystem-ruby")]
-    if project_root.join("Gemfile").exists() || project_root.join("Gemfile.lock").exists() {
-        frameworks.push(Framework::Ruby);
+
+    /// The recorded warnings, in the order they were recorded.
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
-    #[cfg(feature = "ecosystem-python")]
-    if project_root.join("pyproject.toml").exists()
-        || project_root.join("requirements.txt").exists()
-        || project_root.join("Pipfile").exists()
-        || project_root.join("Pipfile.lock").exists()
-        || project_root.join("uv.lock").exists()
-    {
-        frameworks.push(Framework::Python);
+}
+
+/// Which dependencies the built-in discoverers report.
+///
+/// Only ecosystems that read a lockfile can tell the two apart. With
+/// [`DependencyScope::Direct`], Python skips `Pipfile.lock`, `uv.lock` and
+/// `poetry.lock`; Composer reads `composer.json` instead of `composer.lock`;
+/// Dart skips the `transitive` packages of `pubspec.lock`; Go skips
+/// `// indirect` requirements and the modules `vendor/modules.txt` does not
+/// mark `## explicit`; Haskell skips `cabal.project.freeze`; Gradle skips
+/// `gradle.lockfile`; and Deno skips `deno.lock`. Ruby only reads the
+/// `DEPENDENCIES` of `Gemfile.lock`, and Node, Cargo, Maven, R, and SBOM
+/// documents report the same dependencies under both scopes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyScope {
+    /// Everything the lockfiles resolved, transitive dependencies included.
+    #[default]
+    All,
+    /// Only the dependencies the project declares itself.
+    Direct,
+}
+
+/// How the built-in discoverers read a project.
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// Whether to read lockfiles or only the declared dependencies.
+    pub scope: DependencyScope,
+    /// The `cargo` binary to run for `cargo metadata`, over
+    /// `THANKS_STARS_CARGO_BIN` and the one on `PATH`.
+    pub cargo_bin: Option<PathBuf>,
+    /// The user's own Maven `settings.xml`, read when the project brings no
+    /// settings of its own; `None` skips it. Defaults to
+    /// `~/.m2/settings.xml`.
+    pub maven_user_settings: Option<PathBuf>,
+    /// How package registries are reached.
+    pub http: HttpOptions,
+}
+
+// Without Maven there is no user settings file to default to.
+#[cfg_attr(not(feature = "ecosystem-maven"), allow(clippy::derivable_impls))]
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            scope: DependencyScope::default(),
+            cargo_bin: None,
+            #[cfg(feature = "ecosystem-maven")]
+            maven_user_settings: maven_user_settings_path(),
+            #[cfg(not(feature = "ecosystem-maven"))]
+            maven_user_settings: None,
+            http: HttpOptions::default(),
+        }
     }
-    #[cfg(feature = "ecosystem-gradle")]
-    if project_root.join("gradle.lockfile").exists()
-        || project_root.join("build.gradle").exists()
-        || project_root.join("build.gradle.kts").exists()
-    {
-        frameworks.push(Framework::Gradle);
+}
+
+pub trait Discoverer {
+    fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DiscoveryError>;
+
+    /// Discover repositories while counting dependencies in `stats`: each
+    /// dependency considered is recorded as examined, and one that leads to
+    /// no GitHub repository as unresolved. The built-in ecosystems' own
+    /// `discover_with_stats` methods follow the same contract. The default
+    /// counts every returned repository as an examined dependency.
+    fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DiscoveryError> {
+        let repositories = self.discover(project_root)?;
+        stats
+            .examined
+            .fetch_add(repositories.len(), Ordering::Relaxed);
+        Ok(repositories)
     }
-    #[cfg(feature = "ecosystem-maven")]
-    if project_root.join("pom.xml").exists() {
-        frameworks.push(Framework::Maven);
+}
+
+impl Framework {
+    /// Every framework compiled into this build, in detection order.
+    pub const ALL: &'static [Framework] = &[
+        #[cfg(feature = "ecosystem-node")]
+        Framework::Node,
+        #[cfg(feature = "ecosystem-deno")]
+        Framework::Deno,
+        #[cfg(feature = "ecosystem-cargo")]
+        Framework::Cargo,
+        #[cfg(feature = "ecosystem-go")]
+        Framework::Go,
+        #[cfg(feature = "ecosystem-dart")]
+        Framework::Dart,
+        #[cfg(feature = "ecosystem-composer")]
+        Framework::Composer,
+        #[cfg(feature = "ecosystem-ruby")]
+        Framework::Ruby,
+        #[cfg(feature = "ecosystem-python")]
+        Framework::Python,
+        #[cfg(feature = "ecosystem-gradle")]
+        Framework::Gradle,
+        #[cfg(feature = "ecosystem-maven")]
+        Framework::Maven,
+        #[cfg(feature = "ecosystem-renv")]
+        Framework::Renv,
+        #[cfg(feature = "ecosystem-haskell")]
+        Framework::Haskell,
+        #[cfg(feature = "ecosystem-sbom")]
+        Framework::Sbom,
+    ];
+
+    /// Lowercase name of the framework, as accepted by [`FromStr`].
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "ecosystem-node")]
+            Framework::Node => "node",
+            #[cfg(feature = "ecosystem-deno")]
+            Framework::Deno => "deno",
+            #[cfg(feature = "ecosystem-cargo")]
+            Framework::Cargo => "cargo",
+            #[cfg(feature = "ecosystem-go")]
+            Framework::Go => "go",
+            #[cfg(feature = "ecosystem-dart")]
+            Framework::Dart => "dart",
+            #[cfg(feature = "ecosystem-composer")]
+            Framework::Composer => "composer",
+            #[cfg(feature = "ecosystem-ruby")]
+            Framework::Ruby => "ruby",
+            #[cfg(feature = "ecosystem-python")]
+            Framework::Python => "python",
+            #[cfg(feature = "ecosystem-gradle")]
+            Framework::Gradle => "gradle",
+            #[cfg(feature = "ecosystem-maven")]
+            Framework::Maven => "maven",
+            #[cfg(feature = "ecosystem-renv")]
+            Framework::Renv => "renv",
+            #[cfg(feature = "ecosystem-haskell")]
+            Framework::Haskell => "haskell",
+            #[cfg(feature = "ecosystem-sbom")]
+            Framework::Sbom => "sbom",
+        }
     }
-    #[cfg(feature = "ecosystem-renv")]
-    if project_root.join("renv.lock").exists() {
-        frameworks.push(Framework::Renv);
+
+    /// Frameworks that read the same dependencies as this one, like Maven and
+    /// Gradle in a hybrid build with both `pom.xml` and `build.gradle`. See
+    /// [`apply_preference`].
+    pub fn overlaps(self) -> &'static [Framework] {
+        match self {
+            #[cfg(all(feature = "ecosystem-maven", feature = "ecosystem-gradle"))]
+            Framework::Maven => &[Framework::Gradle],
+            #[cfg(all(feature = "ecosystem-maven", feature = "ecosystem-gradle"))]
+            Framework::Gradle => &[Framework::Maven],
+            #[allow(unreachable_patterns)]
+            _ => &[],
+        }
     }
+
+    /// The repository the manifest in `project_root` declares for the project
+    /// itself, for the ecosystems whose manifests have such a field.
+    pub fn own_repository(
+        self,
+        #[cfg_attr(
+            not(any(feature = "ecosystem-node", feature = "ecosystem-cargo")),
+            allow(unused_variables)
+        )]
+        project_root: &Path,
+    ) -> Option<Repository> {
+        match self {
+            #[cfg(feature = "ecosystem-node")]
+            Framework::Node => crate::ecosystems::node::own_repository(project_root),
+            #[cfg(feature = "ecosystem-cargo")]
+            Framework::Cargo => crate::ecosystems::cargo::own_repository(project_root),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Whether `project_root` contains a manifest handled by this framework.
+    pub fn is_present(self, project_root: &Path) -> bool {
+        !self.manifests(project_root).is_empty()
+    }
+
+    /// The files in `project_root` that make this framework
+    /// [present](Self::is_present), such as both `pyproject.toml` and
+    /// `requirements.txt` for Python.
+    pub fn manifests(self, project_root: &Path) -> Vec<PathBuf> {
+        match self {
+            #[cfg(feature = "ecosystem-node")]
+            Framework::Node => existing_files(project_root, &["package.json"]).collect(),
+            #[cfg(feature = "ecosystem-deno")]
+            Framework::Deno => existing_files(
+                project_root,
+                &["deno.lock", "deno.json", "deno.jsonc", "jsr.json"],
+            )
+            .collect(),
+            #[cfg(feature = "ecosystem-cargo")]
+            Framework::Cargo => existing_files(project_root, &["Cargo.toml"]).collect(),
+            #[cfg(feature = "ecosystem-go")]
+            Framework::Go => existing_files(project_root, &["go.mod"]).collect(),
+            #[cfg(feature = "ecosystem-dart")]
+            Framework::Dart => existing_files(project_root, &["pubspec.yaml"]).collect(),
+            #[cfg(feature = "ecosystem-composer")]
+            Framework::Composer => {
+                existing_files(project_root, &["composer.lock", "composer.json"]).collect()
+            }
+            #[cfg(feature = "ecosystem-ruby")]
+            Framework::Ruby => existing_files(project_root, &["Gemfile", "Gemfile.lock"]).collect(),
+            #[cfg(feature = "ecosystem-python")]
+            Framework::Python => existing_files(
+                project_root,
+                &[
+                    "pyproject.toml",
+                    "requirements.txt",
+                    "setup.py",
+                    "setup.cfg",
+                    "Pipfile",
+                    "Pipfile.lock",
+                    "uv.lock",
+                    "poetry.lock",
+                    "environment.yml",
+                ],
+            )
+            .collect(),
+            #[cfg(feature = "ecosystem-gradle")]
+            Framework::Gradle => existing_files(
+                project_root,
+                &[
+                    "gradle.lockfile",
+                    "build.gradle",
+                    "build.gradle.kts",
+                    "gradle/libs.versions.toml",
+                ],
+            )
+            .collect(),
+            #[cfg(feature = "ecosystem-maven")]
+            Framework::Maven => existing_files(project_root, &["pom.xml"]).collect(),
+            #[cfg(feature = "ecosystem-renv")]
+            Framework::Renv => existing_files(project_root, &["renv.lock"]).collect(),
+            #[cfg(feature = "ecosystem-haskell")]
+            Framework::Haskell => existing_files(
+                project_root,
+                &[
+                    "package.yaml",
+                    "stack.yaml",
+                    "cabal.project",
+                    "cabal.project.freeze",
+                ],
+            )
+            .chain(cabal_files(project_root))
+            .collect(),
+            #[cfg(feature = "ecosystem-sbom")]
+            Framework::Sbom => crate::ecosystems::sbom::sbom_files(project_root),
+        }
+    }
+}
+
+/// The files named `names` that exist in `project_root`.
+#[cfg_attr(
+    not(any(
+        feature = "ecosystem-node",
+        feature = "ecosystem-deno",
+        feature = "ecosystem-cargo",
+        feature = "ecosystem-go",
+        feature = "ecosystem-dart",
+        feature = "ecosystem-composer",
+        feature = "ecosystem-ruby",
+        feature = "ecosystem-python",
+        feature = "ecosystem-gradle",
+        feature = "ecosystem-maven",
+        feature = "ecosystem-renv",
+        feature = "ecosystem-haskell"
+    )),
+    allow(dead_code)
+)]
+fn existing_files<'a>(
+    project_root: &'a Path,
+    names: &'a [&str],
+) -> impl Iterator<Item = PathBuf> + 'a {
+    names
+        .iter()
+        .map(|name| project_root.join(name))
+        .filter(|path| path.exists())
+}
+
+impl Discoverer for Framework {
+    fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DiscoveryError> {
+        discover_for_framework(
+            project_root,
+            *self,
+            &DiscoveryOptions::default(),
+            &SharedFetchers::new(&HttpOptions::default()),
+            stats,
+        )
+    }
+}
+
+/// A built-in framework's discoverer with non-default [`DiscoveryOptions`],
+/// sharing registry lookups with the other discoverers of its run.
+struct ConfiguredFramework {
+    framework: Framework,
+    options: DiscoveryOptions,
+    shared: SharedFetchers,
+}
+
+/// Registry lookups the built-in discoverers of one run share, so a package
+/// that two ecosystems declare is fetched once, and the HTTP client they
+/// make them with. Clones share the lookups.
+#[derive(Clone)]
+struct SharedFetchers {
+    /// Built from the run's [`HttpOptions`], for the registries that are not
+    /// cached here.
+    #[cfg_attr(
+        not(any(
+            feature = "ecosystem-cargo",
+            feature = "ecosystem-composer",
+            feature = "ecosystem-deno",
+            feature = "ecosystem-gradle",
+            feature = "ecosystem-maven",
+            feature = "ecosystem-renv"
+        )),
+        allow(dead_code)
+    )]
+    client: Client,
+    /// The run's [`HttpOptions::retries`], for the same registries.
+    #[cfg_attr(
+        not(any(
+            feature = "ecosystem-cargo",
+            feature = "ecosystem-gradle",
+            feature = "ecosystem-maven"
+        )),
+        allow(dead_code)
+    )]
+    retries: u32,
+    /// POMs from Maven Central, or from [`MAVEN_REPOSITORY_ENV`] if set, for
+    /// the Gradle discoverer and for Maven builds without other repositories.
+    ///
+    /// [`MAVEN_REPOSITORY_ENV`]: crate::ecosystems::MAVEN_REPOSITORY_ENV
+    #[cfg(feature = "ecosystem-maven")]
+    maven: CachedMavenClient,
+    #[cfg(feature = "ecosystem-dart")]
+    pub_dev: CachedPubDevClient,
     #[cfg(feature = "ecosystem-haskell")]
-    if project_root.join("package.yaml").exists()
-        || project_root.join("stack.yaml").exists()
-        || project_root.join("cabal.project").exists()
-        || has_cabal_file(project_root)
-    {
-        frameworks.push(Framework::Haskell);
+    hackage: CachedHackageClient,
+    /// Used when [`NPM_REGISTRY_FALLBACK_ENV`] is set.
+    ///
+    /// [`NPM_REGISTRY_FALLBACK_ENV`]: crate::ecosystems::NPM_REGISTRY_FALLBACK_ENV
+    #[cfg(feature = "ecosystem-node")]
+    npm: CachedNpmRegistryClient,
+    #[cfg(feature = "ecosystem-python")]
+    pypi: CachedPyPiClient,
+    #[cfg(feature = "ecosystem-ruby")]
+    rubygems: CachedRubyGemsClient,
+}
+
+impl SharedFetchers {
+    fn new(http: &HttpOptions) -> Self {
+        let client = http.client();
+        let retries = http.retries;
+        Self {
+            #[cfg(feature = "ecosystem-maven")]
+            maven: CachingFetcher::new(
+                HttpMavenClient::new()
+                    .with_client(client.clone())
+                    .with_retries(retries),
+            ),
+            #[cfg(feature = "ecosystem-dart")]
+            pub_dev: CachingFetcher::new(
+                HttpPubDevClient::new()
+                    .with_client(client.clone())
+                    .with_retries(retries),
+            ),
+            #[cfg(feature = "ecosystem-haskell")]
+            hackage: CachingFetcher::new(
+                HttpHackageClient::new()
+                    .with_client(client.clone())
+                    .with_retries(retries),
+            ),
+            #[cfg(feature = "ecosystem-node")]
+            npm: CachingFetcher::new(HttpNpmRegistryClient::new().with_client(client.clone())),
+            #[cfg(feature = "ecosystem-python")]
+            pypi: CachingFetcher::new(
+                HttpPyPiClient::new()
+                    .with_client(client.clone())
+                    .with_retries(retries),
+            ),
+            #[cfg(feature = "ecosystem-ruby")]
+            rubygems: CachingFetcher::new(
+                HttpRubyGemsClient::new()
+                    .with_client(client.clone())
+                    .with_retries(retries),
+            ),
+            client,
+            retries,
+        }
+    }
+}
+
+impl Discoverer for ConfiguredFramework {
+    fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DiscoveryError> {
+        discover_for_framework(
+            project_root,
+            self.framework,
+            &self.options,
+            &self.shared,
+            stats,
+        )
     }
-    frameworks
 }
 
+pub fn detect_frameworks(project_root: &Path) -> Vec<Framework> {
+    detect_frameworks_detailed(project_root)
+        .into_iter()
+        .map(|(framework, _)| framework)
+        .collect()
+}
+
+/// Like [`detect_frameworks`], but also returns the
+/// [manifests](Framework::manifests) that triggered each framework.
+pub fn detect_frameworks_detailed(project_root: &Path) -> Vec<(Framework, Vec<PathBuf>)> {
+    Framework::ALL
+        .iter()
+        .map(|&framework| (framework, framework.manifests(project_root)))
+        .filter(|(_, manifests)| !manifests.is_empty())
+        .collect()
+}
+
+/// Repositories the manifests in `project_root` declare for the project
+/// itself, so a project does not end up starring its own repository.
+pub fn own_repositories(project_root: &Path) -> Vec<Repository> {
+    Framework::ALL
+        .iter()
+        .filter_map(|framework| framework.own_repository(project_root))
+        .collect()
+}
+
+/// Drop every framework in `frameworks` that [overlaps](Framework::overlaps)
+/// with one listed in `prefer` and also present. Without a preference both
+/// are kept, and the repositories they share are merged when starring.
+pub fn apply_preference(frameworks: &mut Vec<Framework>, prefer: &[Framework]) {
+    let present = frameworks.clone();
+    frameworks.retain(|framework| {
+        !prefer.iter().any(|preferred| {
+            preferred != framework
+                && present.contains(preferred)
+                && preferred.overlaps().contains(framework)
+        })
+    });
+}
+
+/// The `*.cabal` files directly in `project_root`, sorted by name.
 #[cfg(feature = "ecosystem-haskell")]
-fn has_cabal_file(project_root: &Path) -> bool {
-    project_root
-        .read_dir()
-        .map(|entries| {
-            entries.filter_map(Result::ok).any(|entry| {
-                let path = entry.path();
-                path.is_file()
-                    && path
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .map(|ext| ext.eq_ignore_ascii_case("cabal"))
-                        .unwrap_or(false)
-            })
+fn cabal_files(project_root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = project_root.read_dir() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("cabal"))
         })
-        .unwrap_or(false)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Directory names that are never descended into during recursive detection
+/// because they hold vendored or generated trees.
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target", "vendor", "build", "dist"];
+
+/// A directory below the project root that contains supported manifests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectDirectory {
+    /// Path relative to the project root; empty for the root itself.
+    pub relative_path: PathBuf,
+    pub frameworks: Vec<Framework>,
+}
+
+/// Detect frameworks in `project_root` and its subdirectories, descending at
+/// most `max_depth` levels. Hidden directories, common vendored trees, and
+/// directory names listed in the root `.gitignore` are skipped.
+pub fn detect_frameworks_recursive(project_root: &Path, max_depth: usize) -> Vec<ProjectDirectory> {
+    let ignored = gitignored_directories(project_root);
+    let mut directories = Vec::new();
+    let mut queue = VecDeque::from([(PathBuf::new(), 0)]);
+
+    while let Some((relative_path, depth)) = queue.pop_front() {
+        let absolute = project_root.join(&relative_path);
+        let frameworks = detect_frameworks(&absolute);
+        if !frameworks.is_empty() {
+            directories.push(ProjectDirectory {
+                relative_path: relative_path.clone(),
+                frameworks,
+            });
+        }
+        if depth >= max_depth {
+            continue;
+        }
+
+        let Ok(entries) = absolute.read_dir() else {
+            continue;
+        };
+        let mut children: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().map(|ty| ty.is_dir()).unwrap_or(false))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| {
+                !name.starts_with('.')
+                    && !SKIPPED_DIRECTORIES.contains(&name.as_str())
+                    && !ignored.contains(name)
+            })
+            .collect();
+        children.sort();
+        for child in children {
+            queue.push_back((relative_path.join(child), depth + 1));
+        }
+    }
+
+    directories
+}
+
+fn gitignored_directories(project_root: &Path) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(project_root.join(".gitignore")) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .map(|line| line.trim_matches('/'))
+        .filter(|line| !line.contains(['/', '*', '?', '[']))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Discover repositories in every directory returned by
+/// [`detect_frameworks_recursive`]. Repositories found below the root have
+/// their `via` prefixed with the relative directory, e.g.
+/// `packages/web/package.json`.
+pub fn discover_recursive(
+    project_root: &Path,
+    directories: &[ProjectDirectory],
+) -> Result<Vec<Repository>, DiscoveryError> {
+    discover_recursive_with_stats(project_root, directories, &DiscoveryStats::default())
+}
+
+/// Like [`discover_recursive`], but counts dependencies in `stats`.
+pub fn discover_recursive_with_stats(
+    project_root: &Path,
+    directories: &[ProjectDirectory],
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    discover_recursive_with_options(
+        project_root,
+        directories,
+        &DiscoveryOptions::default(),
+        stats,
+    )
+}
+
+/// Like [`discover_recursive_with_stats`], reading the projects as `options`
+/// say.
+pub fn discover_recursive_with_options(
+    project_root: &Path,
+    directories: &[ProjectDirectory],
+    options: &DiscoveryOptions,
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    let shared = SharedFetchers::new(&options.http);
+    let mut repositories = Vec::new();
+    for directory in directories {
+        let found = discover_configured(
+            &project_root.join(&directory.relative_path),
+            &directory.frameworks,
+            options,
+            &shared,
+            stats,
+        )?;
+        if directory.relative_path.as_os_str().is_empty() {
+            repositories.extend(found);
+            continue;
+        }
+        let prefix = directory.relative_path.to_string_lossy().replace('\\', "/");
+        repositories.extend(found.into_iter().map(|mut repository| {
+            repository.via = repository.via.map(|via| format!("{prefix}/{via}"));
+            for via in &mut repository.also_via {
+                *via = format!("{prefix}/{via}");
+            }
+            repository
+        }));
+    }
+    Ok(repositories)
 }
 
 pub fn discover_for_frameworks(
     project_root: &Path,
     frameworks: &[Framework],
 ) -> Result<Vec<Repository>, DiscoveryError> {
-    match frameworks {
+    discover_for_frameworks_with_stats(project_root, frameworks, &DiscoveryStats::default())
+}
+
+/// Like [`discover_for_frameworks`], but counts dependencies in `stats`.
+pub fn discover_for_frameworks_with_stats(
+    project_root: &Path,
+    frameworks: &[Framework],
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    discover_for_frameworks_with_options(
+        project_root,
+        frameworks,
+        &DiscoveryOptions::default(),
+        stats,
+    )
+}
+
+/// Like [`discover_for_frameworks_with_stats`], reading the project as
+/// `options` say.
+pub fn discover_for_frameworks_with_options(
+    project_root: &Path,
+    frameworks: &[Framework],
+    options: &DiscoveryOptions,
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    discover_configured(
+        project_root,
+        frameworks,
+        options,
+        &SharedFetchers::new(&options.http),
+        stats,
+    )
+}
+
+fn discover_configured(
+    project_root: &Path,
+    frameworks: &[Framework],
+    options: &DiscoveryOptions,
+    shared: &SharedFetchers,
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    let configured: Vec<ConfiguredFramework> = frameworks
+        .iter()
+        .map(|&framework| ConfiguredFramework {
+            framework,
+            options: options.clone(),
+            shared: shared.clone(),
+        })
+        .collect();
+    let discoverers: Vec<&(dyn Discoverer + Sync)> = configured
+        .iter()
+        .map(|discoverer| discoverer as &(dyn Discoverer + Sync))
+        .collect();
+    discover_in_parallel(project_root, &discoverers, stats)
+}
+
+type DetectFn = dyn Fn(&Path) -> bool + Send + Sync;
+
+struct RegisteredDiscoverer {
+    detect: Box<DetectFn>,
+    discoverer: Box<dyn Discoverer + Send + Sync>,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("unknown ecosystem `{0}`")]
+pub struct UnknownFramework(pub String);
+
+impl FromStr for Framework {
+    type Err = UnknownFramework;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Framework::ALL
+            .iter()
+            .copied()
+            .find(|framework| framework.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| UnknownFramework(name.to_string()))
+    }
+}
+
+/// Set of [`Discoverer`]s keyed by a detection predicate.
+///
+/// [`DiscovererRegistry::default`] contains every built-in [`Framework`];
+/// library consumers can [`register`](Self::register) their own ecosystems on
+/// top of it, or start from [`DiscovererRegistry::empty`].
+pub struct DiscovererRegistry {
+    entries: Vec<RegisteredDiscoverer>,
+}
+
+impl Default for DiscovererRegistry {
+    fn default() -> Self {
+        Self::for_frameworks(Framework::ALL)
+    }
+}
+
+impl DiscovererRegistry {
+    /// A registry with just the built-in discoverers of `frameworks`.
+    pub fn for_frameworks(frameworks: &[Framework]) -> Self {
+        Self::for_frameworks_with_options(frameworks, &DiscoveryOptions::default())
+    }
+
+    /// Like [`for_frameworks`](Self::for_frameworks), with discoverers that
+    /// read the project as `options` say.
+    pub fn for_frameworks_with_options(
+        frameworks: &[Framework],
+        options: &DiscoveryOptions,
+    ) -> Self {
+        let shared = SharedFetchers::new(&options.http);
+        let mut registry = Self::empty();
+        for &framework in frameworks {
+            registry.register(
+                move |root: &Path| framework.is_present(root),
+                ConfiguredFramework {
+                    framework,
+                    options: options.clone(),
+                    shared: shared.clone(),
+                },
+            );
+        }
+        registry
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Run `discoverer` for every project root where `detect` returns `true`.
+    pub fn register<P, D>(&mut self, detect: P, discoverer: D) -> &mut Self
+    where
+        P: Fn(&Path) -> bool + Send + Sync + 'static,
+        D: Discoverer + Send + Sync + 'static,
+    {
+        self.entries.push(RegisteredDiscoverer {
+            detect: Box::new(detect),
+            discoverer: Box::new(discoverer),
+        });
+        self
+    }
+
+    /// Whether any registered discoverer applies to `project_root`.
+    pub fn detects(&self, project_root: &Path) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| (entry.detect)(project_root))
+    }
+
+    /// Run every discoverer whose predicate matches `project_root`, preserving
+    /// registration order in the result.
+    pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    /// Like [`discover`](Self::discover), but counts dependencies in `stats`.
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DiscoveryError> {
+        let discoverers: Vec<&(dyn Discoverer + Sync)> = self
+            .entries
+            .iter()
+            .filter(|entry| (entry.detect)(project_root))
+            .map(|entry| entry.discoverer.as_ref() as &(dyn Discoverer + Sync))
+            .collect();
+        discover_in_parallel(project_root, &discoverers, stats)
+    }
+}
+
+fn discover_in_parallel(
+    project_root: &Path,
+    discoverers: &[&(dyn Discoverer + Sync)],
+    stats: &DiscoveryStats,
+) -> Result<Vec<Repository>, DiscoveryError> {
+    match discoverers {
         [] => Ok(Vec::new()),
-        [framework] => discover_for_framework(project_root, *framework),
+        [discoverer] => discoverer.discover_with_stats(project_root, stats),
         _ => thread::scope(|scope| {
-            let mut handles = Vec::with_capacity(frameworks.len());
+            let mut handles = Vec::with_capacity(discoverers.len());
 
-            for (index, framework) in frameworks.iter().copied().enumerate() {
+            for (index, discoverer) in discoverers.iter().copied().enumerate() {
                 handles.push(scope.spawn(
                     move || -> Result<(usize, Vec<Repository>), DiscoveryError> {
-                        let repositories = discover_for_framework(project_root, framework)?;
+                        let repositories = discoverer.discover_with_stats(project_root, stats)?;
                         Ok((index, repositories))
                     },
                 ));
             }
 
-            let mut ordered: Vec<Option<Vec<Repository>>> = vec![None; frameworks.len()];
+            let mut ordered: Vec<Option<Vec<Repository>>> = vec![None; discoverers.len()];
             for handle in handles {
                 let (index, repos) = handle.join().expect("framework discovery task panicked")?;
                 ordered[index] = Some(repos);
@@ -266,74 +1154,230 @@ pub fn discover_for_frameworks(
 fn discover_for_framework(
     project_root: &Path,
     framework: Framework,
+    #[cfg_attr(
+        not(any(
+            feature = "ecosystem-deno",
+            feature = "ecosystem-cargo",
+            feature = "ecosystem-go",
+            feature = "ecosystem-dart",
+            feature = "ecosystem-composer",
+            feature = "ecosystem-python",
+            feature = "ecosystem-gradle",
+            feature = "ecosystem-maven",
+            feature = "ecosystem-haskell"
+        )),
+        allow(unused_variables)
+    )]
+    options: &DiscoveryOptions,
+    #[cfg_attr(
+        not(any(
+            feature = "ecosystem-cargo",
+            feature = "ecosystem-composer",
+            feature = "ecosystem-dart",
+            feature = "ecosystem-deno",
+            feature = "ecosystem-gradle",
+            feature = "ecosystem-haskell",
+            feature = "ecosystem-maven",
+            feature = "ecosystem-node",
+            feature = "ecosystem-python",
+            feature = "ecosystem-renv",
+            feature = "ecosystem-ruby"
+        )),
+        allow(unused_variables)
+    )]
+    shared: &SharedFetchers,
+    stats: &DiscoveryStats,
 ) -> Result<Vec<Repository>, DiscoveryError> {
-    let repositories = match framework {
+    let _span = tracing::debug_span!("discover", ?framework).entered();
+    let mut repositories: Vec<Repository> = match framework {
         #[cfg(feature = "ecosystem-node")]
         Framework::Node => {
-            let discoverer = NodeDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = NodeDiscoverer::from_env_with_registry(shared.npm.clone());
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-deno")]
         Framework::Deno => {
-            let discoverer = DenoDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = DenoDiscoverer::with_fetchers(
+                HttpJsrClient::new().with_client(shared.client.clone()),
+                HttpNpmRegistryClient::new().with_client(shared.client.clone()),
+                HttpDenoLandClient::new().with_client(shared.client.clone()),
+            )
+            .with_scope(options.scope);
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-cargo")]
         Framework::Cargo => {
-            let discoverer = CargoDiscoverer::new(CommandMetadataFetcher);
-            discoverer.discover(project_root)?
+            let fetcher = match &options.cargo_bin {
+                Some(program) => CommandMetadataFetcher::with_program(program),
+                None => CommandMetadataFetcher::from_env(),
+            };
+            let program = fetcher.program().to_path_buf();
+            let discoverer = CargoDiscoverer::new(fetcher);
+            match discoverer.discover_with_stats(project_root, stats) {
+                Ok(repositories) => repositories,
+                // Without a working `cargo`, fall back to reading Cargo.toml.
+                Err(
+                    err @ (CargoDiscoveryError::CommandIo { .. }
+                    | CargoDiscoveryError::CommandFailed { .. }),
+                ) => {
+                    tracing::debug!(error = %err, "cargo metadata unavailable; reading Cargo.toml");
+                    let reason = match &err {
+                        CargoDiscoveryError::CommandIo { source, .. }
+                            if source.kind() == std::io::ErrorKind::NotFound =>
+                        {
+                            if program == Path::new("cargo") {
+                                "`cargo` was not found on PATH".to_string()
+                            } else {
+                                format!("`{}` was not found", program.display())
+                            }
+                        }
+                        CargoDiscoveryError::CommandFailed { stderr, .. } => format!(
+                            "`{} metadata` failed ({})",
+                            program.display(),
+                            cargo_error_line(stderr)
+                        ),
+                        _ => format!("`{} metadata` could not be run", program.display()),
+                    };
+                    stats.record_warning(format!(
+                        "{reason}, so only the dependencies listed in {} were read",
+                        project_root.join("Cargo.toml").display()
+                    ));
+                    match FileBackedFetcher::from_env("crates") {
+                        Some(fixtures) => CargoManifestDiscoverer::with_fetcher(fixtures)
+                            .discover_with_stats(project_root, stats)?,
+                        None => CargoManifestDiscoverer::with_fetcher(
+                            HttpCratesIoClient::new()
+                                .with_client(shared.client.clone())
+                                .with_retries(shared.retries),
+                        )
+                        .discover_with_stats(project_root, stats)?,
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
         #[cfg(feature = "ecosystem-go")]
         Framework::Go => {
-            let discoverer = GoDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = GoDiscoverer::new().with_scope(options.scope);
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-dart")]
-        Framework::Dart => {
-            let discoverer = DartDiscoverer::new();
-            discoverer.discover(project_root)?
-        }
+        Framework::Dart => match FileBackedFetcher::from_env("pub") {
+            Some(fixtures) => DartDiscoverer::with_fetcher(fixtures)
+                .with_scope(options.scope)
+                .discover_with_stats(project_root, stats)?,
+            None => DartDiscoverer::with_fetcher(shared.pub_dev.clone())
+                .with_scope(options.scope)
+                .discover_with_stats(project_root, stats)?,
+        },
         #[cfg(feature = "ecosystem-composer")]
         Framework::Composer => {
-            let discoverer = ComposerDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = ComposerDiscoverer::with_fetcher(
+                HttpPackagistClient::new().with_client(shared.client.clone()),
+            )
+            .with_scope(options.scope);
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-ruby")]
-        Framework::Ruby => {
-            let discoverer = RubyDiscoverer::new();
-            discoverer.discover(project_root)?
-        }
+        Framework::Ruby => match FileBackedFetcher::from_env("rubygems") {
+            Some(fixtures) => {
+                RubyDiscoverer::with_fetcher(fixtures).discover_with_stats(project_root, stats)?
+            }
+            None => RubyDiscoverer::with_fetcher(shared.rubygems.clone())
+                .discover_with_stats(project_root, stats)?,
+        },
         #[cfg(feature = "ecosystem-python")]
-        Framework::Python => {
-            let discoverer = PythonDiscoverer::new();
-            discoverer.discover(project_root)?
-        }
+        Framework::Python => match FileBackedFetcher::from_env("pypi") {
+            Some(fixtures) => PythonDiscoverer::with_fetcher(fixtures)
+                .with_scope(options.scope)
+                .discover_with_stats(project_root, stats)?,
+            None => PythonDiscoverer::with_fetcher(shared.pypi.clone())
+                .with_scope(options.scope)
+                .discover_with_stats(project_root, stats)?,
+        },
         #[cfg(feature = "ecosystem-gradle")]
         Framework::Gradle => {
-            let discoverer = GradleDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = GradleDiscoverer::with_fetcher(shared.maven.clone())
+                .with_plugin_fetcher(CachingFetcher::new(
+                    HttpMavenClient::gradle_plugin_portal()
+                        .with_client(shared.client.clone())
+                        .with_retries(shared.retries),
+                ))
+                .with_scope(options.scope);
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-maven")]
         Framework::Maven => {
-            let discoverer = MavenDiscoverer::new();
-            discoverer.discover(project_root)?
+            let repositories = configured_maven_repositories(
+                project_root,
+                options.maven_user_settings.as_deref(),
+                stats,
+            );
+            if repositories.is_empty() || repositories == [shared.maven.inner().base_url()] {
+                MavenDiscoverer::with_fetcher(shared.maven.clone())
+                    .discover_with_stats(project_root, stats)?
+            } else {
+                let chain = repositories
+                    .into_iter()
+                    .map(|url| {
+                        HttpMavenClient::with_base_url(url)
+                            .with_client(shared.client.clone())
+                            .with_retries(shared.retries)
+                    })
+                    .collect();
+                MavenDiscoverer::with_fetcher(MavenRepositoryChain::new(chain))
+                    .discover_with_stats(project_root, stats)?
+            }
         }
         #[cfg(feature = "ecosystem-renv")]
         Framework::Renv => {
-            let discoverer = RenvDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer = RenvDiscoverer::with_fetcher(
+                HttpRUniverseClient::new().with_client(shared.client.clone()),
+            );
+            discoverer.discover_with_stats(project_root, stats)?
         }
         #[cfg(feature = "ecosystem-haskell")]
         Framework::Haskell => {
-            let discoverer = HaskellDiscoverer::new();
-            discoverer.discover(project_root)?
+            let discoverer =
+                HaskellDiscoverer::with_fetcher(shared.hackage.clone()).with_scope(options.scope);
+            discoverer.discover_with_stats(project_root, stats)?
+        }
+        #[cfg(feature = "ecosystem-sbom")]
+        Framework::Sbom => {
+            let discoverer = SbomDiscoverer::new();
+            discoverer.discover_with_stats(project_root, stats)?
         }
     };
 
+    for repository in &mut repositories {
+        repository.framework.get_or_insert(framework);
+    }
+    tracing::debug!(count = repositories.len(), "discovery finished");
     Ok(repositories)
 }
 
+/// The line of `cargo metadata`'s stderr worth showing in a warning: the
+/// first `error` line, or else the last line.
+#[cfg(feature = "ecosystem-cargo")]
+fn cargo_error_line(stderr: &str) -> &str {
+    let mut lines = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    lines
+        .clone()
+        .find(|line| line.starts_with("error"))
+        .or_else(|| lines.next_back())
+        .unwrap_or("no output")
+}
+
 pub fn parse_github_repository(input: &str) -> Option<Repository> {
+    let mut repository = parse_repository_reference(input)?;
+    repository.original_url = Some(input.trim().to_string());
+    Some(repository)
+}
+
+fn parse_repository_reference(input: &str) -> Option<Repository> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
         return None;
@@ -345,11 +1389,18 @@ pub fn parse_github_repository(input: &str) -> Option<Repository> {
 
     let without_git = trimmed.strip_prefix("git+").unwrap_or(trimmed);
 
+    if let Some((host, path)) = split_scp_url(without_git) {
+        if !host.eq_ignore_ascii_case("github.com") {
+            return None;
+        }
+        return parse_owner_repo(strip_query_and_fragment(path));
+    }
+
     if let Ok(url) = Url::parse(without_git) {
         if url.scheme() == "file" {
             return None;
         }
-        if matches!(url.host_str(), Some("github.com")) {
+        if matches!(url.host_str(), Some("github.com" | "www.github.com")) {
             let segments = url
                 .path_segments()
                 .map(|segments| segments.filter(|segment| !segment.is_empty()));
@@ -359,15 +1410,65 @@ pub fn parse_github_repository(input: &str) -> Option<Repository> {
                 return build_repository(owner, repo);
             }
         }
-    } else if let Some(repo) = parse_owner_repo(without_git) {
+        if let Some(owner) = url
+            .host_str()
+            .and_then(|host| host.strip_suffix(".github.io"))
+        {
+            return parse_pages_repository(owner, &url);
+        }
+    } else if let Some(repo) = parse_owner_repo(strip_query_and_fragment(without_git)) {
         return Some(repo);
     }
 
-    if let Some(rest) = trimmed.strip_prefix("git@github.com:") {
-        return parse_owner_repo(rest);
+    None
+}
+
+/// Split an scp-style SSH address (`git@github.com:owner/repo`) into its host
+/// and the path after the colon, without any leading slash. npm also writes
+/// these behind an `ssh://` scheme; a port after the colon makes the address
+/// a real URL instead.
+fn split_scp_url(input: &str) -> Option<(&str, &str)> {
+    let input = match input.strip_prefix("ssh://") {
+        Some(rest)
+            if rest
+                .split_once(':')
+                .is_some_and(|(_, path)| !path.starts_with(|c: char| c.is_ascii_digit())) =>
+        {
+            rest
+        }
+        _ => input,
+    };
+    if input.contains("://") {
+        return None;
+    }
+    let (user_host, path) = input.split_once(':')?;
+    if user_host.contains('/') {
+        return None;
     }
+    let (_, host) = user_host.rsplit_once('@')?;
+    Some((host, path.trim_start_matches('/')))
+}
 
-    None
+/// Map a GitHub Pages URL (`https://<owner>.github.io/<repo>/`) to the
+/// repository that backs it. A bare `<owner>.github.io` is served from the
+/// `<owner>/<owner>.github.io` repository.
+fn parse_pages_repository(owner: &str, url: &Url) -> Option<Repository> {
+    if owner.is_empty() || owner.contains('.') {
+        return None;
+    }
+    let first_segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.find(|segment| !segment.is_empty()));
+    match first_segment {
+        Some(repo) => build_repository(owner, repo),
+        None => build_repository(owner, &format!("{owner}.github.io")),
+    }
+}
+
+/// Drop any `?query` or `#fragment` suffix that package metadata sometimes
+/// appends to repository links (e.g. `owner/repo#readme`).
+fn strip_query_and_fragment(input: &str) -> &str {
+    input.split_once(['?', '#']).map_or(input, |(head, _)| head)
 }
 
 fn parse_owner_repo(input: &str) -> Option<Repository> {
@@ -383,9 +1484,34 @@ fn parse_owner_repo(input: &str) -> Option<Repository> {
     build_repository(owner, repo)
 }
 
+/// First path segments on github.com that belong to GitHub itself rather than
+/// to a user or organization.
+const RESERVED_OWNERS: &[&str] = &[
+    "sponsors",
+    "orgs",
+    "features",
+    "marketplace",
+    "settings",
+    "apps",
+    "topics",
+    "collections",
+    "about",
+    "login",
+    "join",
+];
+
+/// Build the repository for `owner/repo`, or `None` if either part could not
+/// be a GitHub name. Trailing dots, typically sentence punctuation after a URL
+/// in free-form metadata, are dropped from the name.
 fn build_repository(owner: &str, repo: &str) -> Option<Repository> {
-    let repo = repo.trim_end_matches(".git");
-    if repo.is_empty() || owner.is_empty() {
+    let repo = repo.trim_end_matches('.').trim_end_matches(".git");
+    if !is_valid_owner(owner) || !is_valid_name(repo) {
+        return None;
+    }
+    if RESERVED_OWNERS
+        .iter()
+        .any(|reserved| owner.eq_ignore_ascii_case(reserved))
+    {
         return None;
     }
     Some(Repository {
@@ -393,13 +1519,71 @@ fn build_repository(owner: &str, repo: &str) -> Option<Repository> {
         name: repo.to_string(),
         url: format!("https://github.com/{owner}/{repo}"),
         via: None,
+        also_via: Vec::new(),
+        original_url: None,
+        directory: None,
+        stargazer_count: None,
+        framework: None,
     })
 }
 
+fn is_valid_owner(owner: &str) -> bool {
+    !owner.is_empty() && owner.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn rejects_names_with_characters_github_disallows() {
+        assert!(build_repository("owner", "my repo").is_none());
+        assert!(build_repository("owner", "repo%20name").is_none());
+        assert!(build_repository("@scope", "repo").is_none());
+        assert!(build_repository("own_er", "repo").is_none());
+    }
+
+    #[test]
+    fn drops_trailing_dots_from_names() {
+        let repo = build_repository("owner", "repo.").unwrap();
+        assert_eq!(repo.name, "repo");
+        assert_eq!(repo.url, "https://github.com/owner/repo");
+        assert!(build_repository("owner", "..").is_none());
+    }
+
+    #[test]
+    fn preference_only_drops_overlapping_frameworks() {
+        let mut frameworks = vec![Framework::Node, Framework::Gradle, Framework::Maven];
+        apply_preference(&mut frameworks, &[]);
+        assert_eq!(
+            frameworks,
+            vec![Framework::Node, Framework::Gradle, Framework::Maven]
+        );
+
+        apply_preference(&mut frameworks, &[Framework::Gradle, Framework::Python]);
+        assert_eq!(frameworks, vec![Framework::Node, Framework::Gradle]);
+
+        let mut frameworks = vec![Framework::Maven];
+        apply_preference(&mut frameworks, &[Framework::Gradle]);
+        assert_eq!(frameworks, vec![Framework::Maven]);
+    }
+
+    #[test]
+    fn accepts_valid_owner_and_name() {
+        let repo = build_repository("some-owner", "my_repo.rs-2").unwrap();
+        assert_eq!(repo.owner, "some-owner");
+        assert_eq!(repo.name, "my_repo.rs-2");
+    }
+
     #[test]
     fn parses_https_url() {
         let repo = parse_github_repository("https://github.com/owner/repo").unwrap();
@@ -420,8 +1604,321 @@ mod tests {
         assert_eq!(repo.url, "https://github.com/owner/repo");
     }
 
+    #[test]
+    fn parses_www_host() {
+        let repo = parse_github_repository("https://www.github.com/owner/repo").unwrap();
+        assert_eq!(repo.owner, "owner");
+        assert_eq!(repo.name, "repo");
+        assert_eq!(repo.url, "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn ignores_deep_paths() {
+        let tree = parse_github_repository("https://github.com/owner/repo/tree/main").unwrap();
+        assert_eq!(tree.url, "https://github.com/owner/repo");
+
+        let blob =
+            parse_github_repository("https://github.com/owner/repo/blob/main/README.md").unwrap();
+        assert_eq!(blob.url, "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn monorepo_urls_resolve_to_the_root_repository() {
+        let tree =
+            parse_github_repository("https://github.com/org/monorepo/tree/main/packages/foo")
+                .unwrap();
+        assert_eq!(tree.url, "https://github.com/org/monorepo");
+        assert_eq!(tree.subdirectory().as_deref(), Some("packages/foo"));
+
+        let blob = parse_github_repository(
+            "git+https://github.com/org/monorepo/blob/v1.2.0/packages/bar/package.json",
+        )
+        .unwrap();
+        assert_eq!(blob.url, "https://github.com/org/monorepo");
+        assert_eq!(
+            blob.subdirectory().as_deref(),
+            Some("packages/bar/package.json")
+        );
+
+        let root = parse_github_repository("https://github.com/org/monorepo/tree/main").unwrap();
+        assert_eq!(root.subdirectory(), None);
+        let plain = parse_github_repository("https://github.com/org/monorepo").unwrap();
+        assert_eq!(plain.subdirectory(), None);
+    }
+
+    #[test]
+    fn strips_query_and_fragment() {
+        let readme = parse_github_repository("https://github.com/owner/repo#readme").unwrap();
+        assert_eq!(readme.name, "repo");
+
+        let query =
+            parse_github_repository("https://github.com/owner/repo.git?tab=readme").unwrap();
+        assert_eq!(query.name, "repo");
+
+        let shorthand = parse_github_repository("owner/repo#readme").unwrap();
+        assert_eq!(shorthand.url, "https://github.com/owner/repo");
+
+        let ssh = parse_github_repository("git@github.com:owner/repo.git#main").unwrap();
+        assert_eq!(ssh.name, "repo");
+    }
+
+    #[test]
+    fn parses_ssh_urls() {
+        for input in [
+            "git@github.com:owner/repo",
+            "git@github.com:owner/repo.git",
+            "git@github.com:/owner/repo.git",
+            "ssh://git@github.com/owner/repo",
+            "ssh://git@github.com/owner/repo.git",
+            "ssh://git@github.com:22/owner/repo.git",
+            "git+ssh://git@github.com/owner/repo.git",
+            "git+ssh://github.com/owner/repo.git",
+            "git+ssh://git@github.com:owner/repo.git",
+            "git+ssh://git@github.com:22/owner/repo.git",
+            "git://github.com/owner/repo",
+            "git://github.com/owner/repo.git",
+            "git+git://github.com/owner/repo.git",
+        ] {
+            let repo = parse_github_repository(input).unwrap();
+            assert_eq!(repo.owner, "owner", "{input}");
+            assert_eq!(repo.name, "repo", "{input}");
+            assert_eq!(repo.url, "https://github.com/owner/repo", "{input}");
+            assert_eq!(repo.original_url.as_deref(), Some(input));
+        }
+    }
+
+    #[test]
+    fn rejects_ssh_urls_for_other_hosts() {
+        assert!(parse_github_repository("git@gitlab.com:owner/repo").is_none());
+        assert!(parse_github_repository("git@gitlab.com:owner/repo.git").is_none());
+        assert!(parse_github_repository("ssh://git@gitlab.com/owner/repo.git").is_none());
+        assert!(parse_github_repository("git+ssh://git@gitlab.com:owner/repo.git").is_none());
+        assert!(parse_github_repository("git://gitlab.com/owner/repo.git").is_none());
+    }
+
+    #[test]
+    fn rejects_reserved_path_prefixes() {
+        for url in [
+            "https://github.com/sponsors/owner",
+            "https://github.com/orgs/acme",
+            "https://github.com/marketplace/actions/checkout",
+            "https://github.com/topics/rust",
+            "https://github.com/Settings/profile",
+        ] {
+            assert!(parse_github_repository(url).is_none(), "{url}");
+        }
+        assert!(parse_github_repository("https://github.com/features").is_none());
+
+        let repo = parse_github_repository("https://github.com/owner/repo").unwrap();
+        assert_eq!(repo.url, "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn maps_pages_url_to_repository() {
+        let repo = parse_github_repository("https://owner.github.io/repo/").unwrap();
+        assert_eq!(repo.owner, "owner");
+        assert_eq!(repo.name, "repo");
+        assert_eq!(repo.url, "https://github.com/owner/repo");
+    }
+
+    #[test]
+    fn maps_bare_pages_host_to_user_site_repository() {
+        let repo = parse_github_repository("https://owner.github.io").unwrap();
+        assert_eq!(repo.owner, "owner");
+        assert_eq!(repo.name, "owner.github.io");
+    }
+
+    #[test]
+    fn keeps_original_url_alongside_canonical_url() {
+        let repo = parse_github_repository(" git+https://github.com/owner/repo.git ").unwrap();
+        assert_eq!(repo.url, "https://github.com/owner/repo");
+        assert_eq!(
+            repo.original_url.as_deref(),
+            Some("git+https://github.com/owner/repo.git")
+        );
+    }
+
+    #[test]
+    fn equality_ignores_url_spelling_via_and_framework() {
+        let mut git = parse_github_repository("git+https://github.com/owner/repo.git").unwrap();
+        git.via = Some("Cargo.toml".to_string());
+        git.framework = Some(Framework::Cargo);
+        let https = parse_github_repository("https://www.github.com/owner/repo").unwrap();
+        assert_eq!(git, https);
+    }
+
     #[test]
     fn returns_none_for_non_github_url() {
         assert!(parse_github_repository("https://example.com/owner/repo").is_none());
     }
+
+    #[test]
+    fn detailed_detection_lists_the_triggering_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in [
+            "package.json",
+            "pyproject.toml",
+            "requirements.txt",
+            "README.md",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let detected = detect_frameworks_detailed(root);
+
+        assert_eq!(
+            detected,
+            [
+                (Framework::Node, vec![root.join("package.json")]),
+                (
+                    Framework::Python,
+                    vec![root.join("pyproject.toml"), root.join("requirements.txt")]
+                ),
+            ]
+        );
+        assert_eq!(
+            detect_frameworks(root),
+            [Framework::Node, Framework::Python]
+        );
+    }
+
+    #[test]
+    fn detects_nested_manifests_and_prefixes_via() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join(".gitignore"),
+            "# build output\n/generated/\n*.log\n",
+        )
+        .unwrap();
+        for member in [
+            "packages/web",
+            "generated/app",
+            "node_modules/dep",
+            ".cache/pkg",
+        ] {
+            fs::create_dir_all(root.join(member)).unwrap();
+            fs::write(root.join(member).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("packages/web/node_modules/dep")).unwrap();
+        fs::write(
+            root.join("packages/web/package.json"),
+            r#"{ "dependencies": { "dep": "^1.0.0" } }"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("packages/web/node_modules/dep/package.json"),
+            r#"{ "repository": "https://github.com/example/dep" }"#,
+        )
+        .unwrap();
+
+        let directories = detect_frameworks_recursive(root, 3);
+        assert_eq!(
+            directories,
+            vec![ProjectDirectory {
+                relative_path: PathBuf::from("packages/web"),
+                frameworks: vec![Framework::Node],
+            }]
+        );
+        assert!(detect_frameworks_recursive(root, 1).is_empty());
+
+        let repos = discover_recursive(root, &directories).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].via.as_deref(), Some("packages/web/package.json"));
+        assert_eq!(repos[0].framework, Some(Framework::Node));
+    }
+
+    #[test]
+    fn registry_runs_custom_discoverer_when_detected() {
+        struct FakeDiscoverer;
+
+        impl Discoverer for FakeDiscoverer {
+            fn discover(&self, _project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+                let mut repository = parse_github_repository("custom/ecosystem").unwrap();
+                repository.via = Some("custom.lock".to_string());
+                Ok(vec![repository])
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = DiscovererRegistry::empty();
+        registry.register(
+            |root: &Path| root.join("custom.lock").exists(),
+            FakeDiscoverer,
+        );
+
+        assert!(!registry.detects(dir.path()));
+        assert!(registry.discover(dir.path()).unwrap().is_empty());
+
+        fs::write(dir.path().join("custom.lock"), "").unwrap();
+        assert!(registry.detects(dir.path()));
+        let repos = registry.discover(dir.path()).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/custom/ecosystem");
+        assert_eq!(repos[0].via.as_deref(), Some("custom.lock"));
+        assert_eq!(repos[0].framework, None);
+    }
+
+    #[cfg(all(feature = "ecosystem-maven", feature = "ecosystem-gradle"))]
+    #[test]
+    fn maven_and_gradle_share_pom_lookups_within_a_run() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        let pom = server.mock(|when, then| {
+            when.method(GET)
+                .path("/com/example/library/1.2.3/library-1.2.3.pom");
+            then.status(200)
+                .body("<project><url>https://github.com/example/library</url></project>");
+        });
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("pom.xml"),
+            "<project><dependencies><dependency>\
+             <groupId>com.example</groupId><artifactId>library</artifactId>\
+             <version>1.2.3</version></dependency></dependencies></project>",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("gradle.lockfile"),
+            "com.example:library:1.2.3=runtimeClasspath\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("settings.xml"),
+            format!(
+                "<settings><mirrors><mirror><mirrorOf>*</mirrorOf><url>{}</url></mirror></mirrors></settings>",
+                server.base_url()
+            ),
+        )
+        .unwrap();
+
+        let shared = SharedFetchers {
+            maven: CachingFetcher::new(HttpMavenClient::with_base_url(server.base_url())),
+            ..SharedFetchers::new(&HttpOptions::default())
+        };
+        let options = DiscoveryOptions {
+            maven_user_settings: None,
+            ..DiscoveryOptions::default()
+        };
+        let stats = DiscoveryStats::default();
+        let gradle =
+            discover_for_framework(dir.path(), Framework::Gradle, &options, &shared, &stats)
+                .unwrap();
+        let maven = discover_for_framework(dir.path(), Framework::Maven, &options, &shared, &stats)
+            .unwrap();
+
+        assert_eq!(gradle.len(), 1);
+        assert_eq!(maven.len(), 1);
+        assert_eq!(gradle[0].url, maven[0].url);
+        pom.assert_calls(1);
+
+        let fresh = SharedFetchers {
+            maven: CachingFetcher::new(HttpMavenClient::with_base_url(server.base_url())),
+            ..SharedFetchers::new(&HttpOptions::default())
+        };
+        discover_for_framework(dir.path(), Framework::Maven, &options, &fresh, &stats).unwrap();
+        pom.assert_calls(2);
+    }
 }
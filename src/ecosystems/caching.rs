@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Wraps a registry fetcher and remembers successful lookups by package name,
+/// so asking for the same package twice only hits the network once. Clones
+/// share the cache; failed lookups are not cached.
+pub struct CachingFetcher<F, V> {
+    inner: F,
+    cache: Arc<Mutex<HashMap<String, V>>>,
+}
+
+impl<F: Clone, V> Clone for CachingFetcher<F, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            cache: Arc::clone(&self.cache),
+        }
+    }
+}
+
+impl<F, V: Clone> CachingFetcher<F, V> {
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    /// Return the cached value for `key`, or run `fetch` against the wrapped
+    /// fetcher and remember its result. The lock is not held while fetching.
+    pub fn get_or_fetch<E>(
+        &self,
+        key: &str,
+        fetch: impl FnOnce(&F, &str) -> Result<V, E>,
+    ) -> Result<V, E> {
+        if let Some(value) = self.lock().get(key) {
+            return Ok(value.clone());
+        }
+        let value = fetch(&self.inner, key)?;
+        self.lock().insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, V>> {
+        // A panicking fetch cannot leave the map half-updated, so a poisoned
+        // lock is still safe to use.
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn fetches_each_key_once() {
+        let calls = Cell::new(0);
+        let fetcher: CachingFetcher<(), Option<String>> = CachingFetcher::new(());
+        let fetch = |_: &(), key: &str| -> Result<Option<String>, ()> {
+            calls.set(calls.get() + 1);
+            Ok(Some(key.to_uppercase()))
+        };
+
+        assert_eq!(
+            fetcher.get_or_fetch("serde", fetch),
+            Ok(Some("SERDE".into()))
+        );
+        assert_eq!(
+            fetcher.get_or_fetch("serde", fetch),
+            Ok(Some("SERDE".into()))
+        );
+        assert_eq!(
+            fetcher.clone().get_or_fetch("serde", fetch),
+            Ok(Some("SERDE".into()))
+        );
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn does_not_cache_errors() {
+        let calls = Cell::new(0);
+        let fetcher: CachingFetcher<(), Option<String>> = CachingFetcher::new(());
+        let failing = |_: &(), _: &str| -> Result<Option<String>, &str> {
+            calls.set(calls.get() + 1);
+            Err("offline")
+        };
+
+        assert!(fetcher.get_or_fetch("rand", failing).is_err());
+        assert!(fetcher.get_or_fetch("rand", failing).is_err());
+        assert_eq!(calls.get(), 2);
+    }
+}
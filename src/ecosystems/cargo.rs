@@ -1,38 +1,132 @@
-use std::collections::{BTreeSet, HashMap};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use reqwest::StatusCode;
 use serde::Deserialize;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DiscoveryStats, Repository};
+use crate::ecosystems::fixtures::{FileBackedFetcher, FixtureError};
+use crate::ecosystems::text::read_manifest;
+use crate::http;
+
+const CARGO_MANIFEST: &str = "Cargo.toml";
+/// Path of the `cargo` binary to run instead of the one on `PATH`, for
+/// toolchains pinned with asdf or mise.
+pub const CARGO_BIN_ENV: &str = "THANKS_STARS_CARGO_BIN";
+/// Manifest tables whose entries are dependencies of the project.
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
 
 #[derive(Debug, thiserror::Error)]
 pub enum CargoDiscoveryError {
-    #[error("failed to run `cargo metadata`: {0}")]
-    CommandFailed(String),
-    #[error("failed to execute `cargo metadata`: {0}")]
-    CommandIo(#[from] std::io::Error),
+    #[error("failed to run `{} metadata`: {stderr}", program.display())]
+    CommandFailed { program: PathBuf, stderr: String },
+    #[error("failed to execute `{} metadata`: {source}", program.display())]
+    CommandIo {
+        program: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
     #[error("failed to parse cargo metadata: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Toml {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("failed to fetch metadata for crate {name}: {source}")]
+    CratesIo {
+        name: String,
+        #[source]
+        source: CratesIoError,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CratesIoError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Fixture(#[from] FixtureError),
 }
 
 pub trait MetadataFetcher {
     fn fetch(&self, project_root: &Path) -> Result<String, CargoDiscoveryError>;
 }
 
-#[derive(Default)]
-pub struct CommandMetadataFetcher;
+/// Runs `cargo metadata` in the project root.
+pub struct CommandMetadataFetcher {
+    program: PathBuf,
+}
+
+impl Default for CommandMetadataFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandMetadataFetcher {
+    /// Run the `cargo` found on `PATH`.
+    pub fn new() -> Self {
+        Self::with_program("cargo")
+    }
+
+    /// Run `program` instead of the `cargo` on `PATH`.
+    pub fn with_program(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    /// Run the program named by [`CARGO_BIN_ENV`], falling back to
+    /// [`new`](Self::new).
+    pub fn from_env() -> Self {
+        match std::env::var_os(CARGO_BIN_ENV) {
+            Some(program) if !program.is_empty() => Self::with_program(program),
+            _ => Self::new(),
+        }
+    }
+
+    /// The `cargo` binary this fetcher runs.
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+}
 
 impl MetadataFetcher for CommandMetadataFetcher {
     fn fetch(&self, project_root: &Path) -> Result<String, CargoDiscoveryError> {
-        let output = Command::new("cargo")
+        let output = Command::new(&self.program)
             .current_dir(project_root)
             .args(["metadata", "--format-version", "1"])
-            .output()?;
+            .output()
+            .map_err(|source| CargoDiscoveryError::CommandIo {
+                program: self.program.clone(),
+                source,
+            })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-            return Err(CargoDiscoveryError::CommandFailed(stderr));
+            return Err(CargoDiscoveryError::CommandFailed {
+                program: self.program.clone(),
+                stderr,
+            });
         }
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
@@ -49,6 +143,14 @@ impl<F: MetadataFetcher> CargoDiscoverer<F> {
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, CargoDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, CargoDiscoveryError> {
         let metadata_json = self.fetcher.fetch(project_root)?;
         let metadata: Metadata = serde_json::from_str(&metadata_json)?;
         let Metadata {
@@ -81,12 +183,27 @@ impl<F: MetadataFetcher> CargoDiscoverer<F> {
 
         let mut repositories = Vec::new();
         for id in dependency_ids {
-            if let Some(package) = package_map.get(&id) {
-                if let Some(repo) = &package.repository {
-                    if let Some(mut repository) = parse_github_repository(repo) {
-                        repository.via = Some("Cargo.toml".to_string());
-                        repositories.push(repository);
-                    }
+            trace!(dependency = %id, "considering dependency");
+            stats.record_examined();
+            let Some(package) = package_map.get(&id) else {
+                stats.record_unresolved(&id);
+                debug!(dependency = %id, "skipping: missing from cargo metadata packages");
+                continue;
+            };
+            let Some(repo) = &package.repository else {
+                stats.record_unresolved(&id);
+                debug!(dependency = %id, "skipping: no repository in Cargo.toml");
+                continue;
+            };
+            match parse_github_repository(repo) {
+                Some(mut repository) => {
+                    debug!(dependency = %id, url = %repository.url, "resolved");
+                    repository.via = Some("Cargo.toml".to_string());
+                    repositories.push(repository);
+                }
+                None => {
+                    stats.record_unresolved(&id);
+                    debug!(dependency = %id, url = %repo, "skipping: not a GitHub URL");
                 }
             }
         }
@@ -95,6 +212,254 @@ impl<F: MetadataFetcher> CargoDiscoverer<F> {
     }
 }
 
+pub trait CratesIoFetcher {
+    fn fetch(&self, name: &str) -> Result<Option<CratesIoCrate>, CratesIoError>;
+}
+
+#[derive(Clone)]
+pub struct HttpCratesIoClient {
+    client: Client,
+    base_url: String,
+    retries: u32,
+}
+
+impl Default for HttpCratesIoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpCratesIoClient {
+    const DEFAULT_BASE_URL: &'static str = "https://crates.io/api/v1/crates";
+
+    pub fn new() -> Self {
+        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+    }
+
+    fn with_client_and_base(client: Client, base_url: String) -> Self {
+        Self {
+            client,
+            base_url,
+            retries: http::DEFAULT_RETRIES,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Retry transient failures (timeouts, connection errors, 5xx) up to
+    /// `retries` times before giving up.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self::with_client_and_base(Client::new(), base_url.into())
+    }
+}
+
+impl CratesIoFetcher for FileBackedFetcher {
+    fn fetch(&self, name: &str) -> Result<Option<CratesIoCrate>, CratesIoError> {
+        Ok(self.read(name)?)
+    }
+}
+
+impl CratesIoFetcher for HttpCratesIoClient {
+    fn fetch(&self, name: &str) -> Result<Option<CratesIoCrate>, CratesIoError> {
+        let base = self.base_url.trim_end_matches('/');
+        let url = format!("{base}/{name}");
+        // crates.io rejects requests without a User-Agent.
+        let response = http::send_with_retry(self.retries, &http::ThreadSleeper, || {
+            self.client
+                .get(&url)
+                .header(ACCEPT, "application/json")
+                .header(USER_AGENT, "thanks-stars")
+                .send()
+        })?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(CratesIoError::UnexpectedStatus { status, url }),
+            _ => Ok(Some(
+                serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| CratesIoError::Decode { source })?,
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CratesIoCrate {
+    #[serde(rename = "crate")]
+    krate: CratesIoMetadata,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CratesIoMetadata {
+    #[serde(default)]
+    repository: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+}
+
+impl CratesIoCrate {
+    pub fn candidate_urls(&self) -> impl Iterator<Item = &str> {
+        [&self.krate.repository, &self.krate.homepage]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+}
+
+/// Where a dependency declared in `Cargo.toml` comes from.
+enum ManifestSource {
+    /// A crates.io package, by its published name.
+    Registry(String),
+    Git(String),
+}
+
+/// Reads dependencies straight from `Cargo.toml`, for when `cargo metadata`
+/// cannot be run. Git dependencies are resolved from their URL and registry
+/// dependencies through crates.io; path dependencies are skipped.
+pub struct CargoManifestDiscoverer<F: CratesIoFetcher> {
+    fetcher: F,
+}
+
+impl Default for CargoManifestDiscoverer<HttpCratesIoClient> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CargoManifestDiscoverer<HttpCratesIoClient> {
+    pub fn new() -> Self {
+        Self {
+            fetcher: HttpCratesIoClient::new(),
+        }
+    }
+}
+
+impl<F: CratesIoFetcher> CargoManifestDiscoverer<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self { fetcher }
+    }
+
+    pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, CargoDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, CargoDiscoveryError> {
+        let path = project_root.join(CARGO_MANIFEST);
+        let content = read_manifest(&path).map_err(|source| CargoDiscoveryError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let manifest: toml::Table =
+            toml::from_str(&content).map_err(|source| CargoDiscoveryError::Toml {
+                path: path.display().to_string(),
+                source,
+            })?;
+
+        let mut repositories = Vec::new();
+        for (name, source) in manifest_dependencies(&manifest) {
+            trace!(dependency = %name, "considering dependency");
+            stats.record_examined();
+            let resolved = match &source {
+                ManifestSource::Git(url) => parse_github_repository(url),
+                ManifestSource::Registry(package) => self
+                    .fetcher
+                    .fetch(package)
+                    .map_err(|source| CargoDiscoveryError::CratesIo {
+                        name: package.clone(),
+                        source,
+                    })?
+                    .and_then(|krate| krate.candidate_urls().find_map(parse_github_repository)),
+            };
+            let Some(mut repository) = resolved else {
+                stats.record_unresolved(&name);
+                debug!(dependency = %name, "skipping: no GitHub repository found");
+                continue;
+            };
+            debug!(dependency = %name, url = %repository.url, "resolved");
+            repository.via = Some(CARGO_MANIFEST.to_string());
+            repositories.push(repository);
+        }
+
+        Ok(repositories)
+    }
+}
+
+/// The GitHub repository `Cargo.toml` in `project_root` declares for the
+/// package itself, falling back to `[workspace.package]` for virtual manifests
+/// and `repository.workspace = true`.
+pub fn own_repository(project_root: &Path) -> Option<Repository> {
+    let content = read_manifest(project_root.join(CARGO_MANIFEST)).ok()?;
+    let manifest: toml::Table = toml::from_str(&content).ok()?;
+    let repository = |table: Option<&toml::Value>| {
+        table
+            .and_then(|table| table.get("repository"))
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+    let workspace = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"));
+    repository(manifest.get("package"))
+        .or_else(|| repository(workspace))
+        .and_then(|url| parse_github_repository(&url))
+}
+
+/// Dependencies declared in `[dependencies]`, `[dev-dependencies]`,
+/// `[build-dependencies]` and `[workspace.dependencies]`, keyed by the name
+/// used in the manifest. Path dependencies, alternate registries and entries
+/// inherited with `workspace = true` are left out.
+fn manifest_dependencies(manifest: &toml::Table) -> BTreeMap<String, ManifestSource> {
+    let workspace = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(toml::Value::as_table);
+    let tables = DEPENDENCY_TABLES
+        .iter()
+        .filter_map(|table| manifest.get(*table).and_then(toml::Value::as_table))
+        .chain(workspace);
+
+    let mut dependencies = BTreeMap::new();
+    for table in tables {
+        for (name, spec) in table {
+            let source = match spec {
+                toml::Value::String(_) => ManifestSource::Registry(name.clone()),
+                toml::Value::Table(spec) => {
+                    let field = |key: &str| spec.get(key).and_then(toml::Value::as_str);
+                    if let Some(git) = field("git") {
+                        ManifestSource::Git(git.to_string())
+                    } else if spec.contains_key("path")
+                        || spec.contains_key("registry")
+                        || spec.contains_key("workspace")
+                    {
+                        trace!(dependency = %name, "skipping: not a crates.io dependency");
+                        continue;
+                    } else {
+                        ManifestSource::Registry(field("package").unwrap_or(name).to_string())
+                    }
+                }
+                _ => continue,
+            };
+            dependencies.entry(name.clone()).or_insert(source);
+        }
+    }
+    dependencies
+}
+
 #[derive(Deserialize)]
 struct Metadata {
     packages: Vec<Package>,
@@ -129,7 +494,11 @@ struct Dependency {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+    use std::fs;
     use std::path::Path;
+    use tempfile::tempdir;
 
     struct StaticMetadataFetcher {
         json: String,
@@ -183,6 +552,23 @@ mod tests {
         assert!(names.contains(&"dep2"));
     }
 
+    #[test]
+    fn command_errors_name_the_cargo_binary() {
+        let dir = tempdir().unwrap();
+        let program = dir.path().join("missing/cargo");
+
+        let err = CommandMetadataFetcher::with_program(&program)
+            .fetch(dir.path())
+            .unwrap_err();
+
+        assert!(matches!(
+            &err,
+            CargoDiscoveryError::CommandIo { source, .. }
+                if source.kind() == std::io::ErrorKind::NotFound
+        ));
+        assert!(err.to_string().contains(&program.display().to_string()));
+    }
+
     #[test]
     fn returns_empty_when_no_repositories() {
         let metadata = r#"{
@@ -200,4 +586,66 @@ mod tests {
         let repos = discoverer.discover(Path::new(".")).unwrap();
         assert!(repos.is_empty());
     }
+
+    #[test]
+    fn reads_registry_git_and_workspace_dependencies_from_manifest() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CARGO_MANIFEST),
+            r#"
+            [workspace.dependencies]
+            serde = { version = "1", features = ["derive"] }
+
+            [dependencies]
+            serde = { workspace = true }
+            rand_core = { package = "rand-core-fork", version = "0.6" }
+            local = { path = "../local" }
+            tokio = { git = "https://github.com/tokio-rs/tokio", branch = "master" }
+
+            [dev-dependencies]
+            unpublished = "0.1"
+            "#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        for (name, repository) in [
+            ("serde", "https://github.com/serde-rs/serde"),
+            (
+                "rand-core-fork",
+                "https://github.com/example/rand-core-fork",
+            ),
+        ] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/{name}"));
+                then.status(200)
+                    .json_body(json!({ "crate": { "repository": repository } }));
+            });
+        }
+        server.mock(|when, then| {
+            when.method(GET).path("/unpublished");
+            then.status(404);
+        });
+
+        let stats = DiscoveryStats::default();
+        let discoverer = CargoManifestDiscoverer::with_fetcher(HttpCratesIoClient::with_base_url(
+            server.base_url(),
+        ));
+        let repos = discoverer.discover_with_stats(dir.path(), &stats).unwrap();
+
+        let urls: Vec<_> = repos.iter().map(|repo| repo.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://github.com/example/rand-core-fork",
+                "https://github.com/serde-rs/serde",
+                "https://github.com/tokio-rs/tokio",
+            ]
+        );
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("Cargo.toml")));
+        assert_eq!(stats.examined(), 4);
+        assert_eq!(stats.unresolved(), 1);
+    }
 }
@@ -1,10 +1,16 @@
 use std::collections::BTreeSet;
-use std::fs;
 use std::path::Path;
 
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
 use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::text::read_manifest;
+use crate::http;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ComposerDiscoveryError {
@@ -20,21 +26,153 @@ pub enum ComposerDiscoveryError {
         #[source]
         source: serde_json::Error,
     },
+    #[error("failed to fetch Packagist metadata for package {package}: {source}")]
+    Packagist {
+        package: String,
+        #[source]
+        source: PackagistError,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackagistError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+pub trait PackagistFetcher {
+    /// Return candidate repository URLs for `package`, most specific first.
+    fn fetch_candidate_urls(&self, package: &str) -> Result<Vec<String>, PackagistError>;
+}
+
+#[derive(Clone)]
+pub struct HttpPackagistClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for HttpPackagistClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpPackagistClient {
+    const DEFAULT_BASE_URL: &'static str = "https://repo.packagist.org/p2";
+
+    pub fn new() -> Self {
+        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+    }
+
+    fn with_client_and_base(client: Client, base_url: String) -> Self {
+        Self { client, base_url }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self::with_client_and_base(Client::new(), base_url.into())
+    }
+}
+
+impl PackagistFetcher for HttpPackagistClient {
+    fn fetch_candidate_urls(&self, package: &str) -> Result<Vec<String>, PackagistError> {
+        let url = format!("{}/{package}.json", self.base_url.trim_end_matches('/'));
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/json")
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(Vec::new()),
+            status if !status.is_success() => Err(PackagistError::UnexpectedStatus { status, url }),
+            _ => {
+                let metadata: PackagistMetadata = serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| PackagistError::Decode { source })?;
+                // Minified metadata only repeats changed fields after the first
+                // (newest) version, which carries the full set.
+                let urls = metadata
+                    .packages
+                    .get(package)
+                    .and_then(|versions| versions.first())
+                    .map(|latest| latest.candidate_urls().map(str::to_string).collect())
+                    .unwrap_or_default();
+                Ok(urls)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PackagistMetadata {
+    #[serde(default)]
+    packages: std::collections::BTreeMap<String, Vec<ComposerPackage>>,
 }
 
-#[derive(Default)]
-pub struct ComposerDiscoverer;
+pub struct ComposerDiscoverer<F: PackagistFetcher = HttpPackagistClient> {
+    fetcher: F,
+    scope: DependencyScope,
+}
 
-impl ComposerDiscoverer {
+impl Default for ComposerDiscoverer<HttpPackagistClient> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComposerDiscoverer<HttpPackagistClient> {
     pub fn new() -> Self {
-        Self
+        Self::with_fetcher(HttpPackagistClient::new())
+    }
+}
+
+impl<F: PackagistFetcher> ComposerDiscoverer<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// With [`DependencyScope::Direct`], resolve the `require`/`require-dev`
+    /// packages of `composer.json` even when a `composer.lock` exists.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, ComposerDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, ComposerDiscoveryError> {
+        if self.scope == DependencyScope::Direct {
+            return self.discover_from_manifest(project_root, stats);
+        }
         let lock_path = project_root.join("composer.lock");
-        let content = match fs::read_to_string(&lock_path) {
+        let content = match read_manifest(&lock_path) {
             Ok(content) => content,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return self.discover_from_manifest(project_root, stats)
+            }
             Err(err) => {
                 return Err(ComposerDiscoveryError::Io {
                     path: lock_path.display().to_string(),
@@ -52,24 +190,148 @@ impl ComposerDiscoverer {
         let mut repositories = Vec::new();
         let mut seen = BTreeSet::new();
 
-        for package in lock
-            .packages
-            .into_iter()
-            .chain(lock.packages_dev.into_iter())
-        {
-            for candidate in package.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(candidate) {
-                    if seen.insert((repository.owner.clone(), repository.name.clone())) {
-                        repository.via = Some("composer.lock".to_string());
-                        repositories.push(repository);
-                    }
-                    break;
-                }
+        for package in lock.packages.into_iter().chain(lock.packages_dev) {
+            trace!(package = %package.name, "considering package");
+            stats.record_examined();
+            let Some(mut repository) = package.candidate_urls().find_map(parse_github_repository)
+            else {
+                stats.record_unresolved(&package.name);
+                debug!(package = %package.name, "skipping: no GitHub URL in composer.lock");
+                continue;
+            };
+            debug!(package = %package.name, url = %repository.url, "resolved");
+            if seen.insert((repository.owner.clone(), repository.name.clone())) {
+                repository.via = Some("composer.lock".to_string());
+                repositories.push(repository);
             }
         }
 
         Ok(repositories)
     }
+
+    /// Resolve the `require`/`require-dev` packages of `composer.json` through
+    /// Packagist, for projects that do not commit a lockfile.
+    fn discover_from_manifest(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, ComposerDiscoveryError> {
+        let manifest_path = project_root.join("composer.json");
+        let content = match read_manifest(&manifest_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(ComposerDiscoveryError::Io {
+                    path: manifest_path.display().to_string(),
+                    source: err,
+                })
+            }
+        };
+
+        let manifest: Value =
+            serde_json::from_str(&content).map_err(|source| ComposerDiscoveryError::Json {
+                path: manifest_path.display().to_string(),
+                source,
+            })?;
+
+        let mut repositories = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        let mut served_names = BTreeSet::new();
+        for url in vcs_repository_urls(&manifest) {
+            trace!(%url, "considering custom repository");
+            stats.record_examined();
+            served_names.insert(repository_name(url));
+            let Some(mut repository) = parse_github_repository(url) else {
+                stats.record_unresolved(url);
+                debug!(%url, "skipping custom repository: not a GitHub URL");
+                continue;
+            };
+            debug!(url = %repository.url, "resolved custom repository");
+            if seen.insert((repository.owner.clone(), repository.name.clone())) {
+                repository.via = Some("composer.json".to_string());
+                repositories.push(repository);
+            }
+        }
+
+        for package in manifest_packages(&manifest) {
+            let name = package
+                .split_once('/')
+                .map_or(package.as_str(), |(_, name)| name);
+            if served_names.contains(name) {
+                debug!(%package, "skipping: served by a custom repository");
+                continue;
+            }
+            trace!(%package, "considering package");
+            stats.record_examined();
+            let candidates = self
+                .fetcher
+                .fetch_candidate_urls(&package)
+                .map_err(|source| ComposerDiscoveryError::Packagist {
+                    package: package.clone(),
+                    source,
+                })?;
+            let Some(mut repository) = candidates
+                .iter()
+                .find_map(|candidate| parse_github_repository(candidate))
+            else {
+                stats.record_unresolved(&package);
+                debug!(%package, "skipping: no GitHub URL on Packagist");
+                continue;
+            };
+            debug!(%package, url = %repository.url, "resolved");
+            if seen.insert((repository.owner.clone(), repository.name.clone())) {
+                repository.via = Some("composer.json".to_string());
+                repositories.push(repository);
+            }
+        }
+
+        Ok(repositories)
+    }
+}
+
+/// Package names from `require` and `require-dev`, skipping platform packages
+/// such as `php` and `ext-json`, which have no `vendor/` prefix.
+fn manifest_packages(manifest: &Value) -> BTreeSet<String> {
+    ["require", "require-dev"]
+        .into_iter()
+        .filter_map(|section| manifest.get(section).and_then(|v| v.as_object()))
+        .flat_map(|deps| deps.keys())
+        .filter(|name| name.contains('/'))
+        .map(|name| name.to_ascii_lowercase())
+        .collect()
+}
+
+/// URLs of the `vcs`, `git` and `github` entries in the `repositories` of
+/// `composer.json`, which point at forks or private packages that Packagist
+/// does not know about. `path` and other repository types are skipped.
+fn vcs_repository_urls(manifest: &Value) -> Vec<&str> {
+    let entries: Vec<&Value> = match manifest.get("repositories") {
+        Some(Value::Array(entries)) => entries.iter().collect(),
+        // Older manifests key repositories by name.
+        Some(Value::Object(entries)) => entries.values().collect(),
+        _ => return Vec::new(),
+    };
+    entries
+        .into_iter()
+        .filter(|entry| {
+            matches!(
+                entry.get("type").and_then(Value::as_str),
+                Some("vcs" | "git" | "github")
+            )
+        })
+        .filter_map(|entry| entry.get("url").and_then(Value::as_str))
+        .collect()
+}
+
+/// The lowercased last path segment of a repository URL, without `.git`.
+/// Composer installs a package from a `vcs` repository instead of Packagist
+/// when the repository provides it, which for a fork usually means the
+/// repository keeps the package's name.
+fn repository_name(url: &str) -> String {
+    let path = url.trim_end_matches('/');
+    let name = path.rsplit(['/', ':']).next().unwrap_or(path);
+    name.trim_end_matches(".git").to_ascii_lowercase()
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,6 +344,8 @@ struct ComposerLock {
 
 #[derive(Debug, Deserialize)]
 struct ComposerPackage {
+    #[serde(default)]
+    name: String,
     #[serde(default)]
     source: Option<ComposerSource>,
     #[serde(default)]
@@ -125,6 +389,7 @@ struct ComposerSupport {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use httpmock::prelude::*;
     use serde_json::json;
     use std::fs;
     use tempfile::tempdir;
@@ -175,6 +440,148 @@ mod tests {
         }
     }
 
+    #[test]
+    fn falls_back_to_packagist_without_lockfile() {
+        let server = MockServer::start();
+        let packagist = server.mock(|when, then| {
+            when.method(GET).path("/monolog/monolog.json");
+            then.status(200).json_body(json!({
+                "minified": "composer/2.0",
+                "packages": {
+                    "monolog/monolog": [
+                        {
+                            "version": "3.5.0",
+                            "source": {
+                                "type": "git",
+                                "url": "https://github.com/Seldaek/monolog.git"
+                            },
+                            "homepage": "https://github.com/Seldaek/monolog"
+                        },
+                        { "version": "3.4.0" }
+                    ]
+                }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/vendor/missing.json");
+            then.status(404);
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("composer.json"),
+            json!({
+                "require": { "php": "^8.1", "ext-json": "*", "monolog/monolog": "^3.0" },
+                "require-dev": { "vendor/missing": "^1.0" }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let discoverer =
+            ComposerDiscoverer::with_fetcher(HttpPackagistClient::with_base_url(server.base_url()));
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        packagist.assert();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].owner, "Seldaek");
+        assert_eq!(repos[0].name, "monolog");
+        assert_eq!(repos[0].via.as_deref(), Some("composer.json"));
+    }
+
+    #[test]
+    fn resolves_vcs_repositories_without_packagist() {
+        let server = MockServer::start();
+        let packagist = server.mock(|when, then| {
+            when.method(GET);
+            then.status(404);
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("composer.json"),
+            json!({
+                "repositories": [
+                    { "type": "vcs", "url": "https://github.com/example/monolog.git" },
+                    { "type": "vcs", "url": "https://git.example.com/internal/tools" },
+                    { "type": "path", "url": "../packages/local" },
+                    { "type": "composer", "url": "https://packages.example.com" }
+                ],
+                "require": { "monolog/monolog": "dev-main" }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let discoverer =
+            ComposerDiscoverer::with_fetcher(HttpPackagistClient::with_base_url(server.base_url()));
+        let stats = DiscoveryStats::default();
+        let repos = discoverer.discover_with_stats(dir.path(), &stats).unwrap();
+
+        packagist.assert_calls(0);
+        assert_eq!(stats.examined(), 2);
+        assert_eq!(
+            stats.unresolved_dependencies(),
+            vec!["https://git.example.com/internal/tools".to_string()]
+        );
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/example/monolog");
+        assert_eq!(repos[0].via.as_deref(), Some("composer.json"));
+    }
+
+    #[test]
+    fn direct_scope_reads_composer_json_instead_of_the_lockfile() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/monolog/monolog.json");
+            then.status(200).json_body(json!({
+                "packages": {
+                    "monolog/monolog": [
+                        { "source": { "url": "https://github.com/Seldaek/monolog.git" } }
+                    ]
+                }
+            }));
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("composer.json"),
+            json!({ "require": { "monolog/monolog": "^3.0" } }).to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("composer.lock"),
+            json!({
+                "packages": [
+                    {
+                        "name": "monolog/monolog",
+                        "source": { "url": "https://github.com/Seldaek/monolog.git" }
+                    },
+                    {
+                        "name": "psr/log",
+                        "source": { "url": "https://github.com/php-fig/log.git" }
+                    }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let client = HttpPackagistClient::with_base_url(server.base_url());
+        let all = ComposerDiscoverer::with_fetcher(client.clone())
+            .discover(dir.path())
+            .unwrap();
+        let direct = ComposerDiscoverer::with_fetcher(client)
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].name, "monolog");
+        assert_eq!(direct[0].via.as_deref(), Some("composer.json"));
+    }
+
     #[test]
     fn ignores_missing_lockfile() {
         let dir = tempdir().unwrap();
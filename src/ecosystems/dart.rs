@@ -1,5 +1,4 @@
-use std::collections::BTreeSet;
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
 use reqwest::blocking::Client;
@@ -7,11 +6,21 @@ use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_yaml::{Mapping, Value};
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::caching::CachingFetcher;
+use crate::ecosystems::fixtures::{FileBackedFetcher, FixtureError};
+use crate::ecosystems::text::{parse_yaml, read_manifest};
 use crate::http;
 
 const PUBSPEC_FILE: &str = "pubspec.yaml";
+const PUBSPEC_LOCK_FILE: &str = "pubspec.lock";
+
+/// Package name to the manifest that first mentioned it.
+type HostedPackages = BTreeMap<String, &'static str>;
+/// Package name to its git URL and the manifest that first mentioned it.
+type GitPackages = BTreeMap<String, (String, &'static str)>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum DartDiscoveryError {
@@ -39,18 +48,42 @@ pub enum DartDiscoveryError {
 pub enum PubDevError {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
-    #[error("unexpected status {status}")]
-    UnexpectedStatus { status: StatusCode },
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Fixture(#[from] FixtureError),
 }
 
 pub trait PubDevFetcher {
     fn fetch(&self, name: &str) -> Result<Option<PubDevPackage>, PubDevError>;
 }
 
+/// pub.dev lookups remembered by a default [`DartDiscoverer`], or shared by the
+/// discoverers of one run.
+pub type CachedPubDevClient = CachingFetcher<HttpPubDevClient, Option<PubDevPackage>>;
+
+impl PubDevFetcher for FileBackedFetcher {
+    fn fetch(&self, name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
+        Ok(self.read(name)?)
+    }
+}
+
+impl<F: PubDevFetcher> PubDevFetcher for CachingFetcher<F, Option<PubDevPackage>> {
+    fn fetch(&self, name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
+        self.get_or_fetch(name, |inner, name| inner.fetch(name))
+    }
+}
+
 #[derive(Clone)]
 pub struct HttpPubDevClient {
     client: Client,
     base_url: String,
+    retries: u32,
 }
 
 impl Default for HttpPubDevClient {
@@ -67,7 +100,25 @@ impl HttpPubDevClient {
     }
 
     fn with_client_and_base(client: Client, base_url: String) -> Self {
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            retries: http::DEFAULT_RETRIES,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Retry transient failures (timeouts, connection errors, 5xx) up to
+    /// `retries` times before giving up.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     #[cfg(test)]
@@ -80,31 +131,35 @@ impl PubDevFetcher for HttpPubDevClient {
     fn fetch(&self, name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
         let base = self.base_url.trim_end_matches('/');
         let url = format!("{base}/{name}");
-        let response = self
-            .client
-            .get(&url)
-            .header(ACCEPT, "application/json")
-            .send()?;
+        let response = http::send_with_retry(self.retries, &http::ThreadSleeper, || {
+            self.client
+                .get(&url)
+                .header(ACCEPT, "application/json")
+                .send()
+        })?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(None),
-            status if !status.is_success() => Err(PubDevError::UnexpectedStatus { status }),
-            _ => Ok(Some(response.json()?)),
+            status if !status.is_success() => Err(PubDevError::UnexpectedStatus { status, url }),
+            _ => Ok(Some(
+                serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| PubDevError::Decode { source })?,
+            )),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct PubDevPackage {
     latest: PubDevVersion,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 struct PubDevVersion {
     pubspec: PubDevPubspec,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 struct PubDevPubspec {
     #[serde(default)]
     repository: Option<String>,
@@ -157,66 +212,86 @@ impl<'a> Iterator for CandidateUrls<'a> {
 
 pub struct DartDiscoverer<F: PubDevFetcher> {
     fetcher: F,
+    scope: DependencyScope,
 }
 
-impl Default for DartDiscoverer<HttpPubDevClient> {
+impl Default for DartDiscoverer<CachedPubDevClient> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl DartDiscoverer<HttpPubDevClient> {
+impl DartDiscoverer<CachedPubDevClient> {
     pub fn new() -> Self {
-        Self {
-            fetcher: HttpPubDevClient::new(),
-        }
+        Self::with_fetcher(CachingFetcher::new(HttpPubDevClient::new()))
     }
 }
 
 impl<F: PubDevFetcher> DartDiscoverer<F> {
     pub fn with_fetcher(fetcher: F) -> Self {
-        Self { fetcher }
+        Self {
+            fetcher,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// With [`DependencyScope::Direct`], skip the packages `pubspec.lock`
+    /// marks as `transitive`.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DartDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DartDiscoveryError> {
         let path = project_root.join(PUBSPEC_FILE);
-        let content = fs::read_to_string(&path).map_err(|err| DartDiscoveryError::Io {
+        let content = read_manifest(&path).map_err(|err| DartDiscoveryError::Io {
             path: path.display().to_string(),
             source: err,
         })?;
 
-        let value: Value =
-            serde_yaml::from_str(&content).map_err(|err| DartDiscoveryError::Yaml {
-                path: path.display().to_string(),
-                source: err,
-            })?;
+        let value = parse_yaml(&content).map_err(|err| DartDiscoveryError::Yaml {
+            path: path.display().to_string(),
+            source: err,
+        })?;
 
-        let mut hosted = BTreeSet::new();
-        let mut git_urls = BTreeSet::new();
+        let mut hosted = HostedPackages::new();
+        let mut git = GitPackages::new();
 
-        if let Some(deps) = value.get("dependencies").and_then(Value::as_mapping) {
-            collect_dependencies(deps, &mut hosted, &mut git_urls);
-        }
-        if let Some(deps) = value.get("dev_dependencies").and_then(Value::as_mapping) {
-            collect_dependencies(deps, &mut hosted, &mut git_urls);
-        }
-        if let Some(deps) = value
-            .get("dependency_overrides")
-            .and_then(Value::as_mapping)
-        {
-            collect_dependencies(deps, &mut hosted, &mut git_urls);
+        for section in ["dependencies", "dev_dependencies", "dependency_overrides"] {
+            if let Some(deps) = value.get(section).and_then(Value::as_mapping) {
+                collect_dependencies(deps, &mut hosted, &mut git);
+            }
         }
+        collect_lock_dependencies(project_root, self.scope, &mut hosted, &mut git)?;
 
         let mut repositories = Vec::new();
 
-        for url in git_urls {
-            if let Some(mut repository) = parse_github_repository(&url) {
-                repository.via = Some(PUBSPEC_FILE.to_string());
-                repositories.push(repository);
+        for (name, (url, via)) in git {
+            stats.record_examined();
+            match parse_github_repository(&url) {
+                Some(mut repository) => {
+                    debug!(package = %name, url = %repository.url, "resolved git dependency");
+                    repository.via = Some(via.to_string());
+                    repositories.push(repository);
+                }
+                None => {
+                    stats.record_unresolved(&name);
+                    debug!(package = %name, %url, "skipping: git URL is not on GitHub");
+                }
             }
         }
 
-        for name in hosted {
+        for (name, via) in hosted {
+            trace!(package = %name, "considering package");
+            stats.record_examined();
             let Some(package) =
                 self.fetcher
                     .fetch(&name)
@@ -225,59 +300,112 @@ impl<F: PubDevFetcher> DartDiscoverer<F> {
                         source,
                     })?
             else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: not found on pub.dev");
                 continue;
             };
 
-            for url in package.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(&url) {
-                    repository.via = Some(PUBSPEC_FILE.to_string());
-                    repositories.push(repository);
-                    break;
-                }
-            }
+            let Some(mut repository) = package
+                .candidate_urls()
+                .find_map(|url| parse_github_repository(&url))
+            else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: no GitHub URL in pub.dev metadata");
+                continue;
+            };
+            debug!(package = %name, url = %repository.url, "resolved");
+            repository.via = Some(via.to_string());
+            repositories.push(repository);
         }
 
         Ok(repositories)
     }
 }
 
-fn collect_dependencies(
-    mapping: &Mapping,
-    hosted: &mut BTreeSet<String>,
-    git_urls: &mut BTreeSet<String>,
-) {
+fn collect_dependencies(mapping: &Mapping, hosted: &mut HostedPackages, git: &mut GitPackages) {
     for (name_value, details) in mapping {
         let Some(name) = name_value.as_str() else {
             continue;
         };
 
-        match details {
-            Value::String(_) | Value::Number(_) | Value::Bool(_) | Value::Null => {
-                hosted.insert(name.to_string());
+        if let Value::Mapping(map) = details {
+            let git_key = Value::from("git");
+            if let Some(url) = map.get(&git_key).and_then(git_url) {
+                hosted.remove(name);
+                git.insert(name.to_string(), (url.to_string(), PUBSPEC_FILE));
+                continue;
             }
-            Value::Mapping(map) => {
-                let git_key = Value::from("git");
-                if let Some(git) = map.get(&git_key) {
-                    if let Some(url) = git_url(git) {
-                        git_urls.insert(url.to_string());
-                        continue;
-                    }
-                }
-                let sdk_key = Value::from("sdk");
-                let path_key = Value::from("path");
-                if map.contains_key(&sdk_key) || map.contains_key(&path_key) {
-                    continue;
-                }
-                hosted.insert(name.to_string());
+            let sdk_key = Value::from("sdk");
+            let path_key = Value::from("path");
+            if map.contains_key(&sdk_key) || map.contains_key(&path_key) {
+                continue;
             }
-            Value::Sequence(_) => {
-                hosted.insert(name.to_string());
+        }
+        if !git.contains_key(name) {
+            hosted.entry(name.to_string()).or_insert(PUBSPEC_FILE);
+        }
+    }
+}
+
+/// Merge the resolved packages of `pubspec.lock` into the sets collected from
+/// `pubspec.yaml`. Git URLs recorded in the lock win, since they reflect what
+/// was actually resolved. With [`DependencyScope::Direct`], `transitive`
+/// packages are left out.
+fn collect_lock_dependencies(
+    project_root: &Path,
+    scope: DependencyScope,
+    hosted: &mut HostedPackages,
+    git: &mut GitPackages,
+) -> Result<(), DartDiscoveryError> {
+    let path = project_root.join(PUBSPEC_LOCK_FILE);
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(DartDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let value = parse_yaml(&content).map_err(|err| DartDiscoveryError::Yaml {
+        path: path.display().to_string(),
+        source: err,
+    })?;
+
+    let Some(packages) = value.get("packages").and_then(Value::as_mapping) else {
+        return Ok(());
+    };
+    for (name_value, details) in packages {
+        let Some(name) = name_value.as_str() else {
+            continue;
+        };
+        if scope == DependencyScope::Direct
+            && details.get("dependency").and_then(Value::as_str) == Some("transitive")
+        {
+            trace!(package = %name, "skipping: transitive in pubspec.lock");
+            continue;
+        }
+        match details.get("source").and_then(Value::as_str) {
+            Some("hosted") if !git.contains_key(name) => {
+                hosted.entry(name.to_string()).or_insert(PUBSPEC_LOCK_FILE);
             }
-            _ => {
-                hosted.insert(name.to_string());
+            Some("git") => {
+                let Some(url) = details.get("description").and_then(git_url) else {
+                    continue;
+                };
+                let via = hosted
+                    .remove(name)
+                    .or_else(|| git.get(name).map(|(_, via)| *via))
+                    .unwrap_or(PUBSPEC_LOCK_FILE);
+                git.insert(name.to_string(), (url.to_string(), via));
             }
+            _ => {}
         }
     }
+
+    Ok(())
 }
 
 fn git_url(value: &Value) -> Option<&str> {
@@ -343,16 +471,16 @@ dependencies:
         assert_eq!(repo.via.as_deref(), Some(PUBSPEC_FILE));
     }
 
-    #[test]
-    fn discovers_git_dependencies_without_fetching() {
-        struct PanicFetcher;
+    struct PanicFetcher;
 
-        impl PubDevFetcher for PanicFetcher {
-            fn fetch(&self, _name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
-                panic!("fetch should not be called")
-            }
+    impl PubDevFetcher for PanicFetcher {
+        fn fetch(&self, _name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
+            panic!("fetch should not be called")
         }
+    }
 
+    #[test]
+    fn discovers_git_dependencies_without_fetching() {
         let dir = tempdir().unwrap();
         fs::write(
             dir.path().join(PUBSPEC_FILE),
@@ -377,6 +505,73 @@ dependencies:
         assert_eq!(repo.via.as_deref(), Some(PUBSPEC_FILE));
     }
 
+    #[test]
+    fn resolves_anchored_dependency_blocks() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(PUBSPEC_FILE),
+            r#"
+name: example
+other_url: &other https://github.com/example/other.git
+shared: &shared
+  awesome:
+    git:
+      url: https://github.com/example/awesome.git
+dependencies:
+  <<: *shared
+  other:
+    git: *other
+"#,
+        )
+        .unwrap();
+
+        let discoverer = DartDiscoverer::with_fetcher(PanicFetcher);
+        let mut repos = discoverer.discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names: Vec<_> = repos.iter().map(|repo| repo.name.as_str()).collect();
+        assert_eq!(names, ["awesome", "other"]);
+    }
+
+    #[test]
+    fn reads_the_first_document_after_a_separator() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(PUBSPEC_FILE),
+            r#"# Generated; do not edit.
+---
+name: example
+dependencies:
+  awesome:
+    git: https://github.com/example/awesome.git
+---
+name: ignored
+dependencies:
+  ignored:
+    git: https://github.com/example/ignored.git
+"#,
+        )
+        .unwrap();
+
+        let discoverer = DartDiscoverer::with_fetcher(PanicFetcher);
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].name, "awesome");
+    }
+
+    #[test]
+    fn rejects_malformed_pubspec() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(PUBSPEC_FILE), "dependencies: [unclosed\n").unwrap();
+
+        let err = DartDiscoverer::with_fetcher(PanicFetcher)
+            .discover(dir.path())
+            .unwrap_err();
+
+        assert!(matches!(err, DartDiscoveryError::Yaml { .. }));
+    }
+
     #[test]
     fn includes_dependency_overrides() {
         struct RecordingFetcher {
@@ -434,4 +629,99 @@ dependency_overrides:
         assert!(repos.iter().any(|repo| repo.name == "hosted_dep"));
         assert!(repos.iter().any(|repo| repo.name == "git_dep"));
     }
+
+    #[test]
+    fn merges_resolved_packages_from_pubspec_lock() {
+        struct StaticFetcher;
+
+        impl PubDevFetcher for StaticFetcher {
+            fn fetch(&self, name: &str) -> Result<Option<PubDevPackage>, PubDevError> {
+                Ok(Some(PubDevPackage {
+                    latest: PubDevVersion {
+                        pubspec: PubDevPubspec {
+                            repository: Some(format!("https://github.com/dart-lang/{name}")),
+                            homepage: None,
+                            issue_tracker: None,
+                            documentation: None,
+                        },
+                    },
+                }))
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(PUBSPEC_FILE),
+            r#"
+name: example
+dependencies:
+  http: ^1.0.0
+  forked:
+    git: https://github.com/someone/forked
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(PUBSPEC_LOCK_FILE),
+            r#"
+packages:
+  http:
+    dependency: "direct main"
+    description:
+      name: http
+      sha256: "abc"
+      url: "https://pub.dev"
+    source: hosted
+    version: "1.2.0"
+  async:
+    dependency: transitive
+    description:
+      name: async
+      sha256: "def"
+      url: "https://pub.dev"
+    source: hosted
+    version: "2.11.0"
+  forked:
+    dependency: "direct main"
+    description:
+      path: "."
+      ref: HEAD
+      resolved-ref: "0123456789abcdef"
+      url: "https://github.com/upstream/forked.git"
+    source: git
+    version: "0.1.0"
+  flutter:
+    dependency: "direct main"
+    description: flutter
+    source: sdk
+    version: "0.0.0"
+sdks:
+  dart: ">=3.0.0 <4.0.0"
+"#,
+        )
+        .unwrap();
+
+        let discoverer = DartDiscoverer::with_fetcher(StaticFetcher);
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.owner.as_str(), repo.name.as_str(), repo.via.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("upstream", "forked", Some(PUBSPEC_FILE)),
+                ("dart-lang", "async", Some(PUBSPEC_LOCK_FILE)),
+                ("dart-lang", "http", Some(PUBSPEC_FILE)),
+            ]
+        );
+
+        let direct = DartDiscoverer::with_fetcher(StaticFetcher)
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+        let names: Vec<_> = direct.iter().map(|repo| repo.name.as_str()).collect();
+        assert_eq!(names, vec!["forked", "http"]);
+    }
 }
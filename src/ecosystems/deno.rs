@@ -1,15 +1,23 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs;
+use std::fmt;
 use std::path::Path;
 
 use jsonc_parser::{errors::ParseError, parse_to_serde_value, ParseOptions};
 use serde_json::Value;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::deno_land::{
+    parse_deno_land_module, DenoLandError, DenoLandFetcher, HttpDenoLandClient,
+};
 use crate::ecosystems::jsr::{
     collect_import_specifiers, collect_jsr_packages_from_jsr_manifest, collect_jsr_strings,
     normalize_jsr_name, parse_jsr_specifier, HttpJsrClient, JsrError, JsrFetcher,
 };
+use crate::ecosystems::npm::{
+    parse_npm_specifier, HttpNpmRegistryClient, NpmRegistryError, NpmRegistryFetcher,
+};
+use crate::ecosystems::text::read_manifest;
 
 #[derive(Debug, thiserror::Error)]
 pub enum DenoDiscoveryError {
@@ -37,10 +45,48 @@ pub enum DenoDiscoveryError {
         #[source]
         source: JsrError,
     },
+    #[error("failed to fetch repository for npm package {package}: {source}")]
+    Npm {
+        package: String,
+        #[source]
+        source: NpmRegistryError,
+    },
+    #[error("failed to fetch repository for deno.land/x module {module}: {source}")]
+    DenoLand {
+        module: String,
+        #[source]
+        source: DenoLandError,
+    },
+}
+
+/// A package referenced from a Deno project, keyed by the registry that
+/// serves it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PackageRef {
+    Jsr(String),
+    Npm(String),
+    DenoLand(String),
 }
 
-pub struct DenoDiscoverer<F: JsrFetcher> {
+impl fmt::Display for PackageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Jsr(name) => write!(f, "jsr:{name}"),
+            Self::Npm(name) => write!(f, "npm:{name}"),
+            Self::DenoLand(name) => write!(f, "deno.land/x/{name}"),
+        }
+    }
+}
+
+pub struct DenoDiscoverer<
+    F: JsrFetcher,
+    N: NpmRegistryFetcher = HttpNpmRegistryClient,
+    D: DenoLandFetcher = HttpDenoLandClient,
+> {
     fetcher: F,
+    npm_fetcher: N,
+    deno_land_fetcher: D,
+    scope: DependencyScope,
 }
 
 impl Default for DenoDiscoverer<HttpJsrClient> {
@@ -51,59 +97,114 @@ impl Default for DenoDiscoverer<HttpJsrClient> {
 
 impl DenoDiscoverer<HttpJsrClient> {
     pub fn new() -> Self {
-        Self {
-            fetcher: HttpJsrClient::new(),
-        }
+        Self::with_fetcher(HttpJsrClient::new())
     }
 }
 
 impl<F: JsrFetcher> DenoDiscoverer<F> {
     pub fn with_fetcher(fetcher: F) -> Self {
-        Self { fetcher }
+        Self::with_fetchers(
+            fetcher,
+            HttpNpmRegistryClient::new(),
+            HttpDenoLandClient::new(),
+        )
+    }
+}
+
+impl<F: JsrFetcher, N: NpmRegistryFetcher, D: DenoLandFetcher> DenoDiscoverer<F, N, D> {
+    pub fn with_fetchers(fetcher: F, npm_fetcher: N, deno_land_fetcher: D) -> Self {
+        Self {
+            fetcher,
+            npm_fetcher,
+            deno_land_fetcher,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// With [`DependencyScope::Direct`], skip `deno.lock`.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, DenoDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, DenoDiscoveryError> {
         let mut packages = BTreeMap::new();
 
-        collect_packages_from_deno_lock(project_root, &mut packages)?;
+        if self.scope == DependencyScope::All {
+            collect_packages_from_deno_lock(project_root, &mut packages)?;
+        }
         collect_packages_from_deno_config(project_root, "deno.json", &mut packages)?;
         collect_packages_from_deno_config(project_root, "deno.jsonc", &mut packages)?;
         collect_packages_from_jsr_manifest(project_root, &mut packages)?;
 
         let mut repositories = Vec::new();
         for (package, via) in packages {
-            let package_for_error = package.clone();
-            let Some(url) = self
-                .fetcher
-                .fetch_repository_url(&package)
-                .map_err(|source| DenoDiscoveryError::Jsr {
-                    package: package_for_error,
-                    source,
-                })?
-            else {
+            trace!(?package, "considering package");
+            stats.record_examined();
+            let Some(url) = self.fetch_repository_url(package.clone())? else {
+                stats.record_unresolved(&package);
+                debug!(
+                    ?package,
+                    "skipping: no repository link in registry metadata"
+                );
                 continue;
             };
 
-            if let Some(mut repository) = parse_github_repository(&url) {
-                repository.via = Some(via);
-                repositories.push(repository);
+            match parse_github_repository(&url) {
+                Some(mut repository) => {
+                    debug!(?package, url = %repository.url, "resolved");
+                    repository.via = Some(via);
+                    repositories.push(repository);
+                }
+                None => {
+                    stats.record_unresolved(&package);
+                    debug!(?package, %url, "skipping: not a GitHub URL");
+                }
             }
         }
 
         Ok(repositories)
     }
+
+    fn fetch_repository_url(
+        &self,
+        package: PackageRef,
+    ) -> Result<Option<String>, DenoDiscoveryError> {
+        match package {
+            PackageRef::Jsr(package) => self
+                .fetcher
+                .fetch_repository_url(&package)
+                .map_err(|source| DenoDiscoveryError::Jsr { package, source }),
+            PackageRef::Npm(package) => self
+                .npm_fetcher
+                .fetch_repository_url(&package)
+                .map_err(|source| DenoDiscoveryError::Npm { package, source }),
+            PackageRef::DenoLand(module) => self
+                .deno_land_fetcher
+                .fetch_repository_url(&module)
+                .map_err(|source| DenoDiscoveryError::DenoLand { module, source }),
+        }
+    }
 }
 
 fn collect_packages_from_deno_lock(
     project_root: &Path,
-    packages: &mut BTreeMap<String, String>,
+    packages: &mut BTreeMap<PackageRef, String>,
 ) -> Result<(), DenoDiscoveryError> {
     let lock_path = project_root.join("deno.lock");
     if !lock_path.exists() {
         return Ok(());
     }
 
-    let content = fs::read_to_string(&lock_path).map_err(|source| DenoDiscoveryError::Io {
+    let content = read_manifest(&lock_path).map_err(|source| DenoDiscoveryError::Io {
         path: lock_path.display().to_string(),
         source,
     })?;
@@ -115,7 +216,15 @@ fn collect_packages_from_deno_lock(
         })?;
 
     for package in collect_jsr_packages_from_lock(&json) {
-        insert_package(packages, package, "deno.lock");
+        insert_package(packages, PackageRef::Jsr(package), "deno.lock");
+    }
+    for package in collect_npm_packages_from_lock(&json) {
+        insert_package(packages, PackageRef::Npm(package), "deno.lock");
+    }
+    let mut modules = BTreeSet::new();
+    collect_specifiers(&json, parse_deno_land_module, &mut modules);
+    for module in modules {
+        insert_package(packages, PackageRef::DenoLand(module), "deno.lock");
     }
 
     Ok(())
@@ -124,7 +233,7 @@ fn collect_packages_from_deno_lock(
 fn collect_packages_from_deno_config(
     project_root: &Path,
     file_name: &str,
-    packages: &mut BTreeMap<String, String>,
+    packages: &mut BTreeMap<PackageRef, String>,
 ) -> Result<(), DenoDiscoveryError> {
     let config_path = project_root.join(file_name);
     if !config_path.exists() {
@@ -133,7 +242,17 @@ fn collect_packages_from_deno_config(
 
     let value = parse_jsonc_file(&config_path)?;
     for package in collect_jsr_packages_from_deno_config(&value) {
-        insert_package(packages, package, file_name);
+        insert_package(packages, PackageRef::Jsr(package), file_name);
+    }
+    let mut npm_packages = BTreeSet::new();
+    collect_specifiers(&value, parse_npm_specifier, &mut npm_packages);
+    for package in npm_packages {
+        insert_package(packages, PackageRef::Npm(package), file_name);
+    }
+    let mut modules = BTreeSet::new();
+    collect_specifiers(&value, parse_deno_land_module, &mut modules);
+    for module in modules {
+        insert_package(packages, PackageRef::DenoLand(module), file_name);
     }
 
     Ok(())
@@ -141,7 +260,7 @@ fn collect_packages_from_deno_config(
 
 fn collect_packages_from_jsr_manifest(
     project_root: &Path,
-    packages: &mut BTreeMap<String, String>,
+    packages: &mut BTreeMap<PackageRef, String>,
 ) -> Result<(), DenoDiscoveryError> {
     let manifest_path = project_root.join("jsr.json");
     if !manifest_path.exists() {
@@ -150,13 +269,13 @@ fn collect_packages_from_jsr_manifest(
 
     let value = parse_jsonc_file(&manifest_path)?;
     for package in collect_jsr_packages_from_jsr_manifest(&value) {
-        insert_package(packages, package, "jsr.json");
+        insert_package(packages, PackageRef::Jsr(package), "jsr.json");
     }
 
     Ok(())
 }
 
-fn insert_package(packages: &mut BTreeMap<String, String>, package: String, via: &str) {
+fn insert_package(packages: &mut BTreeMap<PackageRef, String>, package: PackageRef, via: &str) {
     packages.entry(package).or_insert_with(|| via.to_string());
 }
 
@@ -207,6 +326,60 @@ fn collect_jsr_packages_from_lock(value: &Value) -> BTreeSet<String> {
     packages
 }
 
+fn collect_npm_packages_from_lock(value: &Value) -> BTreeSet<String> {
+    let mut packages = BTreeSet::new();
+    collect_specifiers(value, parse_npm_specifier, &mut packages);
+
+    // Lockfile v3 nests resolved packages under `packages.npm`, v4 keeps them
+    // at the top level; both key them as `name@version` without the prefix.
+    for npm_packages in [
+        value.get("packages").and_then(|p| p.get("npm")),
+        value.get("npm"),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(Value::as_object)
+    {
+        for key in npm_packages.keys() {
+            if let Some(pkg) = parse_npm_specifier(&format!("npm:{key}")) {
+                packages.insert(pkg);
+            }
+        }
+    }
+
+    packages
+}
+
+/// Walk every key and string value in `value`, collecting whatever `parse`
+/// recognizes as a package reference.
+fn collect_specifiers(
+    value: &Value,
+    parse: fn(&str) -> Option<String>,
+    packages: &mut BTreeSet<String>,
+) {
+    match value {
+        Value::String(text) => {
+            if let Some(pkg) = parse(text) {
+                packages.insert(pkg);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_specifiers(item, parse, packages);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                if let Some(pkg) = parse(key) {
+                    packages.insert(pkg);
+                }
+                collect_specifiers(child, parse, packages);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn collect_jsr_packages_from_deno_config(value: &Value) -> BTreeSet<String> {
     let mut packages = BTreeSet::new();
     collect_import_specifiers(value, &mut packages);
@@ -215,7 +388,7 @@ fn collect_jsr_packages_from_deno_config(value: &Value) -> BTreeSet<String> {
 }
 
 fn parse_jsonc_file(path: &Path) -> Result<Value, DenoDiscoveryError> {
-    let content = fs::read_to_string(path).map_err(|source| DenoDiscoveryError::Io {
+    let content = read_manifest(path).map_err(|source| DenoDiscoveryError::Io {
         path: path.display().to_string(),
         source,
     })?;
@@ -237,6 +410,15 @@ mod tests {
     use tempfile::tempdir;
 
     use crate::ecosystems::jsr::HttpJsrClient;
+    use crate::ecosystems::npm::HttpNpmRegistryClient;
+
+    fn discoverer(server: &MockServer) -> DenoDiscoverer<HttpJsrClient> {
+        DenoDiscoverer::with_fetchers(
+            HttpJsrClient::with_base_url(server.base_url()),
+            HttpNpmRegistryClient::with_base_url(server.base_url()),
+            HttpDenoLandClient::with_base_url(server.base_url()),
+        )
+    }
 
     fn jsr_html(url: &str) -> String {
         format!(
@@ -286,8 +468,7 @@ mod tests {
                 .body(jsr_html("https://github.com/other/dep"));
         });
 
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let mut repos = discoverer.discover(dir.path()).unwrap();
         repos.sort_by(|a, b| a.name.cmp(&b.name));
 
@@ -298,6 +479,12 @@ mod tests {
         assert!(repos
             .iter()
             .all(|repo| repo.via.as_deref() == Some("deno.lock")));
+
+        let direct = discoverer
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+        assert!(direct.is_empty());
     }
 
     #[test]
@@ -322,8 +509,7 @@ mod tests {
             then.status(200).body("<html><body>No repo</body></html>");
         });
 
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let repos = discoverer.discover(dir.path()).unwrap();
         assert!(repos.is_empty());
     }
@@ -362,8 +548,7 @@ mod tests {
                 .body(jsr_html("https://github.com/types/testing"));
         });
 
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let mut repos = discoverer.discover(dir.path()).unwrap();
         repos.sort_by(|a, b| a.name.cmp(&b.name));
 
@@ -397,8 +582,7 @@ mod tests {
                 .body(jsr_html("https://github.com/jsonc/pkg"));
         });
 
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let repos = discoverer.discover(dir.path()).unwrap();
         assert_eq!(repos.len(), 1);
         assert_eq!(repos[0].name, "pkg");
@@ -449,8 +633,7 @@ mod tests {
                 .body(jsr_html("https://github.com/scope/helper"));
         });
 
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let mut repos = discoverer.discover(dir.path()).unwrap();
         repos.sort_by(|a, b| a.name.cmp(&b.name));
 
@@ -465,7 +648,116 @@ mod tests {
     }
 
     #[test]
-    fn ignores_non_jsr_entries() {
+    fn discovers_jsr_and_npm_packages_from_deno_lock() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("deno.lock"),
+            r#"{
+  "version": "3",
+  "packages": {
+    "specifiers": {
+      "jsr:@std/assert@^1": "jsr:@std/assert@1.0.0",
+      "npm:chalk@5": "npm:chalk@5.3.0",
+      "npm:@scope/tool": "npm:@scope/tool@2.0.0"
+    },
+    "npm": {
+      "chalk@5.3.0": { "dependencies": {} },
+      "@scope/tool@2.0.0": { "dependencies": {} }
+    }
+  }
+}"#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/%40std/assert");
+            then.status(200)
+                .body(jsr_html("https://github.com/denoland/std"));
+        });
+        let chalk = server.mock(|when, then| {
+            when.method(GET).path("/chalk/latest");
+            then.status(200).json_body(serde_json::json!({
+                "repository": "chalk/chalk"
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/@scope/tool/latest");
+            then.status(200).json_body(serde_json::json!({
+                "repository": { "url": "git+https://github.com/scope/tool.git" }
+            }));
+        });
+
+        let mut repos = discoverer(&server).discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        chalk.assert_calls(1);
+        assert_eq!(repos.len(), 3);
+        assert_eq!(repos[0].url, "https://github.com/chalk/chalk");
+        assert_eq!(repos[1].url, "https://github.com/denoland/std");
+        assert_eq!(repos[2].url, "https://github.com/scope/tool");
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("deno.lock")));
+    }
+
+    #[test]
+    fn discovers_npm_imports_from_deno_json() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("deno.json"),
+            r#"{ "imports": { "preact": "npm:preact@10/hooks" } }"#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/preact/latest");
+            then.status(200).json_body(serde_json::json!({
+                "repository": { "url": "https://github.com/preactjs/preact" }
+            }));
+        });
+
+        let repos = discoverer(&server).discover(dir.path()).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].name, "preact");
+        assert_eq!(repos[0].via.as_deref(), Some("deno.json"));
+    }
+
+    #[test]
+    fn discovers_deno_land_modules_from_deno_lock() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("deno.lock"),
+            r#"{
+  "version": "3",
+  "remote": {
+    "https://deno.land/x/oak@v12.6.1/mod.ts": "abc",
+    "https://deno.land/x/oak@v12.6.1/router.ts": "def",
+    "https://deno.land/std@0.200.0/path/mod.ts": "ghi"
+  }
+}"#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        let oak = server.mock(|when, then| {
+            when.method(GET).path("/v2/modules/oak");
+            then.status(200).json_body(serde_json::json!({
+                "upload_options": { "type": "github", "repository": "oakserver/oak" }
+            }));
+        });
+
+        let repos = discoverer(&server).discover(dir.path()).unwrap();
+
+        oak.assert_calls(1);
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/oakserver/oak");
+        assert_eq!(repos[0].via.as_deref(), Some("deno.lock"));
+    }
+
+    #[test]
+    fn skips_npm_and_deno_land_packages_unknown_to_the_registry() {
         let dir = tempdir().unwrap();
         fs::write(
             dir.path().join("deno.lock"),
@@ -482,8 +774,7 @@ mod tests {
         .unwrap();
 
         let server = MockServer::start();
-        let discoverer =
-            DenoDiscoverer::with_fetcher(HttpJsrClient::with_base_url(server.base_url()));
+        let discoverer = discoverer(&server);
         let repos = discoverer.discover(dir.path()).unwrap();
         assert!(repos.is_empty());
     }
@@ -0,0 +1,169 @@
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use url::Url;
+
+use crate::http;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DenoLandError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+pub trait DenoLandFetcher {
+    fn fetch_repository_url(&self, module: &str) -> Result<Option<String>, DenoLandError>;
+}
+
+#[derive(Clone)]
+pub struct HttpDenoLandClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for HttpDenoLandClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpDenoLandClient {
+    const DEFAULT_BASE_URL: &'static str = "https://apiland.deno.dev";
+
+    pub fn new() -> Self {
+        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+    }
+
+    fn with_client_and_base(client: Client, base_url: String) -> Self {
+        Self { client, base_url }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self::with_client_and_base(Client::new(), base_url.into())
+    }
+}
+
+impl DenoLandFetcher for HttpDenoLandClient {
+    fn fetch_repository_url(&self, module: &str) -> Result<Option<String>, DenoLandError> {
+        let base = self.base_url.trim_end_matches('/');
+        let url = format!("{base}/v2/modules/{module}");
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/json")
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(DenoLandError::UnexpectedStatus { status, url }),
+            _ => {
+                let module: DenoLandModule = serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| DenoLandError::Decode { source })?;
+                Ok(module.github_repository())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DenoLandModule {
+    #[serde(default)]
+    upload_options: Option<UploadOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadOptions {
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    repository: Option<String>,
+}
+
+impl DenoLandModule {
+    fn github_repository(self) -> Option<String> {
+        let options = self.upload_options?;
+        if options.kind.as_deref() != Some("github") {
+            return None;
+        }
+        options
+            .repository
+            .map(|repository| format!("https://github.com/{repository}"))
+    }
+}
+
+/// Extract the module name from a `https://deno.land/x/<module>@<version>/...`
+/// URL.
+pub fn parse_deno_land_module(specifier: &str) -> Option<String> {
+    let url = Url::parse(specifier.trim()).ok()?;
+    if url.host_str() != Some("deno.land") {
+        return None;
+    }
+    let mut segments = url.path_segments()?;
+    if segments.next() != Some("x") {
+        return None;
+    }
+    let module = segments.next()?.split('@').next()?.trim();
+    (!module.is_empty()).then(|| module.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn fetches_github_repository_from_upload_options() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/modules/oak");
+            then.status(200).json_body(json!({
+                "name": "oak",
+                "upload_options": {
+                    "type": "github",
+                    "repository": "oakserver/oak",
+                    "ref": "v12.6.1"
+                }
+            }));
+        });
+
+        let client = HttpDenoLandClient::with_base_url(server.base_url());
+        assert_eq!(
+            client.fetch_repository_url("oak").unwrap().as_deref(),
+            Some("https://github.com/oakserver/oak")
+        );
+    }
+
+    #[test]
+    fn parses_module_names_from_urls() {
+        assert_eq!(
+            parse_deno_land_module("https://deno.land/x/oak@v12.6.1/mod.ts"),
+            Some("oak".to_string())
+        );
+        assert_eq!(
+            parse_deno_land_module("https://deno.land/x/cliffy/command/mod.ts"),
+            Some("cliffy".to_string())
+        );
+        assert_eq!(
+            parse_deno_land_module("https://deno.land/std@0.200.0/path/mod.ts"),
+            None
+        );
+        assert_eq!(parse_deno_land_module("npm:chalk"), None);
+    }
+}
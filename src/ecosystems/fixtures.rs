@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+/// Directory of pre-downloaded registry metadata used instead of the network.
+pub const FIXTURES_ENV: &str = "THANKS_STARS_FIXTURES_DIR";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FixtureError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path} as JSON: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Answers registry lookups from `<dir>/<ecosystem>/<name>.json`, each file
+/// holding the document the registry would have returned. A missing file
+/// means the package does not exist.
+#[derive(Clone, Debug)]
+pub struct FileBackedFetcher {
+    dir: PathBuf,
+}
+
+impl FileBackedFetcher {
+    pub fn new(fixtures_dir: impl AsRef<Path>, ecosystem: &str) -> Self {
+        Self {
+            dir: fixtures_dir.as_ref().join(ecosystem),
+        }
+    }
+
+    /// A fetcher for `ecosystem` under `THANKS_STARS_FIXTURES_DIR`, if it is set.
+    pub fn from_env(ecosystem: &str) -> Option<Self> {
+        let dir = env::var_os(FIXTURES_ENV).filter(|dir| !dir.is_empty())?;
+        Some(Self::new(PathBuf::from(dir), ecosystem))
+    }
+
+    pub fn read<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, FixtureError> {
+        // Package names never contain separators; refuse to leave `dir`.
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Ok(None);
+        }
+        let path = self.dir.join(format!("{name}.json"));
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(FixtureError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|source| FixtureError::Json {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+}
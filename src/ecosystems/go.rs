@@ -1,8 +1,13 @@
-use std::collections::BTreeSet;
-use std::fs;
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use crate::discovery::{parse_github_repository, Repository};
+use tracing::{debug, trace};
+
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::text::read_manifest;
+
+const GO_MOD_FILE: &str = "go.mod";
+const VENDOR_MODULES_FILE: &str = "vendor/modules.txt";
 
 #[derive(Debug, thiserror::Error)]
 pub enum GoDiscoveryError {
@@ -15,28 +20,56 @@ pub enum GoDiscoveryError {
 }
 
 #[derive(Default)]
-pub struct GoDiscoverer;
+pub struct GoDiscoverer {
+    scope: DependencyScope,
+}
 
 impl GoDiscoverer {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// With [`DependencyScope::Direct`], skip the `// indirect` requirements
+    /// of `go.mod` and the modules `vendor/modules.txt` does not mark
+    /// `## explicit`.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, GoDiscoveryError> {
-        let go_mod_path = project_root.join("go.mod");
-        let content = fs::read_to_string(&go_mod_path).map_err(|err| GoDiscoveryError::Io {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, GoDiscoveryError> {
+        let go_mod_path = project_root.join(GO_MOD_FILE);
+        let content = read_manifest(&go_mod_path).map_err(|err| GoDiscoveryError::Io {
             path: go_mod_path.display().to_string(),
             source: err,
         })?;
 
-        let mut names = BTreeSet::new();
-        parse_requirements(&content, &mut names);
+        let mut names = BTreeMap::new();
+        parse_requirements(&content, self.scope, &mut names);
+        add_vendored_modules(project_root, self.scope, &mut names)?;
 
         let mut repositories = Vec::new();
-        for name in names {
-            if let Some(mut repository) = parse_go_module(&name) {
-                repository.via = Some("go.mod".to_string());
-                repositories.push(repository);
+        for (name, via) in names {
+            trace!(module = %name, "considering module");
+            stats.record_examined();
+            match parse_go_module(&name) {
+                Some(mut repository) => {
+                    debug!(module = %name, url = %repository.url, "resolved");
+                    repository.via = Some(via.to_string());
+                    repositories.push(repository);
+                }
+                None => {
+                    stats.record_unresolved(&name);
+                    debug!(module = %name, "skipping: not a github.com module path");
+                }
             }
         }
 
@@ -44,45 +77,116 @@ impl GoDiscoverer {
     }
 }
 
-fn parse_requirements(content: &str, names: &mut BTreeSet<String>) {
-    let mut in_block = false;
+/// Add the modules listed in `vendor/modules.txt`, which also names the
+/// indirect dependencies `go.mod` may leave out. Modules already required by
+/// `go.mod` keep it as their source.
+fn add_vendored_modules(
+    project_root: &Path,
+    scope: DependencyScope,
+    names: &mut BTreeMap<String, &'static str>,
+) -> Result<(), GoDiscoveryError> {
+    let path = project_root.join(VENDOR_MODULES_FILE);
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(GoDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+    for (name, explicit) in parse_vendored_modules(&content) {
+        if scope == DependencyScope::Direct && !explicit {
+            trace!(module = %name, "skipping: not explicit in vendor/modules.txt");
+            continue;
+        }
+        names.entry(name).or_insert(VENDOR_MODULES_FILE);
+    }
+    Ok(())
+}
+
+/// The module paths of the `# path version` lines of `vendor/modules.txt`,
+/// each with whether an `## explicit` marker follows it, i.e. whether
+/// `go.mod` requires the module. The package lines below each module are
+/// skipped.
+fn parse_vendored_modules(content: &str) -> Vec<(String, bool)> {
+    let mut modules: Vec<(String, bool)> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with("## explicit") {
+            if let Some((_, explicit)) = modules.last_mut() {
+                *explicit = true;
+            }
+        } else if let Some(name) = line.strip_prefix("# ").and_then(parse_module_name) {
+            modules.push((name, false));
+        }
+    }
+    modules
+}
+
+/// Collect the modules named by `require` directives, whether written on one
+/// line or as a `require ( ... )` block. Other directives (`exclude`,
+/// `retract`, `replace`, `tool`, `toolchain`, ...) and their blocks are
+/// ignored, as are `// indirect` requirements with
+/// [`DependencyScope::Direct`].
+fn parse_requirements(
+    content: &str,
+    scope: DependencyScope,
+    names: &mut BTreeMap<String, &'static str>,
+) {
+    // The directive whose `( ... )` block we are inside, if any.
+    let mut block: Option<&str> = None;
     for raw_line in content.lines() {
-        let line = raw_line.trim();
-        if line.starts_with("require (") {
-            in_block = true;
+        let line = strip_comment(raw_line);
+        if line.is_empty() {
+            continue;
+        }
+        if scope == DependencyScope::Direct && is_indirect(raw_line) {
+            trace!(line = %line, "skipping: indirect requirement");
             continue;
         }
 
-        if in_block {
+        if let Some(directive) = block {
             if line == ")" {
-                in_block = false;
-                continue;
-            }
-            if let Some(name) = parse_module_name(line) {
-                names.insert(name);
+                block = None;
+            } else if directive == "require" {
+                if let Some(name) = parse_module_name(line) {
+                    names.insert(name, GO_MOD_FILE);
+                }
             }
             continue;
         }
 
-        if line.starts_with("require ") {
-            if let Some(rest) = line.strip_prefix("require ") {
-                if let Some(name) = parse_module_name(rest) {
-                    names.insert(name);
-                }
+        let end = line
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(line.len());
+        let (directive, rest) = (&line[..end], line[end..].trim());
+        if rest == "(" {
+            block = Some(directive);
+        } else if directive == "require" {
+            if let Some(name) = parse_module_name(rest) {
+                names.insert(name, GO_MOD_FILE);
             }
         }
     }
 }
 
+fn is_indirect(line: &str) -> bool {
+    line.split_once("//")
+        .is_some_and(|(_, comment)| comment.trim().starts_with("indirect"))
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split("//").next().unwrap_or_default().trim()
+}
+
+/// The module path of a `path version` requirement; anything without a
+/// version is not a requirement.
 fn parse_module_name(line: &str) -> Option<String> {
-    let without_comment = line.split("//").next()?.trim();
-    if without_comment.is_empty() {
-        return None;
-    }
-    without_comment
-        .split_whitespace()
-        .next()
-        .map(|s| s.to_string())
+    let mut parts = strip_comment(line).split_whitespace();
+    let module = parts.next()?.trim_matches('"');
+    parts.next()?;
+    (!module.is_empty()).then(|| module.to_string())
 }
 
 fn parse_go_module(module: &str) -> Option<Repository> {
@@ -121,6 +225,106 @@ mod tests {
         assert!(owners.contains(&("pkg", "errors")));
     }
 
+    #[test]
+    fn reads_bom_prefixed_crlf_go_mod() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("go.mod"),
+            "\u{feff}module example.com/project\r\n\r\nrequire (\r\n\tgithub.com/pkg/errors v0.9.1\r\n)\r\n",
+        )
+        .unwrap();
+
+        let repos = GoDiscoverer::new().discover(dir.path()).unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/pkg/errors");
+    }
+
+    #[test]
+    fn adds_modules_from_vendor_modules_txt() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("go.mod"),
+            "module example.com/project\n\nrequire github.com/pkg/errors v0.9.1\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("vendor")).unwrap();
+        fs::write(
+            dir.path().join("vendor/modules.txt"),
+            "# github.com/pkg/errors v0.9.1\n\
+             ## explicit\n\
+             github.com/pkg/errors\n\
+             # github.com/davecgh/go-spew v1.1.1\n\
+             github.com/davecgh/go-spew/spew\n\
+             # github.com/stretchr/testify v1.9.0\n\
+             ## explicit; go 1.17\n\
+             github.com/stretchr/testify/assert\n\
+             # golang.org/x/sys v0.20.0\n\
+             golang.org/x/sys/unix\n",
+        )
+        .unwrap();
+
+        let stats = DiscoveryStats::default();
+        let repos = GoDiscoverer::new()
+            .discover_with_stats(dir.path(), &stats)
+            .unwrap();
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.url.as_str(), repo.via.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("https://github.com/davecgh/go-spew", "vendor/modules.txt"),
+                ("https://github.com/pkg/errors", "go.mod"),
+                ("https://github.com/stretchr/testify", "vendor/modules.txt"),
+            ]
+        );
+        assert_eq!(stats.unresolved(), 1);
+    }
+
+    #[test]
+    fn direct_scope_skips_indirect_and_implicitly_vendored_modules() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("go.mod"),
+            "module example.com/project\n\n\
+             require (\n\
+             \tgithub.com/pkg/errors v0.9.1\n\
+             \tgithub.com/stretchr/testify v1.9.0 // indirect\n\
+             )\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("vendor")).unwrap();
+        fs::write(
+            dir.path().join("vendor/modules.txt"),
+            "# github.com/pkg/errors v0.9.1\n\
+             ## explicit\n\
+             github.com/pkg/errors\n\
+             # github.com/davecgh/go-spew v1.1.1\n\
+             github.com/davecgh/go-spew/spew\n\
+             # github.com/google/uuid v1.6.0\n\
+             ## explicit; go 1.17\n\
+             github.com/google/uuid\n",
+        )
+        .unwrap();
+
+        let repos = GoDiscoverer::new()
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+
+        let urls: Vec<_> = repos.iter().map(|repo| repo.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://github.com/google/uuid",
+                "https://github.com/pkg/errors",
+            ]
+        );
+    }
+
     #[test]
     fn skips_non_github_modules() {
         let dir = tempdir().unwrap();
@@ -135,4 +339,47 @@ mod tests {
 
         assert!(repos.is_empty());
     }
+
+    #[test]
+    fn ignores_other_directives_and_comments() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("go.mod"),
+            "\u{feff}module example.com/project // the module\n\
+             \n\
+             go 1.22\n\
+             toolchain go1.22.3\n\
+             \n\
+             require github.com/pkg/errors v0.9.1 // indirect\n\
+             \n\
+             exclude (\n\
+             \tgithub.com/excluded/one v1.0.0\n\
+             )\n\
+             exclude github.com/excluded/two v1.0.0\n\
+             \n\
+             require(\n\
+             \t// github.com/commented/out v1.0.0\n\
+             \tgithub.com/stretchr/testify v1.9.0 // indirect\n\
+             \t\"github.com/quoted/module\" v1.0.0\n\
+             )\n\
+             \n\
+             retract [v1.0.0, v1.0.5] // broken releases\n\
+             retract (\n\
+             \tv0.9.0\n\
+             )\n\
+             replace github.com/replaced/module => github.com/fork/module v1.2.3\n\
+             tool github.com/tools/generator\n",
+        )
+        .unwrap();
+
+        let discoverer = GoDiscoverer::new();
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let mut names: Vec<_> = repos
+            .iter()
+            .map(|repo| format!("{}/{}", repo.owner, repo.name))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["pkg/errors", "quoted/module", "stretchr/testify"]);
+    }
 }
@@ -1,11 +1,13 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs;
+use std::fmt;
 use std::path::Path;
 
 use regex::Regex;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
-use crate::ecosystems::maven::{HttpMavenClient, MavenDependencyError, MavenFetcher};
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::maven::{HttpMavenClient, MavenDependencyError, MavenFetcher, MavenProject};
+use crate::ecosystems::text::read_manifest;
 
 #[derive(Debug, thiserror::Error)]
 pub enum GradleDiscoveryError {
@@ -15,6 +17,12 @@ pub enum GradleDiscoveryError {
         #[source]
         source: std::io::Error,
     },
+    #[error("failed to parse {path}: {source}")]
+    Toml {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
     #[error("failed to fetch metadata for {0}")]
     Maven(#[from] Box<MavenDependencyError>),
 }
@@ -26,10 +34,21 @@ struct GradleCoordinate {
     version: String,
 }
 
+impl fmt::Display for GradleCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)
+    }
+}
+
 type DependencyMap = BTreeMap<GradleCoordinate, BTreeSet<String>>;
 
+const VERSION_CATALOG: &str = "gradle/libs.versions.toml";
+
 pub struct GradleDiscoverer<F: MavenFetcher> {
     fetcher: F,
+    /// Where plugin marker POMs are looked up; `None` uses `fetcher`.
+    plugin_fetcher: Option<F>,
+    scope: DependencyScope,
 }
 
 impl Default for GradleDiscoverer<HttpMavenClient> {
@@ -42,55 +61,124 @@ impl GradleDiscoverer<HttpMavenClient> {
     pub fn new() -> Self {
         Self {
             fetcher: HttpMavenClient::new(),
+            plugin_fetcher: Some(HttpMavenClient::gradle_plugin_portal()),
+            scope: DependencyScope::All,
         }
     }
 }
 
 impl<F: MavenFetcher> GradleDiscoverer<F> {
     pub fn with_fetcher(fetcher: F) -> Self {
-        Self { fetcher }
+        Self {
+            fetcher,
+            plugin_fetcher: None,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// With [`DependencyScope::Direct`], skip `gradle.lockfile`.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Look up version catalog plugins through `fetcher` instead of the
+    /// library fetcher.
+    pub fn with_plugin_fetcher(mut self, fetcher: F) -> Self {
+        self.plugin_fetcher = Some(fetcher);
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, GradleDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, GradleDiscoveryError> {
         let mut dependencies: DependencyMap = BTreeMap::new();
+        let mut plugins: DependencyMap = BTreeMap::new();
 
-        collect_lockfile_dependencies(project_root, &mut dependencies)?;
+        if self.scope == DependencyScope::All {
+            collect_lockfile_dependencies(project_root, &mut dependencies)?;
+        }
         collect_build_dependencies(project_root, "build.gradle", &mut dependencies)?;
         collect_build_dependencies(project_root, "build.gradle.kts", &mut dependencies)?;
+        collect_version_catalog(project_root, &mut dependencies, &mut plugins)?;
 
         let mut repositories = Vec::new();
+        resolve(&self.fetcher, dependencies, stats, &mut repositories)?;
+        let plugin_fetcher = self.plugin_fetcher.as_ref().unwrap_or(&self.fetcher);
+        resolve(plugin_fetcher, plugins, stats, &mut repositories)?;
 
-        for (coord, vias) in dependencies {
-            let Some(project) = self
-                .fetcher
-                .fetch(&coord.group, &coord.artifact, &coord.version)
-                .map_err(|source| {
-                    GradleDiscoveryError::Maven(Box::new(MavenDependencyError {
-                        group: coord.group.clone(),
-                        artifact: coord.artifact.clone(),
-                        version: coord.version.clone(),
-                        source,
-                    }))
-                })?
-            else {
-                continue;
-            };
+        Ok(repositories)
+    }
+}
 
-            for url in project.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(&url) {
-                    if let Some(via) = vias.iter().next() {
-                        repository.via = Some(via.clone());
-                    } else {
-                        repository.via = Some("Gradle".to_string());
-                    }
-                    repositories.push(repository);
-                    break;
+fn resolve<F: MavenFetcher>(
+    fetcher: &F,
+    dependencies: DependencyMap,
+    stats: &DiscoveryStats,
+    repositories: &mut Vec<Repository>,
+) -> Result<(), GradleDiscoveryError> {
+    for (coord, vias) in dependencies {
+        trace!(dependency = %coord, "considering dependency");
+        stats.record_examined();
+        let Some(mut project) = fetch(fetcher, &coord.group, &coord.artifact, &coord.version)?
+        else {
+            stats.record_unresolved(&coord);
+            debug!(dependency = %coord, "skipping: POM not found");
+            continue;
+        };
+        // A plugin marker POM usually has no URL of its own, only a
+        // dependency on the artifact that implements the plugin.
+        if project.candidate_urls().is_empty() {
+            if let Some((group, artifact, version)) = project.marker_target() {
+                debug!(dependency = %coord, "following plugin marker to {group}:{artifact}:{version}");
+                if let Some(target) = fetch(fetcher, group, artifact, version)? {
+                    project = target;
                 }
             }
         }
 
-        Ok(repositories)
+        let Some(mut repository) = project
+            .candidate_urls()
+            .iter()
+            .find_map(|url| parse_github_repository(url))
+        else {
+            stats.record_unresolved(&coord);
+            debug!(dependency = %coord, "skipping: no GitHub URL in POM");
+            continue;
+        };
+        debug!(dependency = %coord, url = %repository.url, "resolved");
+        if vias.is_empty() {
+            repository.via = Some("Gradle".to_string());
+        }
+        for via in vias {
+            repository.add_via(via);
+        }
+        repositories.push(repository);
     }
+
+    Ok(())
+}
+
+fn fetch<F: MavenFetcher>(
+    fetcher: &F,
+    group: &str,
+    artifact: &str,
+    version: &str,
+) -> Result<Option<MavenProject>, GradleDiscoveryError> {
+    fetcher.fetch(group, artifact, version).map_err(|source| {
+        GradleDiscoveryError::Maven(Box::new(MavenDependencyError {
+            group: group.to_string(),
+            artifact: artifact.to_string(),
+            version: version.to_string(),
+            source,
+        }))
+    })
 }
 
 fn collect_lockfile_dependencies(
@@ -98,7 +186,7 @@ fn collect_lockfile_dependencies(
     dependencies: &mut DependencyMap,
 ) -> Result<(), GradleDiscoveryError> {
     let path = project_root.join("gradle.lockfile");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -138,7 +226,7 @@ fn collect_build_dependencies(
     dependencies: &mut DependencyMap,
 ) -> Result<(), GradleDiscoveryError> {
     let path = project_root.join(filename);
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -162,6 +250,136 @@ fn collect_build_dependencies(
     Ok(())
 }
 
+/// Reads `gradle/libs.versions.toml`: `[libraries]` and the aliases listed
+/// in `[bundles]` go into `dependencies`, and each `[plugins]` entry is
+/// turned into its plugin marker coordinate (`<id>:<id>.gradle.plugin`) in
+/// `plugins`.
+fn collect_version_catalog(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+    plugins: &mut DependencyMap,
+) -> Result<(), GradleDiscoveryError> {
+    let path = project_root.join(VERSION_CATALOG);
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(GradleDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+    let catalog: toml::Table =
+        toml::from_str(&content).map_err(|source| GradleDiscoveryError::Toml {
+            path: path.display().to_string(),
+            source,
+        })?;
+    let via = "libs.versions.toml";
+    let section = |name: &str| catalog.get(name).and_then(toml::Value::as_table);
+    let versions = section("versions");
+
+    let mut libraries = BTreeMap::new();
+    for (alias, entry) in section("libraries").into_iter().flatten() {
+        let Some((group, artifact, version)) = catalog_library(entry, versions) else {
+            debug!(alias = %alias, "skipping catalog library without a version");
+            continue;
+        };
+        insert_dependency(dependencies, &group, &artifact, &version, via);
+        libraries.insert(normalize_alias(alias), (group, artifact, version));
+    }
+
+    for (bundle, aliases) in section("bundles").into_iter().flatten() {
+        for alias in aliases.as_array().into_iter().flatten() {
+            let Some(alias) = alias.as_str() else {
+                continue;
+            };
+            match libraries.get(&normalize_alias(alias)) {
+                Some((group, artifact, version)) => {
+                    insert_dependency(dependencies, group, artifact, version, via)
+                }
+                None => debug!(bundle = %bundle, alias = %alias, "skipping unknown bundle entry"),
+            }
+        }
+    }
+
+    for (alias, entry) in section("plugins").into_iter().flatten() {
+        let (id, version) = match entry {
+            toml::Value::String(notation) => match notation.split_once(':') {
+                Some((id, version)) => (id.to_string(), Some(version.to_string())),
+                None => (notation.clone(), None),
+            },
+            toml::Value::Table(table) => (
+                table
+                    .get("id")
+                    .and_then(toml::Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                table
+                    .get("version")
+                    .and_then(|version| catalog_version(version, versions)),
+            ),
+            _ => continue,
+        };
+        let Some(version) = version else {
+            debug!(alias = %alias, "skipping catalog plugin without a version");
+            continue;
+        };
+        insert_dependency(plugins, &id, &format!("{id}.gradle.plugin"), &version, via);
+    }
+
+    Ok(())
+}
+
+/// `group`, `artifact` and `version` of a `[libraries]` entry, written either
+/// as `"group:artifact:version"` or as a table with `module` (or `group` and
+/// `name`) and `version`.
+fn catalog_library(
+    entry: &toml::Value,
+    versions: Option<&toml::Table>,
+) -> Option<(String, String, String)> {
+    match entry {
+        toml::Value::String(notation) => {
+            let mut parts = notation.splitn(3, ':');
+            let (group, artifact, version) = (parts.next()?, parts.next()?, parts.next()?);
+            Some((group.to_string(), artifact.to_string(), version.to_string()))
+        }
+        toml::Value::Table(table) => {
+            let field = |name: &str| table.get(name).and_then(toml::Value::as_str);
+            let (group, artifact) = match field("module") {
+                Some(module) => module.split_once(':')?,
+                None => (field("group")?, field("name")?),
+            };
+            let version = catalog_version(table.get("version")?, versions)?;
+            Some((group.to_string(), artifact.to_string(), version))
+        }
+        _ => None,
+    }
+}
+
+/// A catalog version: a plain string, `{ ref = "..." }` into `[versions]`, or
+/// a rich version table, of which `strictly`, `require` or `prefer` is used.
+fn catalog_version(value: &toml::Value, versions: Option<&toml::Table>) -> Option<String> {
+    match value {
+        toml::Value::String(version) => Some(version.clone()),
+        toml::Value::Table(table) => {
+            if let Some(reference) = table.get("ref").and_then(toml::Value::as_str) {
+                return catalog_version(versions?.get(reference)?, None);
+            }
+            ["strictly", "require", "prefer"]
+                .iter()
+                .find_map(|key| table.get(*key).and_then(toml::Value::as_str))
+                .map(str::to_string)
+        }
+        _ => None,
+    }
+}
+
+/// Gradle treats `-`, `_` and `.` in catalog aliases as the same separator.
+fn normalize_alias(alias: &str) -> String {
+    alias.replace(['-', '_'], ".")
+}
+
 fn insert_dependency(
     dependencies: &mut DependencyMap,
     group: &str,
@@ -187,6 +405,7 @@ fn insert_dependency(
 mod tests {
     use super::*;
     use httpmock::prelude::*;
+    use std::fs;
     use tempfile::tempdir;
 
     #[test]
@@ -222,6 +441,119 @@ mod tests {
         assert_eq!(repos[0].owner, "example");
         assert_eq!(repos[0].name, "library");
         assert_eq!(repos[0].via.as_deref(), Some("gradle.lockfile"));
+
+        let direct = discoverer
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+        assert!(direct.is_empty());
+    }
+
+    #[test]
+    fn discovers_catalog_libraries_bundles_and_plugins() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("gradle")).unwrap();
+        fs::write(
+            dir.path().join(VERSION_CATALOG),
+            r#"
+            [versions]
+            okhttp = "4.12.0"
+
+            [libraries]
+            okhttp = { module = "com.squareup.okhttp3:okhttp", version.ref = "okhttp" }
+            logging = { group = "com.squareup.okhttp3", name = "logging-interceptor", version.ref = "okhttp" }
+            unversioned = { module = "com.example:bom-managed" }
+
+            [bundles]
+            network = ["okhttp", "logging", "missing"]
+
+            [plugins]
+            detekt = { id = "io.gitlab.arturbosch.detekt", version = "1.23.6" }
+            "#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        for path in [
+            "/com/squareup/okhttp3/okhttp/4.12.0/okhttp-4.12.0.pom",
+            "/com/squareup/okhttp3/logging-interceptor/4.12.0/logging-interceptor-4.12.0.pom",
+        ] {
+            server.mock(|when, then| {
+                when.method(GET).path(path);
+                then.status(200)
+                    .body("<project><url>https://github.com/square/okhttp</url></project>");
+            });
+        }
+        let marker = server.mock(|when, then| {
+            when.method(GET).path(
+                "/io/gitlab/arturbosch/detekt/io.gitlab.arturbosch.detekt.gradle.plugin/1.23.6/io.gitlab.arturbosch.detekt.gradle.plugin-1.23.6.pom",
+            );
+            then.status(200).body(
+                "<project><scm><url>https://github.com/detekt/detekt</url></scm></project>",
+            );
+        });
+
+        let stats = DiscoveryStats::default();
+        let discoverer =
+            GradleDiscoverer::with_fetcher(HttpMavenClient::with_base_url(server.base_url()));
+        let repos = discoverer.discover_with_stats(dir.path(), &stats).unwrap();
+
+        let urls: Vec<_> = repos.iter().map(|repo| repo.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://github.com/square/okhttp",
+                "https://github.com/square/okhttp",
+                "https://github.com/detekt/detekt",
+            ]
+        );
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("libs.versions.toml")));
+        assert_eq!(stats.examined(), 3);
+        marker.assert();
+    }
+
+    #[test]
+    fn follows_plugin_marker_to_the_implementation_pom() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("gradle")).unwrap();
+        fs::write(
+            dir.path().join(VERSION_CATALOG),
+            r#"
+            [plugins]
+            ktlint = { id = "org.jlleitschuh.gradle.ktlint", version = "12.1.0" }
+            "#,
+        )
+        .unwrap();
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path(
+                "/org/jlleitschuh/gradle/ktlint/org.jlleitschuh.gradle.ktlint.gradle.plugin/12.1.0/org.jlleitschuh.gradle.ktlint.gradle.plugin-12.1.0.pom",
+            );
+            then.status(200).body(
+                "<project><dependencies><dependency>\
+                 <groupId>org.jlleitschuh.gradle</groupId>\
+                 <artifactId>ktlint-gradle</artifactId>\
+                 <version>12.1.0</version>\
+                 </dependency></dependencies></project>",
+            );
+        });
+        let implementation = server.mock(|when, then| {
+            when.method(GET)
+                .path("/org/jlleitschuh/gradle/ktlint-gradle/12.1.0/ktlint-gradle-12.1.0.pom");
+            then.status(200)
+                .body("<project><url>https://github.com/JLLeitschuh/ktlint-gradle</url></project>");
+        });
+
+        let discoverer =
+            GradleDiscoverer::with_fetcher(HttpMavenClient::with_base_url(server.base_url()));
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/JLLeitschuh/ktlint-gradle");
+        implementation.assert();
     }
 
     #[test]
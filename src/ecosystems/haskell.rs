@@ -1,13 +1,15 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs;
 use std::path::Path;
 
 use reqwest::blocking::Client;
 use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
 use serde_yaml::Value as YamlValue;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::caching::CachingFetcher;
+use crate::ecosystems::text::{parse_yaml, read_manifest};
 use crate::http;
 
 #[derive(Debug, thiserror::Error)]
@@ -36,10 +38,21 @@ pub trait HackageFetcher {
     fn fetch(&self, name: &str) -> Result<Option<HackagePackage>, HackageError>;
 }
 
+/// Hackage lookups remembered by a default [`HaskellDiscoverer`], or shared by the
+/// discoverers of one run.
+pub type CachedHackageClient = CachingFetcher<HttpHackageClient, Option<HackagePackage>>;
+
+impl<F: HackageFetcher> HackageFetcher for CachingFetcher<F, Option<HackagePackage>> {
+    fn fetch(&self, name: &str) -> Result<Option<HackagePackage>, HackageError> {
+        self.get_or_fetch(name, |inner, name| inner.fetch(name))
+    }
+}
+
 #[derive(Clone)]
 pub struct HttpHackageClient {
     client: Client,
     base_url: String,
+    retries: u32,
 }
 
 impl Default for HttpHackageClient {
@@ -56,7 +69,25 @@ impl HttpHackageClient {
     }
 
     fn with_client_and_base(client: Client, base_url: String) -> Self {
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            retries: http::DEFAULT_RETRIES,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Retry transient failures (timeouts, connection errors, 5xx) up to
+    /// `retries` times before giving up.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     #[cfg(test)]
@@ -71,11 +102,13 @@ impl HackageFetcher for HttpHackageClient {
             "{}/{name}/{name}.cabal",
             self.base_url.trim_end_matches('/')
         );
-        let response = self.client.get(&url).header(ACCEPT, "text/plain").send()?;
+        let response = http::send_with_retry(self.retries, &http::ThreadSleeper, || {
+            self.client.get(&url).header(ACCEPT, "text/plain").send()
+        })?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(None),
-            status if !status.is_success() => Err(HackageError::UnexpectedStatus { status }),
+            status if !status.is_success() => Err(HackageError::UnexpectedStatus { status, url }),
             _ => {
                 let cabal = response.text()?;
                 Ok(Some(HackagePackage::from_cabal(&cabal)))
@@ -88,8 +121,8 @@ impl HackageFetcher for HttpHackageClient {
 pub enum HackageError {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
-    #[error("unexpected status {status}")]
-    UnexpectedStatus { status: StatusCode },
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
 }
 
 #[derive(Clone, Debug, Default)]
@@ -155,35 +188,58 @@ fn push_url(rest: &str, urls: &mut Vec<String>, seen: &mut BTreeSet<String>) {
 
 pub struct HaskellDiscoverer<F: HackageFetcher> {
     fetcher: F,
+    scope: DependencyScope,
 }
 
-impl Default for HaskellDiscoverer<HttpHackageClient> {
+impl Default for HaskellDiscoverer<CachedHackageClient> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl HaskellDiscoverer<HttpHackageClient> {
+impl HaskellDiscoverer<CachedHackageClient> {
     pub fn new() -> Self {
-        Self {
-            fetcher: HttpHackageClient::new(),
-        }
+        Self::with_fetcher(CachingFetcher::new(HttpHackageClient::new()))
     }
 }
 
 impl<F: HackageFetcher> HaskellDiscoverer<F> {
     pub fn with_fetcher(fetcher: F) -> Self {
-        Self { fetcher }
+        Self {
+            fetcher,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// With [`DependencyScope::Direct`], skip `cabal.project.freeze`.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, HaskellDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, HaskellDiscoveryError> {
         let mut dependencies: DependencyMap = BTreeMap::new();
+        let mut repositories = Vec::new();
 
         collect_package_yaml_dependencies(project_root, &mut dependencies)?;
         collect_cabal_dependencies(project_root, &mut dependencies)?;
+        collect_stack_yaml_dependencies(project_root, &mut dependencies, &mut repositories)?;
+        collect_cabal_project_dependencies(project_root, &mut dependencies, &mut repositories)?;
+        if self.scope == DependencyScope::All {
+            collect_freeze_dependencies(project_root, &mut dependencies)?;
+        }
 
-        let mut repositories = Vec::new();
         for (name, vias) in dependencies {
+            trace!(package = %name, "considering package");
+            stats.record_examined();
             let Some(package) =
                 self.fetcher
                     .fetch(&name)
@@ -192,20 +248,28 @@ impl<F: HackageFetcher> HaskellDiscoverer<F> {
                         source,
                     })?
             else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: not found on Hackage");
                 continue;
             };
 
-            for url in package.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(&url) {
-                    if let Some(via) = vias.iter().next() {
-                        repository.via = Some(via.clone());
-                    } else {
-                        repository.via = Some("Hackage".to_string());
-                    }
-                    repositories.push(repository);
-                    break;
-                }
+            let Some(mut repository) = package
+                .candidate_urls()
+                .iter()
+                .find_map(|url| parse_github_repository(url))
+            else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: no GitHub URL in cabal metadata");
+                continue;
+            };
+            debug!(package = %name, url = %repository.url, "resolved");
+            if vias.is_empty() {
+                repository.via = Some("Hackage".to_string());
             }
+            for via in vias {
+                repository.add_via(via);
+            }
+            repositories.push(repository);
         }
 
         Ok(repositories)
@@ -219,7 +283,7 @@ fn collect_package_yaml_dependencies(
     dependencies: &mut DependencyMap,
 ) -> Result<(), HaskellDiscoveryError> {
     let path = project_root.join("package.yaml");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -230,11 +294,10 @@ fn collect_package_yaml_dependencies(
         }
     };
 
-    let value: YamlValue =
-        serde_yaml::from_str(&content).map_err(|err| HaskellDiscoveryError::Yaml {
-            path: path.display().to_string(),
-            source: err,
-        })?;
+    let value = parse_yaml(&content).map_err(|err| HaskellDiscoveryError::Yaml {
+        path: path.display().to_string(),
+        source: err,
+    })?;
 
     if let Some(deps) = value.get("dependencies") {
         match deps {
@@ -302,7 +365,7 @@ fn collect_cabal_dependencies(
         if !is_cabal_file(&path) {
             continue;
         }
-        let content = fs::read_to_string(&path).map_err(|err| HaskellDiscoveryError::Io {
+        let content = read_manifest(&path).map_err(|err| HaskellDiscoveryError::Io {
             path: path.display().to_string(),
             source: err,
         })?;
@@ -318,6 +381,255 @@ fn collect_cabal_dependencies(
     Ok(())
 }
 
+/// Collect `extra-deps` from `stack.yaml`. Hackage entries such as
+/// `acme-missiles-0.3@sha256:...` are looked up by name; `git:`/`github:`
+/// entries are resolved directly.
+fn collect_stack_yaml_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+    repositories: &mut Vec<Repository>,
+) -> Result<(), HaskellDiscoveryError> {
+    let path = project_root.join("stack.yaml");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(HaskellDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let value = parse_yaml(&content).map_err(|err| HaskellDiscoveryError::Yaml {
+        path: path.display().to_string(),
+        source: err,
+    })?;
+
+    let Some(extra_deps) = value.get("extra-deps").and_then(|v| v.as_sequence()) else {
+        return Ok(());
+    };
+    for entry in extra_deps {
+        match entry {
+            YamlValue::String(value) => {
+                if let Some(name) = parse_package_identifier(value) {
+                    add_dependency(dependencies, &name, "stack.yaml");
+                }
+            }
+            YamlValue::Mapping(map) => {
+                let url = map
+                    .get("git")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .or_else(|| {
+                        map.get("github")
+                            .and_then(|v| v.as_str())
+                            .map(|repo| format!("https://github.com/{repo}"))
+                    });
+                if let Some(mut repository) = url.as_deref().and_then(parse_github_repository) {
+                    repository.via = Some("stack.yaml".to_string());
+                    repositories.push(repository);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect `source-repository-package` locations and the `.cabal` files of
+/// local `packages:` from `cabal.project`.
+fn collect_cabal_project_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+    repositories: &mut Vec<Repository>,
+) -> Result<(), HaskellDiscoveryError> {
+    let path = project_root.join("cabal.project");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(HaskellDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let project = parse_cabal_project(&content);
+    for location in project.locations {
+        if let Some(mut repository) = parse_github_repository(&location) {
+            repository.via = Some("cabal.project".to_string());
+            repositories.push(repository);
+        }
+    }
+
+    for package in project.packages {
+        let package_path = project_root.join(&package);
+        let cabal_files: Vec<_> = if is_cabal_file(&package_path) {
+            vec![package_path]
+        } else if package_path.is_dir() && !is_same_dir(&package_path, project_root) {
+            package_path
+                .read_dir()
+                .map(|entries| {
+                    entries
+                        .filter_map(Result::ok)
+                        .map(|entry| entry.path())
+                        .filter(|path| is_cabal_file(path))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            continue;
+        };
+        for cabal_file in cabal_files {
+            let content = read_manifest(&cabal_file).map_err(|err| HaskellDiscoveryError::Io {
+                path: cabal_file.display().to_string(),
+                source: err,
+            })?;
+            for dep in parse_cabal_dependencies(&content) {
+                add_dependency(dependencies, &dep, "cabal.project");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const FREEZE_FILE: &str = "cabal.project.freeze";
+
+/// Collect the packages pinned by the `constraints:` of
+/// `cabal.project.freeze`, which cover the full transitive dependency set.
+fn collect_freeze_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+) -> Result<(), HaskellDiscoveryError> {
+    let path = project_root.join(FREEZE_FILE);
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(HaskellDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    for name in parse_freeze_constraints(&content) {
+        add_dependency(dependencies, &name, FREEZE_FILE);
+    }
+
+    Ok(())
+}
+
+/// Package names in the `constraints:` field of a freeze file, such as
+/// `any.aeson ==2.0.3.0` or the flag assignment `aeson -cffi`. Qualified
+/// constraints like `foo:setup.Cabal ==3.6` only pin a build tool and are
+/// left out.
+fn parse_freeze_constraints(content: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.trim().strip_prefix("constraints:") else {
+            continue;
+        };
+        let mut buffer = rest.trim().to_string();
+        while let Some(next) = lines.peek() {
+            let next_trimmed = next.trim();
+            let is_indented = next.starts_with(' ') || next.starts_with('\t');
+            if next_trimmed.starts_with("--") {
+                lines.next();
+                continue;
+            }
+            if !is_indented && !next_trimmed.starts_with(',') {
+                break;
+            }
+            buffer.push_str(", ");
+            buffer.push_str(next_trimmed);
+            lines.next();
+        }
+        for entry in buffer.split(',') {
+            let Some(package) = entry.split_whitespace().next() else {
+                continue;
+            };
+            let package = package.strip_prefix("any.").unwrap_or(package);
+            if package.is_empty() || package.contains([':', '.']) {
+                continue;
+            }
+            names.insert(package.to_string());
+        }
+    }
+    names
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CabalProject {
+    packages: Vec<String>,
+    locations: Vec<String>,
+}
+
+fn parse_cabal_project(content: &str) -> CabalProject {
+    let mut project = CabalProject::default();
+    let mut in_packages = false;
+    let mut in_source_repo = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("--") {
+            continue;
+        }
+        let is_indented = line.starts_with(' ') || line.starts_with('\t');
+        if !is_indented {
+            in_packages = false;
+            in_source_repo = trimmed == "source-repository-package";
+            if let Some(rest) = trimmed.strip_prefix("packages:") {
+                in_packages = true;
+                project
+                    .packages
+                    .extend(rest.split_whitespace().map(str::to_string));
+            }
+            continue;
+        }
+        if in_packages {
+            project
+                .packages
+                .extend(trimmed.split_whitespace().map(str::to_string));
+        } else if in_source_repo {
+            if let Some(location) = trimmed.strip_prefix("location:") {
+                project.locations.push(location.trim().to_string());
+            }
+        }
+    }
+
+    project
+}
+
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Strip the version and any `@sha256:`/`@rev:` suffix from a Stack package
+/// identifier like `acme-missiles-0.3@rev:0`.
+fn parse_package_identifier(input: &str) -> Option<String> {
+    let identifier = input.split('@').next()?.trim();
+    if identifier.is_empty() || identifier.contains('/') || identifier.contains(':') {
+        return None;
+    }
+    match identifier.rsplit_once('-') {
+        Some((name, version))
+            if !name.is_empty() && version.chars().all(|c| c.is_ascii_digit() || c == '.') =>
+        {
+            Some(name.to_string())
+        }
+        _ => Some(identifier.to_string()),
+    }
+}
+
 fn is_cabal_file(path: &Path) -> bool {
     path.is_file()
         && path
@@ -414,6 +726,7 @@ fn add_dependency(map: &mut DependencyMap, name: &str, via: &str) {
 mod tests {
     use super::*;
     use std::collections::HashMap;
+    use std::fs;
     use tempfile::tempdir;
 
     struct MockHackageFetcher {
@@ -492,6 +805,101 @@ dependencies:
         }
     }
 
+    #[test]
+    fn discovers_hackage_and_git_extra_deps_from_stack_yaml() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("stack.yaml"),
+            r#"
+resolver: lts-22.0
+packages:
+  - .
+extra-deps:
+  - acme-missiles-0.3@sha256:2ba66a092a32593880a87fb00f3213762d7bca65a687d45965778deb8694c5d1,613
+  - text-2.1
+  - ./vendor/local-lib
+  - git: https://github.com/example/forked.git
+    commit: 0123456789abcdef
+  - github: owner/from-github
+    commit: fedcba9876543210
+"#,
+        )
+        .unwrap();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "acme-missiles".to_string(),
+            Some(HackagePackage {
+                urls: vec!["https://github.com/acme/missiles".to_string()],
+            }),
+        );
+        packages.insert(
+            "text".to_string(),
+            Some(HackagePackage {
+                urls: vec!["https://github.com/haskell/text".to_string()],
+            }),
+        );
+
+        let discoverer = HaskellDiscoverer::with_fetcher(MockHackageFetcher::new(packages));
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.owner.as_str(), repo.name.as_str(), repo.via.as_deref()))
+            .collect();
+        assert_eq!(found.len(), 4);
+        assert!(found.contains(&("acme", "missiles", Some("stack.yaml"))));
+        assert!(found.contains(&("haskell", "text", Some("stack.yaml"))));
+        assert!(found.contains(&("example", "forked", Some("stack.yaml"))));
+        assert!(found.contains(&("owner", "from-github", Some("stack.yaml"))));
+    }
+
+    #[test]
+    fn discovers_cabal_project_packages_and_source_repositories() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("cabal.project"),
+            r#"
+packages: ./
+          libs/core
+
+source-repository-package
+    type: git
+    location: https://github.com/example/patched
+    tag: 0123456789abcdef
+"#,
+        )
+        .unwrap();
+        let core = dir.path().join("libs/core");
+        fs::create_dir_all(&core).unwrap();
+        fs::write(
+            core.join("core.cabal"),
+            "name: core
+build-depends: text
+",
+        )
+        .unwrap();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "text".to_string(),
+            Some(HackagePackage {
+                urls: vec!["https://github.com/haskell/text".to_string()],
+            }),
+        );
+
+        let discoverer = HaskellDiscoverer::with_fetcher(MockHackageFetcher::new(packages));
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.owner.as_str(), repo.name.as_str(), repo.via.as_deref()))
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&("example", "patched", Some("cabal.project"))));
+        assert!(found.contains(&("haskell", "text", Some("cabal.project"))));
+    }
+
     #[test]
     fn discovers_dependencies_from_cabal_file() {
         let dir = tempdir().unwrap();
@@ -530,4 +938,65 @@ build-depends: text >= 1.2,
         assert!(owners.contains(&("haskell", "text", Some("example.cabal"))));
         assert!(owners.contains(&("haskell", "bytestring", Some("example.cabal"))));
     }
+
+    #[test]
+    fn discovers_pinned_dependencies_from_freeze_file() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("cabal.project.freeze"),
+            r#"active-repositories: hackage.haskell.org:merge
+constraints: any.aeson ==2.0.3.0,
+             aeson -cffi +ordered-keymap,
+             any.text ==2.0.2,
+             -- pinned for the custom setup
+             example:setup.Cabal ==3.6.3.0,
+             any.vector ==0.13.0.0
+index-state: hackage.haskell.org 2023-06-01T00:00:00Z
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_freeze_constraints(&fs::read_to_string(dir.path().join(FREEZE_FILE)).unwrap()),
+            BTreeSet::from(["aeson", "text", "vector"].map(str::to_string))
+        );
+
+        let mut packages = HashMap::new();
+        for (name, url) in [
+            ("aeson", "https://github.com/haskell/aeson"),
+            ("text", "https://github.com/haskell/text"),
+        ] {
+            packages.insert(
+                name.to_string(),
+                Some(HackagePackage {
+                    urls: vec![url.to_string()],
+                }),
+            );
+        }
+        packages.insert("vector".to_string(), None);
+
+        let stats = DiscoveryStats::default();
+        let discoverer = HaskellDiscoverer::with_fetcher(MockHackageFetcher::new(packages));
+        let repos = discoverer.discover_with_stats(dir.path(), &stats).unwrap();
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.owner.as_str(), repo.name.as_str(), repo.via.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("haskell", "aeson", Some("cabal.project.freeze")),
+                ("haskell", "text", Some("cabal.project.freeze")),
+            ]
+        );
+        assert_eq!(stats.examined(), 3);
+        assert_eq!(stats.unresolved(), 1);
+
+        let direct = HaskellDiscoverer::with_fetcher(MockHackageFetcher::new(HashMap::new()))
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+        assert!(direct.is_empty());
+    }
 }
@@ -4,7 +4,9 @@ use regex::Regex;
 use reqwest::blocking::Client;
 use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
+use serde::Deserialize;
 use serde_json::Value;
+use tracing::debug;
 
 use crate::http;
 
@@ -12,8 +14,13 @@ use crate::http;
 pub enum JsrError {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
-    #[error("unexpected status {status}")]
-    UnexpectedStatus { status: StatusCode },
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
 }
 
 pub trait JsrFetcher {
@@ -24,6 +31,7 @@ pub trait JsrFetcher {
 pub struct HttpJsrClient {
     client: Client,
     base_url: String,
+    api_base_url: String,
 }
 
 impl Default for HttpJsrClient {
@@ -34,48 +42,125 @@ impl Default for HttpJsrClient {
 
 impl HttpJsrClient {
     const DEFAULT_BASE_URL: &'static str = "https://jsr.io";
+    const DEFAULT_API_BASE_URL: &'static str = "https://api.jsr.io";
 
     pub fn new() -> Self {
-        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+        Self::with_client_and_base(
+            http::shared_client(),
+            Self::DEFAULT_BASE_URL.to_string(),
+            Self::DEFAULT_API_BASE_URL.to_string(),
+        )
+    }
+
+    fn with_client_and_base(client: Client, base_url: String, api_base_url: String) -> Self {
+        Self {
+            client,
+            base_url,
+            api_base_url,
+        }
     }
 
-    fn with_client_and_base(client: Client, base_url: String) -> Self {
-        Self { client, base_url }
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
     }
 
+    /// Point both the package pages and the JSON API at `base_url`.
     #[cfg(test)]
     pub fn with_base_url(base_url: impl Into<String>) -> Self {
-        Self::with_client_and_base(Client::new(), base_url.into())
+        let base_url = base_url.into();
+        Self::with_client_and_base(Client::new(), base_url.clone(), base_url)
     }
 
-    fn package_url(&self, package: &str) -> String {
-        let path = package.trim().trim_start_matches('/');
-        if let Some(stripped) = path.strip_prefix('@') {
-            format!("{}/%40{}", self.base_url.trim_end_matches('/'), stripped)
-        } else {
-            format!("{}/{}", self.base_url.trim_end_matches('/'), path)
+    /// Ask the JSR API for the package's linked GitHub repository. Returns
+    /// `None` for unscoped names, unknown packages, or packages without a link.
+    fn fetch_from_api(&self, package: &str) -> Result<Option<String>, JsrError> {
+        let Some((scope, name)) = package
+            .trim()
+            .strip_prefix('@')
+            .and_then(|rest| rest.split_once('/'))
+        else {
+            return Ok(None);
+        };
+        let url = format!(
+            "{}/scopes/{scope}/packages/{name}",
+            self.api_base_url.trim_end_matches('/')
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/json")
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(JsrError::UnexpectedStatus { status, url }),
+            _ => {
+                let package: JsrPackage = serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| JsrError::Decode { source })?;
+                Ok(package
+                    .github_repository
+                    .map(|repo| format!("https://github.com/{}/{}", repo.owner, repo.name)))
+            }
         }
     }
-}
 
-impl JsrFetcher for HttpJsrClient {
-    fn fetch_repository_url(&self, package: &str) -> Result<Option<String>, JsrError> {
+    fn fetch_from_html(&self, package: &str) -> Result<Option<String>, JsrError> {
         let url = self.package_url(package);
         let response = self
             .client
-            .get(url)
+            .get(&url)
             .header(ACCEPT, "text/html,application/xhtml+xml")
             .send()?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(None),
-            status if !status.is_success() => Err(JsrError::UnexpectedStatus { status }),
+            status if !status.is_success() => Err(JsrError::UnexpectedStatus { status, url }),
             _ => {
                 let body = response.text()?;
                 Ok(extract_github_repository(&body))
             }
         }
     }
+
+    fn package_url(&self, package: &str) -> String {
+        let path = package.trim().trim_start_matches('/');
+        if let Some(stripped) = path.strip_prefix('@') {
+            format!("{}/%40{}", self.base_url.trim_end_matches('/'), stripped)
+        } else {
+            format!("{}/{}", self.base_url.trim_end_matches('/'), path)
+        }
+    }
+}
+
+impl JsrFetcher for HttpJsrClient {
+    fn fetch_repository_url(&self, package: &str) -> Result<Option<String>, JsrError> {
+        // Older packages may only expose the link on their page, and the API
+        // can be down while the site is up, so fall back to scraping it.
+        match self.fetch_from_api(package) {
+            Ok(Some(url)) => return Ok(Some(url)),
+            Ok(None) => {}
+            Err(err) => {
+                debug!(%package, error = %err, "JSR API lookup failed; trying the package page")
+            }
+        }
+        self.fetch_from_html(package)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsrPackage {
+    #[serde(default)]
+    github_repository: Option<JsrGithubRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsrGithubRepository {
+    owner: String,
+    name: String,
 }
 
 pub fn parse_jsr_specifier(specifier: &str) -> Option<String> {
@@ -213,6 +298,68 @@ mod tests {
         )
     }
 
+    #[test]
+    fn fetches_repository_url_from_api() {
+        let server = MockServer::start();
+        let api = server.mock(|when, then| {
+            when.method(GET).path("/scopes/std/packages/assert");
+            then.status(200).json_body(serde_json::json!({
+                "scope": "std",
+                "name": "assert",
+                "githubRepository": { "owner": "denoland", "name": "std" }
+            }));
+        });
+        let html = server.mock(|when, then| {
+            when.method(GET).path("/%40std/assert");
+            then.status(200)
+                .body(jsr_html("https://github.com/wrong/place"));
+        });
+
+        let client = HttpJsrClient::with_base_url(server.base_url());
+        let repo = client.fetch_repository_url("@std/assert").unwrap().unwrap();
+        assert_eq!(repo, "https://github.com/denoland/std");
+        api.assert();
+        html.assert_calls(0);
+    }
+
+    #[test]
+    fn falls_back_to_html_when_api_has_no_repository() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/scopes/scope/packages/pkg");
+            then.status(200)
+                .json_body(serde_json::json!({ "githubRepository": null }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/%40scope/pkg");
+            then.status(200)
+                .body(jsr_html("https://github.com/scope/pkg"));
+        });
+
+        let client = HttpJsrClient::with_base_url(server.base_url());
+        let repo = client.fetch_repository_url("@scope/pkg").unwrap().unwrap();
+        assert_eq!(repo, "https://github.com/scope/pkg");
+    }
+
+    #[test]
+    fn falls_back_to_html_when_api_fails() {
+        let server = MockServer::start();
+        let api = server.mock(|when, then| {
+            when.method(GET).path("/scopes/scope/packages/pkg");
+            then.status(500);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/%40scope/pkg");
+            then.status(200)
+                .body(jsr_html("https://github.com/scope/pkg"));
+        });
+
+        let client = HttpJsrClient::with_base_url(server.base_url());
+        let repo = client.fetch_repository_url("@scope/pkg").unwrap().unwrap();
+        api.assert();
+        assert_eq!(repo, "https://github.com/scope/pkg");
+    }
+
     #[test]
     fn fetches_repository_url() {
         let server = MockServer::start();
@@ -1,3 +1,4 @@
+pub mod caching;
 #[cfg(feature = "ecosystem-cargo")]
 pub mod cargo;
 #[cfg(feature = "ecosystem-composer")]
@@ -6,6 +7,9 @@ pub mod composer;
 pub mod dart;
 #[cfg(feature = "ecosystem-deno")]
 pub mod deno;
+#[cfg(feature = "ecosystem-deno")]
+pub mod deno_land;
+pub mod fixtures;
 #[cfg(feature = "ecosystem-go")]
 pub mod go;
 #[cfg(feature = "ecosystem-gradle")]
@@ -18,41 +22,71 @@ pub mod jsr;
 pub mod maven;
 #[cfg(feature = "ecosystem-node")]
 pub mod node;
+#[cfg(feature = "ecosystem-npm")]
+pub mod npm;
 #[cfg(feature = "ecosystem-python")]
 pub mod python;
 #[cfg(feature = "ecosystem-renv")]
 pub mod renv;
 #[cfg(feature = "ecosystem-ruby")]
 pub mod ruby;
+#[cfg(feature = "ecosystem-sbom")]
+pub mod sbom;
+mod text;
 
+pub use caching::CachingFetcher;
 #[cfg(feature = "ecosystem-cargo")]
-pub use cargo::{CargoDiscoverer, CargoDiscoveryError, CommandMetadataFetcher, MetadataFetcher};
+pub use cargo::{
+    CargoDiscoverer, CargoDiscoveryError, CargoManifestDiscoverer, CommandMetadataFetcher,
+    CratesIoError, CratesIoFetcher, HttpCratesIoClient, MetadataFetcher, CARGO_BIN_ENV,
+};
 #[cfg(feature = "ecosystem-composer")]
-pub use composer::{ComposerDiscoverer, ComposerDiscoveryError};
+pub use composer::{
+    ComposerDiscoverer, ComposerDiscoveryError, HttpPackagistClient, PackagistError,
+    PackagistFetcher,
+};
 #[cfg(feature = "ecosystem-dart")]
-pub use dart::{DartDiscoverer, DartDiscoveryError, HttpPubDevClient, PubDevFetcher};
+pub use dart::{
+    CachedPubDevClient, DartDiscoverer, DartDiscoveryError, HttpPubDevClient, PubDevFetcher,
+};
 #[cfg(feature = "ecosystem-deno")]
 pub use deno::{DenoDiscoverer, DenoDiscoveryError};
+#[cfg(feature = "ecosystem-deno")]
+pub use deno_land::{DenoLandError, DenoLandFetcher, HttpDenoLandClient};
+pub use fixtures::{FileBackedFetcher, FixtureError, FIXTURES_ENV};
 #[cfg(feature = "ecosystem-go")]
 pub use go::{GoDiscoverer, GoDiscoveryError};
 #[cfg(feature = "ecosystem-gradle")]
 pub use gradle::{GradleDiscoverer, GradleDiscoveryError};
 #[cfg(feature = "ecosystem-haskell")]
 pub use haskell::{
-    HackageError, HackageFetcher, HaskellDiscoverer, HaskellDiscoveryError, HttpHackageClient,
+    CachedHackageClient, HackageError, HackageFetcher, HaskellDiscoverer, HaskellDiscoveryError,
+    HttpHackageClient,
 };
 #[cfg(feature = "ecosystem-jsr")]
 pub use jsr::{HttpJsrClient, JsrError, JsrFetcher};
 #[cfg(feature = "ecosystem-maven")]
 pub use maven::{
-    HttpMavenClient, MavenDependencyError, MavenDiscoverer, MavenDiscoveryError, MavenError,
-    MavenFetcher, MavenProject,
+    configured_repositories as configured_maven_repositories,
+    user_settings_path as maven_user_settings_path, CachedMavenClient, HttpMavenClient,
+    MavenDependencyError, MavenDiscoverer, MavenDiscoveryError, MavenError, MavenFetcher,
+    MavenProject, MavenRepositoryChain, MAVEN_REPOSITORY_ENV,
 };
 #[cfg(feature = "ecosystem-node")]
-pub use node::{NodeDiscoverer, NodeDiscoveryError};
+pub use node::{NodeDiscoverer, NodeDiscoveryError, NPM_REGISTRY_FALLBACK_ENV};
+#[cfg(feature = "ecosystem-npm")]
+pub use npm::{
+    CachedNpmRegistryClient, HttpNpmRegistryClient, NpmRegistryError, NpmRegistryFetcher,
+};
 #[cfg(feature = "ecosystem-python")]
-pub use python::{HttpPyPiClient, PyPiFetcher, PythonDiscoverer, PythonDiscoveryError};
+pub use python::{
+    CachedPyPiClient, HttpPyPiClient, PyPiFetcher, PythonDiscoverer, PythonDiscoveryError,
+};
 #[cfg(feature = "ecosystem-renv")]
-pub use renv::{RenvDiscoverer, RenvDiscoveryError};
+pub use renv::{
+    HttpRUniverseClient, RUniverseError, RUniverseFetcher, RenvDiscoverer, RenvDiscoveryError,
+};
 #[cfg(feature = "ecosystem-ruby")]
-pub use ruby::{HttpRubyGemsClient, RubyDiscoverer, RubyDiscoveryError};
+pub use ruby::{CachedRubyGemsClient, HttpRubyGemsClient, RubyDiscoverer, RubyDiscoveryError};
+#[cfg(feature = "ecosystem-sbom")]
+pub use sbom::{SbomDiscoverer, SbomDiscoveryError};
@@ -1,10 +1,17 @@
 use std::collections::BTreeSet;
-use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde_json::Value;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DiscoveryStats, Repository};
+use crate::ecosystems::caching::CachingFetcher;
+use crate::ecosystems::npm::{HttpNpmRegistryClient, NpmRegistryError, NpmRegistryFetcher};
+use crate::ecosystems::text::read_manifest;
+
+/// Set to any non-empty value to look up installed packages without a
+/// `repository` in the npm registry.
+pub const NPM_REGISTRY_FALLBACK_ENV: &str = "THANKS_STARS_NPM_REGISTRY_FALLBACK";
 
 #[derive(Debug, thiserror::Error)]
 pub enum NodeDiscoveryError {
@@ -22,15 +29,65 @@ pub enum NodeDiscoveryError {
     },
 }
 
-#[derive(Default)]
-pub struct NodeDiscoverer;
+pub struct NodeDiscoverer {
+    registry: Option<Box<dyn NpmRegistryFetcher>>,
+}
+
+impl Default for NodeDiscoverer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl NodeDiscoverer {
+    /// Only read what is installed in `node_modules`.
     pub fn new() -> Self {
-        Self
+        Self { registry: None }
+    }
+
+    /// Ask registry.npmjs.org for installed packages whose `package.json`
+    /// names no repository.
+    pub fn with_registry_fallback() -> Self {
+        Self::with_registry(CachingFetcher::new(HttpNpmRegistryClient::new()))
+    }
+
+    /// [`with_registry_fallback`](Self::with_registry_fallback) if
+    /// [`NPM_REGISTRY_FALLBACK_ENV`] is set, [`new`](Self::new) otherwise.
+    pub fn from_env() -> Self {
+        if registry_fallback_enabled() {
+            Self::with_registry_fallback()
+        } else {
+            Self::new()
+        }
+    }
+
+    /// [`with_registry`](Self::with_registry) if [`NPM_REGISTRY_FALLBACK_ENV`]
+    /// is set, [`new`](Self::new) otherwise.
+    pub fn from_env_with_registry(registry: impl NpmRegistryFetcher + 'static) -> Self {
+        if registry_fallback_enabled() {
+            Self::with_registry(registry)
+        } else {
+            Self::new()
+        }
+    }
+
+    /// Fall back to `registry` for installed packages whose `package.json`
+    /// names no repository.
+    pub fn with_registry(registry: impl NpmRegistryFetcher + 'static) -> Self {
+        Self {
+            registry: Some(Box::new(registry)),
+        }
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, NodeDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, NodeDiscoveryError> {
         let package_json_path = project_root.join("package.json");
         let package_json = read_json(&package_json_path)?;
 
@@ -40,21 +97,75 @@ impl NodeDiscoverer {
 
         let mut repositories = Vec::new();
         for name in names {
+            trace!(dependency = %name, "considering dependency");
+            stats.record_examined();
             let package_path = dependency_package_path(project_root, &name);
             let dependency_json = match read_json(&package_path) {
                 Ok(value) => value,
-                Err(_) => continue,
+                Err(_) => {
+                    stats.record_unresolved(&name);
+                    debug!(dependency = %name, "skipping: not installed in node_modules");
+                    continue;
+                }
+            };
+            let found = match repository_from_package(&dependency_json) {
+                Some(found) => Some(found),
+                None => match self.repository_from_registry(&name) {
+                    Ok(found) => found,
+                    Err(err) => {
+                        stats.record_unresolved(&name);
+                        stats.record_warning(format!(
+                            "npm registry lookup for {name} failed: {err}"
+                        ));
+                        continue;
+                    }
+                },
+            };
+            let Some((repo, directory)) = found else {
+                stats.record_unresolved(&name);
+                debug!(dependency = %name, "skipping: no repository in package.json");
+                continue;
             };
-            if let Some(repo) = repository_from_package(&dependency_json) {
-                if let Some(mut repository) = parse_github_repository(&repo) {
+            match parse_github_repository(&repo) {
+                Some(mut repository) => {
+                    debug!(dependency = %name, url = %repository.url, "resolved");
                     repository.via = Some("package.json".to_string());
+                    repository.directory = directory;
                     repositories.push(repository);
                 }
+                None => {
+                    stats.record_unresolved(&name);
+                    debug!(dependency = %name, url = %repo, "skipping: not a GitHub URL");
+                }
             }
         }
 
         Ok(repositories)
     }
+
+    fn repository_from_registry(
+        &self,
+        name: &str,
+    ) -> Result<Option<(String, Option<String>)>, NpmRegistryError> {
+        let Some(registry) = &self.registry else {
+            return Ok(None);
+        };
+        Ok(registry.fetch_repository_url(name)?.map(|url| (url, None)))
+    }
+}
+
+/// The GitHub repository `package.json` in `project_root` declares for the
+/// package itself.
+pub fn own_repository(project_root: &Path) -> Option<Repository> {
+    let package_json = read_json(&project_root.join("package.json")).ok()?;
+    let (url, directory) = repository_from_package(&package_json)?;
+    let mut repository = parse_github_repository(&url)?;
+    repository.directory = directory;
+    Some(repository)
+}
+
+fn registry_fallback_enabled() -> bool {
+    std::env::var(NPM_REGISTRY_FALLBACK_ENV).is_ok_and(|value| !value.is_empty())
 }
 
 fn add_dependency_names(target: &mut BTreeSet<String>, package_json: &Value, key: &str) {
@@ -73,26 +184,33 @@ fn dependency_package_path(root: &Path, name: &str) -> PathBuf {
     path.join("package.json")
 }
 
-fn repository_from_package(package: &Value) -> Option<String> {
+/// The repository URL of a package, with the monorepo `directory` it lives
+/// in when the object form of `repository` gives one.
+fn repository_from_package(package: &Value) -> Option<(String, Option<String>)> {
     if let Some(repo) = package.get("repository") {
         match repo {
-            Value::String(value) => return Some(value.clone()),
+            Value::String(value) => return Some((value.clone(), None)),
             Value::Object(map) => {
                 if let Some(Value::String(url)) = map.get("url") {
-                    return Some(url.clone());
+                    let directory = map
+                        .get("directory")
+                        .and_then(Value::as_str)
+                        .filter(|directory| !directory.trim().is_empty())
+                        .map(str::to_string);
+                    return Some((url.clone(), directory));
                 }
             }
             _ => {}
         }
     }
     if let Some(Value::String(homepage)) = package.get("homepage") {
-        return Some(homepage.clone());
+        return Some((homepage.clone(), None));
     }
     None
 }
 
 fn read_json(path: &Path) -> Result<Value, NodeDiscoveryError> {
-    let content = fs::read_to_string(path).map_err(|err| NodeDiscoveryError::Io {
+    let content = read_manifest(path).map_err(|err| NodeDiscoveryError::Io {
         path: path.display().to_string(),
         source: err,
     })?;
@@ -105,6 +223,7 @@ fn read_json(path: &Path) -> Result<Value, NodeDiscoveryError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use httpmock::prelude::*;
     use serde_json::json;
     use std::fs;
     use tempfile::tempdir;
@@ -150,6 +269,121 @@ mod tests {
         assert_eq!(repos[1].name, "pkg");
     }
 
+    #[test]
+    fn keeps_repository_directory_and_git_protocol_urls() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            json!({ "dependencies": { "@babel/core": "^7.0.0", "legacy": "^1.0.0" } }).to_string(),
+        )
+        .unwrap();
+        for (name, repository) in [
+            (
+                "@babel/core",
+                json!({
+                    "type": "git",
+                    "url": "https://github.com/babel/babel.git",
+                    "directory": "packages/babel-core"
+                }),
+            ),
+            ("legacy", json!("git://github.com/example/legacy.git")),
+        ] {
+            let package_dir = dir.path().join("node_modules").join(name);
+            fs::create_dir_all(&package_dir).unwrap();
+            fs::write(
+                package_dir.join("package.json"),
+                json!({ "repository": repository }).to_string(),
+            )
+            .unwrap();
+        }
+
+        let mut repos = NodeDiscoverer::new().discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(repos[0].url, "https://github.com/babel/babel");
+        assert_eq!(
+            repos[0].subdirectory().as_deref(),
+            Some("packages/babel-core")
+        );
+        assert_eq!(repos[1].url, "https://github.com/example/legacy");
+        assert_eq!(repos[1].subdirectory(), None);
+    }
+
+    #[test]
+    fn falls_back_to_the_registry_when_package_json_has_no_repository() {
+        let server = MockServer::start();
+        let lookup = server.mock(|when, then| {
+            when.method(GET).path("/bare/latest");
+            then.status(200).json_body(json!({
+                "name": "bare",
+                "repository": { "type": "git", "url": "git+https://github.com/example/bare.git" }
+            }));
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            json!({ "dependencies": { "bare": "^1.0.0" } }).to_string(),
+        )
+        .unwrap();
+        let package_dir = dir.path().join("node_modules/bare");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join("package.json"),
+            json!({ "name": "bare", "version": "1.0.0" }).to_string(),
+        )
+        .unwrap();
+
+        assert!(NodeDiscoverer::new()
+            .discover(dir.path())
+            .unwrap()
+            .is_empty());
+
+        let registry = CachingFetcher::new(HttpNpmRegistryClient::with_base_url(server.base_url()));
+        let discoverer = NodeDiscoverer::with_registry(registry);
+        let repos = discoverer.discover(dir.path()).unwrap();
+        discoverer.discover(dir.path()).unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].url, "https://github.com/example/bare");
+        assert_eq!(repos[0].via.as_deref(), Some("package.json"));
+        lookup.assert_calls(1);
+    }
+
+    #[test]
+    fn reports_failed_registry_lookups_as_unresolved() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/bare/latest");
+            then.status(500);
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            json!({ "dependencies": { "bare": "^1.0.0" } }).to_string(),
+        )
+        .unwrap();
+        let package_dir = dir.path().join("node_modules/bare");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join("package.json"),
+            json!({ "name": "bare", "version": "1.0.0" }).to_string(),
+        )
+        .unwrap();
+
+        let discoverer =
+            NodeDiscoverer::with_registry(HttpNpmRegistryClient::with_base_url(server.base_url()));
+        let stats = DiscoveryStats::default();
+        let repos = discoverer.discover_with_stats(dir.path(), &stats).unwrap();
+
+        assert!(repos.is_empty());
+        assert_eq!(stats.unresolved_dependencies(), vec!["bare".to_string()]);
+        let warnings = stats.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("npm registry lookup for bare failed: "));
+    }
+
     #[test]
     fn skips_packages_without_metadata() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,225 @@
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::ecosystems::caching::CachingFetcher;
+use crate::http::{self, PrivateRegistry, RegistryCredentials};
+
+#[derive(Debug, thiserror::Error)]
+pub enum NpmRegistryError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+pub trait NpmRegistryFetcher {
+    fn fetch_repository_url(&self, package: &str) -> Result<Option<String>, NpmRegistryError>;
+}
+
+/// Registry lookups remembered by a [`NodeDiscoverer`] with the registry
+/// fallback, or shared by the discoverers of one run.
+///
+/// [`NodeDiscoverer`]: crate::ecosystems::NodeDiscoverer
+pub type CachedNpmRegistryClient = CachingFetcher<HttpNpmRegistryClient, Option<String>>;
+
+impl<F: NpmRegistryFetcher> NpmRegistryFetcher for CachingFetcher<F, Option<String>> {
+    fn fetch_repository_url(&self, package: &str) -> Result<Option<String>, NpmRegistryError> {
+        self.get_or_fetch(package, |inner, package| {
+            inner.fetch_repository_url(package)
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpNpmRegistryClient {
+    client: Client,
+    base_url: String,
+    credentials: Option<RegistryCredentials>,
+}
+
+impl Default for HttpNpmRegistryClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpNpmRegistryClient {
+    const DEFAULT_BASE_URL: &'static str = "https://registry.npmjs.org";
+
+    /// Client for the registry in `THANKS_STARS_NPM_URL`, authenticating with
+    /// the `THANKS_STARS_NPM_*` credentials if set, or for registry.npmjs.org
+    /// without credentials (see [`PrivateRegistry::from_env`]).
+    pub fn new() -> Self {
+        Self::for_registry(Self::DEFAULT_BASE_URL, PrivateRegistry::from_env("NPM"))
+    }
+
+    /// Client for `private`, or for the public registry at `public_url`.
+    fn for_registry(public_url: &str, private: Option<PrivateRegistry>) -> Self {
+        let Some(private) = private else {
+            return Self::with_client_and_base(http::shared_client(), public_url.to_string());
+        };
+        let client = Self::with_client_and_base(http::shared_client(), private.url);
+        match private.credentials {
+            Some(credentials) => client.with_credentials(credentials),
+            None => client,
+        }
+    }
+
+    fn with_client_and_base(client: Client, base_url: String) -> Self {
+        Self {
+            client,
+            base_url,
+            credentials: None,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send `credentials` with every request.
+    pub fn with_credentials(mut self, credentials: RegistryCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self::with_client_and_base(Client::new(), base_url.into())
+    }
+}
+
+impl NpmRegistryFetcher for HttpNpmRegistryClient {
+    fn fetch_repository_url(&self, package: &str) -> Result<Option<String>, NpmRegistryError> {
+        let base = self.base_url.trim_end_matches('/');
+        let url = format!("{base}/{}/latest", package.trim());
+        let request = self.client.get(&url).header(ACCEPT, "application/json");
+        let response = http::authenticate(request, self.credentials.as_ref()).send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => {
+                Err(NpmRegistryError::UnexpectedStatus { status, url })
+            }
+            _ => {
+                let manifest: NpmManifest = serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| NpmRegistryError::Decode { source })?;
+                Ok(manifest.repository_url())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmManifest {
+    #[serde(default)]
+    repository: Option<NpmRepository>,
+    #[serde(default)]
+    homepage: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum NpmRepository {
+    Url(String),
+    Object {
+        #[serde(default)]
+        url: Option<String>,
+    },
+}
+
+impl NpmManifest {
+    fn repository_url(self) -> Option<String> {
+        match self.repository {
+            Some(NpmRepository::Url(url)) => Some(url),
+            Some(NpmRepository::Object { url: Some(url) }) => Some(url),
+            _ => self.homepage,
+        }
+    }
+}
+
+/// Extract the package name from an `npm:` specifier such as
+/// `npm:@scope/pkg@^1.2.0/subpath`.
+pub fn parse_npm_specifier(specifier: &str) -> Option<String> {
+    let rest = specifier
+        .strip_prefix("npm:")?
+        .trim()
+        .trim_start_matches('/');
+    let (scope, remainder) = match rest.strip_prefix('@') {
+        Some(scoped) => {
+            let (scope, remainder) = scoped.split_once('/')?;
+            (Some(scope), remainder)
+        }
+        None => (None, rest),
+    };
+    let name = remainder.split(['@', '/']).next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(match scope {
+        Some(scope) if !scope.is_empty() => format!("@{scope}/{name}"),
+        Some(_) => return None,
+        None => name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn fetches_repository_url_from_latest_manifest() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/@scope/pkg/latest");
+            then.status(200).json_body(json!({
+                "name": "@scope/pkg",
+                "repository": { "type": "git", "url": "git+https://github.com/scope/pkg.git" }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/missing/latest");
+            then.status(404);
+        });
+
+        let client = HttpNpmRegistryClient::with_base_url(server.base_url());
+        assert_eq!(
+            client
+                .fetch_repository_url("@scope/pkg")
+                .unwrap()
+                .as_deref(),
+            Some("git+https://github.com/scope/pkg.git")
+        );
+        assert!(client.fetch_repository_url("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_npm_specifier_strips_versions_and_subpaths() {
+        assert_eq!(parse_npm_specifier("npm:chalk"), Some("chalk".to_string()));
+        assert_eq!(
+            parse_npm_specifier("npm:chalk@5.0.0"),
+            Some("chalk".to_string())
+        );
+        assert_eq!(
+            parse_npm_specifier("npm:@scope/pkg@^1/sub/path"),
+            Some("@scope/pkg".to_string())
+        );
+        assert_eq!(
+            parse_npm_specifier("npm:preact@10/hooks"),
+            Some("preact".to_string())
+        );
+        assert_eq!(parse_npm_specifier("jsr:@std/assert"), None);
+    }
+}
@@ -1,16 +1,25 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::LazyLock;
+use std::thread;
 
+use regex::Regex;
 use reqwest::blocking::Client;
 use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
 use toml::Value as TomlValue;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
-use crate::http;
+use crate::discovery::{parse_github_repository, DependencyScope, DiscoveryStats, Repository};
+use crate::ecosystems::caching::CachingFetcher;
+use crate::ecosystems::fixtures::{FileBackedFetcher, FixtureError};
+use crate::ecosystems::text::read_manifest;
+use crate::http::{self, PrivateRegistry, RegistryCredentials};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PythonDiscoveryError {
@@ -32,6 +41,12 @@ pub enum PythonDiscoveryError {
         #[source]
         source: serde_json::Error,
     },
+    #[error("failed to parse {path} as YAML: {source}")]
+    Yaml {
+        path: String,
+        #[source]
+        source: serde_yaml::Error,
+    },
     #[error("failed to fetch metadata for package {name}: {source}")]
     PyPi {
         name: String,
@@ -47,11 +62,29 @@ pub trait PyPiFetcher {
     fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError>;
 }
 
+/// PyPI lookups remembered by a default [`PythonDiscoverer`], or shared by the
+/// discoverers of one run.
+pub type CachedPyPiClient = CachingFetcher<HttpPyPiClient, Option<PyPiProject>>;
+
+impl PyPiFetcher for FileBackedFetcher {
+    fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
+        Ok(self.read(name)?)
+    }
+}
+
+impl<F: PyPiFetcher> PyPiFetcher for CachingFetcher<F, Option<PyPiProject>> {
+    fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
+        self.get_or_fetch(name, |inner, name| inner.fetch(name))
+    }
+}
+
 /// Thin wrapper around [`reqwest`] that talks to the live PyPI service.
 #[derive(Clone)]
 pub struct HttpPyPiClient {
     client: Client,
     base_url: String,
+    retries: u32,
+    credentials: Option<RegistryCredentials>,
 }
 
 impl Default for HttpPyPiClient {
@@ -63,12 +96,52 @@ impl Default for HttpPyPiClient {
 impl HttpPyPiClient {
     const DEFAULT_BASE_URL: &'static str = "https://pypi.org/pypi";
 
+    /// Client for the index in `THANKS_STARS_PYPI_URL`, authenticating with
+    /// the `THANKS_STARS_PYPI_*` credentials if set, or for pypi.org without
+    /// credentials (see [`PrivateRegistry::from_env`]).
     pub fn new() -> Self {
-        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+        Self::for_registry(Self::DEFAULT_BASE_URL, PrivateRegistry::from_env("PYPI"))
+    }
+
+    /// Client for `private`, or for the public index at `public_url`.
+    fn for_registry(public_url: &str, private: Option<PrivateRegistry>) -> Self {
+        let Some(private) = private else {
+            return Self::with_client_and_base(http::shared_client(), public_url.to_string());
+        };
+        let client = Self::with_client_and_base(http::shared_client(), private.url);
+        match private.credentials {
+            Some(credentials) => client.with_credentials(credentials),
+            None => client,
+        }
     }
 
     fn with_client_and_base(client: Client, base_url: String) -> Self {
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            retries: http::DEFAULT_RETRIES,
+            credentials: None,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send `credentials` with every request.
+    pub fn with_credentials(mut self, credentials: RegistryCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Retry transient failures (timeouts, connection errors, 5xx) up to
+    /// `retries` times before giving up.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     #[cfg(test)]
@@ -80,16 +153,18 @@ impl HttpPyPiClient {
 impl PyPiFetcher for HttpPyPiClient {
     fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
         let url = format!("{}/{name}/json", self.base_url.trim_end_matches('/'));
-        let response = self
-            .client
-            .get(&url)
-            .header(ACCEPT, "application/json")
-            .send()?;
+        let response = http::send_with_retry(self.retries, &http::ThreadSleeper, || {
+            let request = self.client.get(&url).header(ACCEPT, "application/json");
+            http::authenticate(request, self.credentials.as_ref()).send()
+        })?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(None),
-            status if !status.is_success() => Err(PyPiError::UnexpectedStatus { status }),
-            _ => Ok(Some(response.json()?)),
+            status if !status.is_success() => Err(PyPiError::UnexpectedStatus { status, url }),
+            _ => Ok(Some(
+                serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| PyPiError::Decode { source })?,
+            )),
         }
     }
 }
@@ -98,8 +173,15 @@ impl PyPiFetcher for HttpPyPiClient {
 pub enum PyPiError {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
-    #[error("unexpected status {status}")]
-    UnexpectedStatus { status: StatusCode },
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Fixture(#[from] FixtureError),
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -185,66 +267,150 @@ impl<'a> Iterator for CandidateUrls<'a> {
     }
 }
 
+/// Number of PyPI lookups [`PythonDiscoverer`] runs at once by default.
+const DEFAULT_CONCURRENCY: usize = 8;
+
 pub struct PythonDiscoverer<F: PyPiFetcher> {
     fetcher: F,
+    concurrency: usize,
+    scope: DependencyScope,
 }
 
-impl Default for PythonDiscoverer<HttpPyPiClient> {
+impl Default for PythonDiscoverer<CachedPyPiClient> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl PythonDiscoverer<HttpPyPiClient> {
+impl PythonDiscoverer<CachedPyPiClient> {
     pub fn new() -> Self {
-        Self {
-            fetcher: HttpPyPiClient::new(),
-        }
+        Self::with_fetcher(CachingFetcher::new(HttpPyPiClient::new()))
     }
 }
 
-impl<F: PyPiFetcher> PythonDiscoverer<F> {
+impl<F: PyPiFetcher + Sync> PythonDiscoverer<F> {
     pub fn with_fetcher(fetcher: F) -> Self {
-        Self { fetcher }
+        Self {
+            fetcher,
+            concurrency: DEFAULT_CONCURRENCY,
+            scope: DependencyScope::All,
+        }
+    }
+
+    /// Run up to `concurrency` PyPI lookups at once. `1` fetches sequentially.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// With [`DependencyScope::Direct`], skip `Pipfile.lock`, `uv.lock` and
+    /// `poetry.lock` and read only the declared requirements.
+    pub fn with_scope(mut self, scope: DependencyScope) -> Self {
+        self.scope = scope;
+        self
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, PythonDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, PythonDiscoveryError> {
         let mut dependencies: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut repositories = Vec::new();
 
-        collect_pyproject_dependencies(project_root, &mut dependencies)?;
+        collect_pyproject_dependencies(project_root, &mut dependencies, &mut repositories)?;
         collect_pipfile_dependencies(project_root, &mut dependencies)?;
-        collect_pipfile_lock_dependencies(project_root, &mut dependencies)?;
         collect_requirements_dependencies(project_root, &mut dependencies)?;
-        collect_uv_lock_dependencies(project_root, &mut dependencies)?;
+        collect_setup_cfg_dependencies(project_root, &mut dependencies)?;
+        collect_setup_py_dependencies(project_root, &mut dependencies)?;
+        collect_conda_dependencies(project_root, &mut dependencies)?;
+        if self.scope == DependencyScope::All {
+            collect_pipfile_lock_dependencies(project_root, &mut dependencies)?;
+            collect_uv_lock_dependencies(project_root, &mut dependencies)?;
+            collect_poetry_lock_dependencies(project_root, &mut dependencies, &mut repositories)?;
+        }
 
-        let mut repositories = Vec::new();
-        for (name, vias) in dependencies {
-            let Some(project) =
-                self.fetcher
-                    .fetch(&name)
-                    .map_err(|source| PythonDiscoveryError::PyPi {
-                        name: name.clone(),
-                        source,
-                    })?
+        let names: Vec<&str> = dependencies.keys().map(String::as_str).collect();
+        let projects = self.fetch_projects(&names);
+
+        for ((name, vias), project) in dependencies.into_iter().zip(projects) {
+            trace!(package = %name, "considering package");
+            stats.record_examined();
+            let Some(project) = project.map_err(|source| PythonDiscoveryError::PyPi {
+                name: name.clone(),
+                source,
+            })?
             else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: not found on PyPI");
                 continue;
             };
 
-            for url in project.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(&url) {
-                    if let Some(via) = vias.iter().next() {
-                        repository.via = Some(via.clone());
-                    } else {
-                        repository.via = Some("PyPI".to_string());
-                    }
-                    repositories.push(repository);
-                    break;
-                }
+            let Some(mut repository) = project
+                .candidate_urls()
+                .find_map(|url| parse_github_repository(&url))
+            else {
+                stats.record_unresolved(&name);
+                debug!(package = %name, "skipping: no GitHub URL in PyPI metadata");
+                continue;
+            };
+            debug!(package = %name, url = %repository.url, "resolved");
+            if vias.is_empty() {
+                repository.via = Some("PyPI".to_string());
+            }
+            for via in vias {
+                repository.add_via(via);
             }
+            repositories.push(repository);
         }
 
         Ok(repositories)
     }
+
+    /// Fetch every package in `names`, returning the results in the same
+    /// order regardless of which worker finished first.
+    fn fetch_projects(&self, names: &[&str]) -> Vec<Result<Option<PyPiProject>, PyPiError>> {
+        let workers = self.concurrency.min(names.len());
+        if workers <= 1 {
+            return names.iter().map(|name| self.fetcher.fetch(name)).collect();
+        }
+
+        let next = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(name) = names.get(index) else {
+                                break;
+                            };
+                            results.push((index, self.fetcher.fetch(name)));
+                        }
+                        results
+                    })
+                })
+                .collect();
+
+            let mut ordered: Vec<Option<Result<Option<PyPiProject>, PyPiError>>> =
+                (0..names.len()).map(|_| None).collect();
+            for handle in handles {
+                for (index, result) in handle.join().expect("PyPI lookup task panicked") {
+                    ordered[index] = Some(result);
+                }
+            }
+
+            ordered
+                .into_iter()
+                .map(|result| result.expect("every package is fetched once"))
+                .collect()
+        })
+    }
 }
 
 type DependencyMap = BTreeMap<String, BTreeSet<String>>;
@@ -252,9 +418,10 @@ type DependencyMap = BTreeMap<String, BTreeSet<String>>;
 fn collect_pyproject_dependencies(
     project_root: &Path,
     dependencies: &mut DependencyMap,
+    repositories: &mut Vec<Repository>,
 ) -> Result<(), PythonDiscoveryError> {
     let path = project_root.join("pyproject.toml");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -270,6 +437,10 @@ fn collect_pyproject_dependencies(
         source: err,
     })?;
 
+    if let Some(repository) = local_project_repository(&value) {
+        repositories.push(repository);
+    }
+
     if let Some(project) = value.get("project") {
         if let Some(array) = project.get("dependencies").and_then(|v| v.as_array()) {
             for entry in array {
@@ -297,10 +468,10 @@ fn collect_pyproject_dependencies(
     if let Some(tool) = value.get("tool").and_then(|v| v.as_table()) {
         if let Some(poetry) = tool.get("poetry").and_then(|v| v.as_table()) {
             if let Some(table) = poetry.get("dependencies").and_then(|v| v.as_table()) {
-                collect_poetry_table(table, dependencies);
+                collect_poetry_table(project_root, table, dependencies, repositories);
             }
             if let Some(table) = poetry.get("dev-dependencies").and_then(|v| v.as_table()) {
-                collect_poetry_table(table, dependencies);
+                collect_poetry_table(project_root, table, dependencies, repositories);
             }
             if let Some(group) = poetry.get("group").and_then(|v| v.as_table()) {
                 for value in group.values() {
@@ -309,7 +480,7 @@ fn collect_pyproject_dependencies(
                         .and_then(|table| table.get("dependencies"))
                         .and_then(|v| v.as_table())
                     {
-                        collect_poetry_table(table, dependencies);
+                        collect_poetry_table(project_root, table, dependencies, repositories);
                     }
                 }
             }
@@ -325,27 +496,80 @@ fn collect_pyproject_dependencies(
                     }
                 }
             }
+            if let Some(sources) = uv.get("sources").and_then(|v| v.as_table()) {
+                for (name, source) in sources {
+                    let Some(repository) = local_path_repository(project_root, source) else {
+                        continue;
+                    };
+                    if let Some(normalized) = normalize_name(name) {
+                        dependencies.remove(&normalized);
+                    }
+                    repositories.push(repository);
+                }
+            }
         }
     }
 
     Ok(())
 }
 
-fn collect_poetry_table(table: &toml::value::Table, dependencies: &mut DependencyMap) {
-    for (name, _value) in table {
+fn collect_poetry_table(
+    project_root: &Path,
+    table: &toml::value::Table,
+    dependencies: &mut DependencyMap,
+    repositories: &mut Vec<Repository>,
+) {
+    for (name, value) in table {
         if name.eq_ignore_ascii_case("python") {
             continue;
         }
+        if let Some(repository) = local_path_repository(project_root, value) {
+            repositories.push(repository);
+            continue;
+        }
         add_named_dependency(dependencies, name, "pyproject.toml");
     }
 }
 
+/// Resolve the repository declared in `[project.urls]` of a local
+/// `pyproject.toml`, using the same key priority as PyPI metadata.
+fn local_project_repository(pyproject: &TomlValue) -> Option<Repository> {
+    let urls = pyproject
+        .get("project")
+        .and_then(|project| project.get("urls"))
+        .and_then(|urls| urls.as_table())?;
+    let project = PyPiProject {
+        info: PyPiInfo {
+            home_page: None,
+            project_urls: Some(
+                urls.iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect(),
+            ),
+        },
+    };
+    let mut repository = project
+        .candidate_urls()
+        .find_map(|url| parse_github_repository(&url))?;
+    repository.via = Some("pyproject.toml".to_string());
+    Some(repository)
+}
+
+/// For a `{ path = "..." }` dependency, read the referenced project's own
+/// `pyproject.toml` and resolve its `[project.urls]` without asking PyPI.
+fn local_path_repository(project_root: &Path, spec: &TomlValue) -> Option<Repository> {
+    let path = spec.get("path").and_then(|v| v.as_str())?;
+    let content = read_manifest(project_root.join(path).join("pyproject.toml")).ok()?;
+    let value: TomlValue = toml::from_str(&content).ok()?;
+    local_project_repository(&value)
+}
+
 fn collect_pipfile_dependencies(
     project_root: &Path,
     dependencies: &mut DependencyMap,
 ) -> Result<(), PythonDiscoveryError> {
     let path = project_root.join("Pipfile");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -382,7 +606,7 @@ fn collect_pipfile_lock_dependencies(
     dependencies: &mut DependencyMap,
 ) -> Result<(), PythonDiscoveryError> {
     let path = project_root.join("Pipfile.lock");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -414,8 +638,30 @@ fn collect_requirements_dependencies(
     project_root: &Path,
     dependencies: &mut DependencyMap,
 ) -> Result<(), PythonDiscoveryError> {
-    let path = project_root.join("requirements.txt");
-    let content = match fs::read_to_string(&path) {
+    let mut visited = BTreeSet::new();
+    collect_requirements_file(
+        project_root,
+        &project_root.join("requirements.txt"),
+        dependencies,
+        &mut visited,
+    )
+}
+
+/// Collect one requirements file and, recursively, the files it pulls in with
+/// `-r`/`--requirement` or `-c`/`--constraint`. Includes are resolved relative
+/// to the including file; `visited` stops include cycles.
+fn collect_requirements_file(
+    project_root: &Path,
+    path: &Path,
+    dependencies: &mut DependencyMap,
+    visited: &mut BTreeSet<PathBuf>,
+) -> Result<(), PythonDiscoveryError> {
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+
+    let content = match read_manifest(path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -426,21 +672,171 @@ fn collect_requirements_dependencies(
         }
     };
 
+    let via = path
+        .strip_prefix(project_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let base = path.parent().unwrap_or(project_root);
     for line in content.lines() {
-        if let Some(name) = normalize_requirement(line) {
-            add_dependency(dependencies, name, "requirements.txt");
+        if let Some(include) = requirements_include(line) {
+            collect_requirements_file(project_root, &base.join(include), dependencies, visited)?;
+        } else if let Some(name) = normalize_requirement(line) {
+            add_dependency(dependencies, name, &via);
+        }
+    }
+
+    Ok(())
+}
+
+/// The file named by a `-r`/`--requirement` or `-c`/`--constraint` line.
+fn requirements_include(line: &str) -> Option<&str> {
+    let trimmed = line.split(" #").next().unwrap_or(line).trim();
+    let rest = ["--requirement", "--constraint", "-r", "-c"]
+        .iter()
+        .find_map(|flag| trimmed.strip_prefix(flag))?;
+    let file = rest.strip_prefix('=').unwrap_or(rest).trim();
+    (!file.is_empty()).then_some(file)
+}
+
+/// Read `install_requires` from `[options]` and every list in
+/// `[options.extras_require]` of a `setup.cfg`. Values either follow the `=`
+/// on the same line or continue on indented lines below it.
+fn collect_setup_cfg_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+) -> Result<(), PythonDiscoveryError> {
+    let path = project_root.join("setup.cfg");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(PythonDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let mut section = String::new();
+    let mut in_requirements = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            section = name.trim().to_string();
+            in_requirements = false;
+            continue;
+        }
+
+        let value = if line.starts_with(char::is_whitespace) {
+            if !in_requirements {
+                continue;
+            }
+            trimmed
+        } else {
+            let Some((key, value)) = trimmed.split_once(['=', ':']) else {
+                in_requirements = false;
+                continue;
+            };
+            in_requirements = match section.as_str() {
+                "options" => key.trim() == "install_requires",
+                "options.extras_require" => true,
+                _ => false,
+            };
+            if !in_requirements {
+                continue;
+            }
+            value
+        };
+
+        for requirement in value.split(',') {
+            add_requirement_dependency(dependencies, requirement, "setup.cfg");
+        }
+    }
+
+    Ok(())
+}
+
+/// Pull the string literals out of `install_requires=[...]` and
+/// `extras_require={...}` in a `setup.py`. This is a heuristic: the script is
+/// never executed, so requirements built dynamically are missed.
+fn collect_setup_py_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+) -> Result<(), PythonDiscoveryError> {
+    static ARGUMENT: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"\b(install_requires|extras_require)\s*=\s*([\[{])").expect("valid regex")
+    });
+
+    let path = project_root.join("setup.py");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(PythonDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    for captures in ARGUMENT.captures_iter(&content) {
+        let open = captures.get(2).expect("group always matches");
+        let close = if open.as_str() == "[" { ']' } else { '}' };
+        for requirement in python_string_values(&content[open.end()..], close) {
+            add_requirement_dependency(dependencies, &requirement, "setup.py");
         }
     }
 
     Ok(())
 }
 
+/// Collect the quoted strings in `source` up to the first unquoted `close`,
+/// skipping strings used as dictionary keys.
+fn python_string_values(source: &str, close: char) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                let mut value = String::new();
+                for inner in chars.by_ref() {
+                    if inner == ch {
+                        break;
+                    }
+                    value.push(inner);
+                }
+                while chars.next_if(|next| next.is_whitespace()).is_some() {}
+                if chars.peek() != Some(&':') {
+                    values.push(value);
+                }
+            }
+            '#' => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ if ch == close => break,
+            _ => {}
+        }
+    }
+    values
+}
+
 fn collect_uv_lock_dependencies(
     project_root: &Path,
     dependencies: &mut DependencyMap,
 ) -> Result<(), PythonDiscoveryError> {
     let path = project_root.join("uv.lock");
-    let content = match fs::read_to_string(&path) {
+    let content = match read_manifest(&path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(err) => {
@@ -471,6 +867,127 @@ fn collect_uv_lock_dependencies(
     Ok(())
 }
 
+/// Collect every resolved `[[package]]` from `poetry.lock`. Packages installed
+/// from a git source are resolved straight from their URL instead of PyPI.
+fn collect_poetry_lock_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+    repositories: &mut Vec<Repository>,
+) -> Result<(), PythonDiscoveryError> {
+    let path = project_root.join("poetry.lock");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(PythonDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let value: TomlValue = toml::from_str(&content).map_err(|err| PythonDiscoveryError::Toml {
+        path: path.display().to_string(),
+        source: err,
+    })?;
+
+    let Some(packages) = value.get("package").and_then(|v| v.as_array()) else {
+        return Ok(());
+    };
+    for package in packages.iter().filter_map(|package| package.as_table()) {
+        let Some(name) = package.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+
+        let source = package.get("source").and_then(|v| v.as_table());
+        let source_type = source
+            .and_then(|source| source.get("type"))
+            .and_then(|v| v.as_str());
+        if source_type == Some("git") {
+            if let Some(mut repository) = source
+                .and_then(|source| source.get("url"))
+                .and_then(|v| v.as_str())
+                .and_then(parse_github_repository)
+            {
+                repository.via = Some("poetry.lock".to_string());
+                repositories.push(repository);
+            }
+            continue;
+        }
+        if matches!(source_type, Some("directory" | "file")) {
+            continue;
+        }
+
+        add_named_dependency(dependencies, name, "poetry.lock");
+    }
+
+    Ok(())
+}
+
+/// Collect conda and nested `pip:` dependencies from `environment.yml`.
+/// Conda-only packages are looked up on PyPI as well and simply won't resolve.
+fn collect_conda_dependencies(
+    project_root: &Path,
+    dependencies: &mut DependencyMap,
+) -> Result<(), PythonDiscoveryError> {
+    let path = project_root.join("environment.yml");
+    let content = match read_manifest(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => {
+            return Err(PythonDiscoveryError::Io {
+                path: path.display().to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let value: YamlValue =
+        serde_yaml::from_str(&content).map_err(|err| PythonDiscoveryError::Yaml {
+            path: path.display().to_string(),
+            source: err,
+        })?;
+
+    let Some(entries) = value.get("dependencies").and_then(YamlValue::as_sequence) else {
+        return Ok(());
+    };
+    for entry in entries {
+        match entry {
+            YamlValue::String(spec) => {
+                if let Some(name) = normalize_conda_spec(spec) {
+                    add_dependency(dependencies, name, "environment.yml");
+                }
+            }
+            YamlValue::Mapping(_) => {
+                let Some(pip) = entry.get("pip").and_then(YamlValue::as_sequence) else {
+                    continue;
+                };
+                for requirement in pip.iter().filter_map(YamlValue::as_str) {
+                    add_requirement_dependency(dependencies, requirement, "environment.yml");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the package name from a conda match spec such as
+/// `conda-forge::numpy>=1.26` or `scipy=1.11=py311h`.
+fn normalize_conda_spec(spec: &str) -> Option<String> {
+    let spec = spec.trim();
+    let spec = spec.rsplit_once("::").map_or(spec, |(_, rest)| rest);
+    let name = spec
+        .split(['=', '<', '>', '!', '~', ' ', '['])
+        .next()?
+        .trim();
+    if name.eq_ignore_ascii_case("python") || name.eq_ignore_ascii_case("pip") {
+        return None;
+    }
+    normalize_name(name)
+}
+
 fn add_dependency(map: &mut DependencyMap, name: String, via: &str) {
     map.entry(name).or_default().insert(via.to_string());
 }
@@ -534,6 +1051,7 @@ mod tests {
     use super::*;
     use serde_json::json;
     use std::collections::HashMap;
+    use std::sync::Arc;
     use tempfile::tempdir;
 
     #[derive(Clone)]
@@ -556,6 +1074,36 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Default)]
+    struct RecordingPyPiFetcher {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl PyPiFetcher for RecordingPyPiFetcher {
+        fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(project_with_url(&format!(
+                "https://github.com/psf/{name}"
+            ))))
+        }
+    }
+
+    #[test]
+    fn caching_fetcher_looks_up_shared_packages_once() {
+        let recording = RecordingPyPiFetcher::default();
+        let fetcher = CachingFetcher::new(recording.clone());
+
+        for _ in 0..2 {
+            let dir = tempdir().unwrap();
+            fs::write(dir.path().join("requirements.txt"), "requests==2.32\n").unwrap();
+            let discoverer = PythonDiscoverer::with_fetcher(fetcher.clone());
+            let repos = discoverer.discover(dir.path()).unwrap();
+            assert_eq!(repos[0].url, "https://github.com/psf/requests");
+        }
+
+        assert_eq!(recording.calls.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn discovers_repositories_from_python_manifests() {
         let dir = tempdir().unwrap();
@@ -668,6 +1216,7 @@ name = "uvicorn"
         assert_eq!(repos.len(), 10);
         let requests = repos.iter().find(|repo| repo.name == "requests").unwrap();
         assert_eq!(requests.via.as_deref(), Some("pyproject.toml"));
+        assert_eq!(requests.also_via, ["requirements.txt"]);
         let uvicorn = repos.iter().find(|repo| repo.name == "uvicorn").unwrap();
         assert_eq!(uvicorn.via.as_deref(), Some("requirements.txt"));
         let fastapi = repos.iter().find(|repo| repo.name == "fastapi").unwrap();
@@ -678,6 +1227,493 @@ name = "uvicorn"
         assert_eq!(httpcore.via.as_deref(), Some("uv.lock"));
     }
 
+    #[test]
+    fn resolves_local_project_urls_without_fetching() {
+        struct PanicFetcher;
+
+        impl PyPiFetcher for PanicFetcher {
+            fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
+                panic!("unexpected PyPI lookup for {name}")
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("pyproject.toml"),
+            r#"
+[project]
+name = "app"
+
+[project.urls]
+Documentation = "https://app.readthedocs.io"
+Source = "https://github.com/example/app"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+mylib = { path = "libs/mylib", develop = true }
+"#,
+        )
+        .unwrap();
+        let lib_dir = dir.path().join("libs/mylib");
+        fs::create_dir_all(&lib_dir).unwrap();
+        fs::write(
+            lib_dir.join("pyproject.toml"),
+            r#"
+[project]
+name = "mylib"
+
+[project.urls]
+Repository = "https://github.com/example/mylib"
+"#,
+        )
+        .unwrap();
+
+        let discoverer = PythonDiscoverer::with_fetcher(PanicFetcher);
+        let mut repos = discoverer.discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[0].url, "https://github.com/example/app");
+        assert_eq!(repos[1].url, "https://github.com/example/mylib");
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("pyproject.toml")));
+    }
+
+    #[test]
+    fn discovers_resolved_packages_from_poetry_lock() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("poetry.lock"),
+            r#"
+# This file is automatically @generated by Poetry and should not be changed by hand.
+
+[[package]]
+name = "certifi"
+version = "2024.2.2"
+description = "Python package for providing Mozilla's CA Bundle."
+optional = false
+python-versions = ">=3.6"
+files = [
+    {file = "certifi-2024.2.2-py3-none-any.whl", hash = "sha256:00"},
+]
+
+[[package]]
+name = "Flask_Cors"
+version = "4.0.0"
+optional = false
+python-versions = "*"
+
+[[package]]
+name = "forked"
+version = "0.1.0"
+optional = false
+python-versions = "^3.11"
+
+[package.source]
+type = "git"
+url = "https://github.com/example/forked.git"
+reference = "main"
+resolved_reference = "0123456789abcdef"
+
+[[package]]
+name = "local-lib"
+version = "0.0.1"
+optional = false
+python-versions = "*"
+
+[package.source]
+type = "directory"
+url = "../local-lib"
+
+[metadata]
+lock-version = "2.0"
+python-versions = "^3.11"
+content-hash = "abc"
+"#,
+        )
+        .unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: HashMap::from([
+                (
+                    "certifi".to_string(),
+                    Some(project_with_url(
+                        "https://github.com/certifi/python-certifi",
+                    )),
+                ),
+                (
+                    "flask-cors".to_string(),
+                    Some(project_with_url(
+                        "https://github.com/corydolphin/flask-cors",
+                    )),
+                ),
+            ]),
+        };
+
+        let discoverer = PythonDiscoverer::with_fetcher(fetcher);
+        let mut repos = discoverer.discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names: Vec<_> = repos.iter().map(|repo| repo.name.as_str()).collect();
+        assert_eq!(names, ["flask-cors", "forked", "python-certifi"]);
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("poetry.lock")));
+        assert_eq!(repos[1].owner, "example");
+    }
+
+    #[test]
+    fn direct_scope_skips_lockfiles() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("requirements.txt"), "requests==2.32\n").unwrap();
+        fs::write(
+            dir.path().join("uv.lock"),
+            r#"
+version = 1
+
+[[package]]
+name = "requests"
+
+[[package]]
+name = "urllib3"
+"#,
+        )
+        .unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: HashMap::from([
+                (
+                    "requests".to_string(),
+                    Some(project_with_url("https://github.com/psf/requests")),
+                ),
+                (
+                    "urllib3".to_string(),
+                    Some(project_with_url("https://github.com/urllib3/urllib3")),
+                ),
+            ]),
+        };
+
+        let all = PythonDiscoverer::with_fetcher(fetcher.clone())
+            .discover(dir.path())
+            .unwrap();
+        let direct = PythonDiscoverer::with_fetcher(fetcher)
+            .with_scope(DependencyScope::Direct)
+            .discover(dir.path())
+            .unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].name, "requests");
+        assert_eq!(direct[0].via.as_deref(), Some("requirements.txt"));
+    }
+
+    #[test]
+    fn discovers_conda_and_pip_dependencies_from_environment_yml() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("environment.yml"),
+            r#"
+name: analysis
+channels:
+  - conda-forge
+dependencies:
+  - python=3.11
+  - numpy=1.26
+  - conda-forge::pandas>=2.0
+  - cudatoolkit
+  - pip
+  - pip:
+      - requests==2.31
+      - rich[jupyter]
+"#,
+        )
+        .unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: HashMap::from([
+                (
+                    "numpy".to_string(),
+                    Some(project_with_url("https://github.com/numpy/numpy")),
+                ),
+                (
+                    "pandas".to_string(),
+                    Some(project_with_url("https://github.com/pandas-dev/pandas")),
+                ),
+                (
+                    "requests".to_string(),
+                    Some(project_with_url("https://github.com/psf/requests")),
+                ),
+                (
+                    "rich".to_string(),
+                    Some(project_with_url("https://github.com/Textualize/rich")),
+                ),
+            ]),
+        };
+
+        let discoverer = PythonDiscoverer::with_fetcher(fetcher);
+        let mut repos = discoverer.discover(dir.path()).unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names: Vec<_> = repos.iter().map(|repo| repo.name.as_str()).collect();
+        assert_eq!(names, ["numpy", "pandas", "requests", "rich"]);
+        assert!(repos
+            .iter()
+            .all(|repo| repo.via.as_deref() == Some("environment.yml")));
+    }
+
+    #[test]
+    fn follows_included_requirements_files() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("requirements.txt"),
+            "requests\n-r requirements-dev.txt\n--constraint=constraints/pins.txt\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("requirements-dev.txt"),
+            "-r requirements.txt\npytest>=7\n-e ./local\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("constraints")).unwrap();
+        fs::write(dir.path().join("constraints/pins.txt"), "urllib3==2.2\n").unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: ["requests", "pytest", "urllib3"]
+                .into_iter()
+                .map(|name| {
+                    let url = format!("https://github.com/example/{name}");
+                    (name.to_string(), Some(project_with_url(&url)))
+                })
+                .collect(),
+        };
+
+        let discoverer = PythonDiscoverer::with_fetcher(fetcher);
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let via = |name: &str| {
+            repos
+                .iter()
+                .find(|repo| repo.name == name)
+                .and_then(|repo| repo.via.as_deref())
+        };
+        assert_eq!(repos.len(), 3);
+        assert_eq!(via("requests"), Some("requirements.txt"));
+        assert_eq!(via("pytest"), Some("requirements-dev.txt"));
+        assert_eq!(via("urllib3"), Some("constraints/pins.txt"));
+    }
+
+    #[test]
+    fn discovers_setup_cfg_and_setup_py_requirements() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("setup.cfg"),
+            r#"
+[metadata]
+name = legacy
+
+[options]
+packages = find:
+install_requires =
+    requests>=2.0
+    click; python_version >= "3.8"
+
+[options.extras_require]
+dev = pytest>=7, black
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("setup.py"),
+            r#"
+from setuptools import setup
+
+setup(
+    name="legacy",
+    install_requires=[
+        "uvicorn[standard]==0.30",  # ASGI server
+        'numpy',
+    ],
+    extras_require={"docs": ["sphinx>=7"]},
+)
+"#,
+        )
+        .unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: [
+                "requests", "click", "pytest", "black", "uvicorn", "numpy", "sphinx",
+            ]
+            .into_iter()
+            .map(|name| {
+                let url = format!("https://github.com/example/{name}");
+                (name.to_string(), Some(project_with_url(&url)))
+            })
+            .collect(),
+        };
+
+        let discoverer = PythonDiscoverer::with_fetcher(fetcher);
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let via = |name: &str| {
+            repos
+                .iter()
+                .find(|repo| repo.name == name)
+                .and_then(|repo| repo.via.as_deref())
+        };
+        assert_eq!(repos.len(), 7);
+        for name in ["requests", "click", "pytest", "black"] {
+            assert_eq!(via(name), Some("setup.cfg"), "{name}");
+        }
+        for name in ["uvicorn", "numpy", "sphinx"] {
+            assert_eq!(via(name), Some("setup.py"), "{name}");
+        }
+    }
+
+    #[test]
+    fn unexpected_status_names_the_requested_url() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/requests/json");
+            then.status(500);
+        });
+
+        let client = HttpPyPiClient::with_base_url(server.base_url()).with_retries(0);
+        let err = client.fetch("requests").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "unexpected status 500 Internal Server Error for {}/requests/json",
+                server.base_url()
+            )
+        );
+    }
+
+    #[test]
+    fn http_client_retries_server_errors_but_not_missing_packages() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        let mut unavailable = server.mock(|when, then| {
+            when.method(GET).path("/requests/json");
+            then.status(503);
+        });
+        let available = server.mock(|when, then| {
+            when.method(GET).path("/requests/json");
+            then.status(200).json_body(json!({
+                "info": { "home_page": "https://github.com/psf/requests" }
+            }));
+        });
+        let missing = server.mock(|when, then| {
+            when.method(GET).path("/missing/json");
+            then.status(404);
+        });
+
+        let client = HttpPyPiClient::with_base_url(server.base_url()).with_retries(1);
+        let project = thread::scope(|scope| {
+            let lookup = scope.spawn(|| client.fetch("requests"));
+            // Take the 503 away once it has been served, so the retry that
+            // follows the backoff is answered by the healthy mock.
+            while unavailable.calls() == 0 {
+                thread::sleep(std::time::Duration::from_millis(10));
+            }
+            unavailable.delete();
+            lookup.join().unwrap()
+        })
+        .unwrap()
+        .expect("package found after retry");
+
+        assert_eq!(
+            project.candidate_urls().next().as_deref(),
+            Some("https://github.com/psf/requests")
+        );
+        available.assert();
+        assert!(client.fetch("missing").unwrap().is_none());
+        missing.assert_calls(1);
+    }
+
+    #[test]
+    fn http_client_sends_credentials_only_to_the_configured_index() {
+        use httpmock::prelude::*;
+
+        let public = MockServer::start();
+        let private = MockServer::start();
+        let anonymous = public.mock(|when, then| {
+            when.method(GET)
+                .path("/requests/json")
+                .header_missing("authorization");
+            then.status(200).json_body(json!({
+                "info": { "home_page": "https://github.com/psf/requests" }
+            }));
+        });
+        let authenticated = private.mock(|when, then| {
+            when.method(GET)
+                .path("/internal-lib/json")
+                .header("authorization", "Bearer pypi-secret");
+            then.status(200).json_body(json!({
+                "info": { "home_page": "https://github.com/acme/internal-lib" }
+            }));
+        });
+        let private_url = private.base_url();
+        let env = |with_url: bool| {
+            let private_url = private_url.clone();
+            move |name: &str| match name {
+                "THANKS_STARS_PYPI_URL" if with_url => Some(private_url.clone()),
+                "THANKS_STARS_PYPI_TOKEN" => Some("pypi-secret".to_string()),
+                _ => None,
+            }
+        };
+
+        let client = HttpPyPiClient::for_registry(
+            &public.base_url(),
+            PrivateRegistry::from_lookup("PYPI", env(false)),
+        );
+        client.fetch("requests").unwrap().unwrap();
+        anonymous.assert();
+
+        let client = HttpPyPiClient::for_registry(
+            &public.base_url(),
+            PrivateRegistry::from_lookup("PYPI", env(true)),
+        );
+        let project = client.fetch("internal-lib").unwrap().unwrap();
+        authenticated.assert();
+        assert_eq!(
+            project.candidate_urls().next().as_deref(),
+            Some("https://github.com/acme/internal-lib")
+        );
+    }
+
+    #[test]
+    fn malformed_metadata_is_reported_as_a_decode_error() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/broken/json");
+            then.status(200).body("<html>not json</html>");
+        });
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("requirements.txt"), "broken\n").unwrap();
+
+        let discoverer =
+            PythonDiscoverer::with_fetcher(HttpPyPiClient::with_base_url(server.base_url()));
+        let err = discoverer.discover(dir.path()).unwrap_err();
+
+        match &err {
+            PythonDiscoveryError::PyPi {
+                name,
+                source: PyPiError::Decode { .. },
+            } => assert_eq!(name, "broken"),
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("failed to fetch metadata for package broken: failed to decode"));
+    }
+
     #[test]
     fn normalize_requirement_parses_basic_specs() {
         assert_eq!(
@@ -698,4 +1734,53 @@ name = "uvicorn"
         assert_eq!(normalize_requirement("-r other.txt"), None);
         assert_eq!(normalize_requirement("https://example.com/pkg.whl"), None);
     }
+
+    #[test]
+    fn resolves_every_package_with_concurrent_lookups() {
+        let names: Vec<String> = (0..24).map(|i| format!("pkg-{i:02}")).collect();
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("requirements.txt"), names.join("\n")).unwrap();
+
+        let fetcher = StaticPyPiFetcher {
+            packages: names
+                .iter()
+                .map(|name| {
+                    let url = format!("https://github.com/example/{name}");
+                    (name.clone(), Some(project_with_url(&url)))
+                })
+                .collect(),
+        };
+
+        let discoverer = PythonDiscoverer::with_fetcher(fetcher).with_concurrency(4);
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        let found: Vec<_> = repos.iter().map(|repo| repo.name.clone()).collect();
+        assert_eq!(found, names);
+    }
+
+    #[test]
+    fn reports_the_failing_package_from_concurrent_lookups() {
+        struct FailingFetcher;
+
+        impl PyPiFetcher for FailingFetcher {
+            fn fetch(&self, name: &str) -> Result<Option<PyPiProject>, PyPiError> {
+                if name == "broken" {
+                    Err(PyPiError::UnexpectedStatus {
+                        status: StatusCode::BAD_GATEWAY,
+                        url: "https://pypi.org/pypi/broken/json".to_string(),
+                    })
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("requirements.txt"), "alpha\nbroken\nzeta\n").unwrap();
+
+        let discoverer = PythonDiscoverer::with_fetcher(FailingFetcher).with_concurrency(3);
+        let err = discoverer.discover(dir.path()).unwrap_err();
+
+        assert!(matches!(err, PythonDiscoveryError::PyPi { ref name, .. } if name == "broken"));
+    }
 }
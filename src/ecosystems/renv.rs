@@ -1,11 +1,16 @@
 use std::collections::BTreeSet;
-use std::fs;
 use std::path::Path;
 
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
 use serde::Deserialize;
+use tracing::{debug, trace};
 use url::Url;
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DiscoveryStats, Repository};
+use crate::ecosystems::text::read_manifest;
+use crate::http;
 
 #[derive(Debug, thiserror::Error)]
 pub enum RenvDiscoveryError {
@@ -21,19 +26,141 @@ pub enum RenvDiscoveryError {
         #[source]
         source: serde_json::Error,
     },
+    #[error("failed to fetch R-universe metadata for package {package}: {source}")]
+    RUniverse {
+        package: String,
+        #[source]
+        source: RUniverseError,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RUniverseError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+pub trait RUniverseFetcher {
+    /// Return the `RemoteUrl` that `universe` records for `package`.
+    fn fetch_remote_url(
+        &self,
+        universe: &str,
+        package: &str,
+    ) -> Result<Option<String>, RUniverseError>;
+}
+
+#[derive(Clone)]
+pub struct HttpRUniverseClient {
+    client: Client,
+    /// Base URL template; `{universe}` is replaced with the universe name.
+    base_url: String,
+}
+
+impl Default for HttpRUniverseClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpRUniverseClient {
+    const DEFAULT_BASE_URL: &'static str = "https://{universe}.r-universe.dev";
+
+    pub fn new() -> Self {
+        Self::with_client_and_base(http::shared_client(), Self::DEFAULT_BASE_URL.to_string())
+    }
+
+    fn with_client_and_base(client: Client, base_url: String) -> Self {
+        Self { client, base_url }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Serve every universe from `{base_url}/{universe}`.
+    #[cfg(test)]
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        let base_url = format!("{}/{{universe}}", base_url.into().trim_end_matches('/'));
+        Self::with_client_and_base(Client::new(), base_url)
+    }
+}
+
+impl RUniverseFetcher for HttpRUniverseClient {
+    fn fetch_remote_url(
+        &self,
+        universe: &str,
+        package: &str,
+    ) -> Result<Option<String>, RUniverseError> {
+        let base = self.base_url.replace("{universe}", universe);
+        let url = format!("{}/api/packages/{package}", base.trim_end_matches('/'));
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/json")
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(RUniverseError::UnexpectedStatus { status, url }),
+            _ => {
+                let package: RUniversePackage = serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| RUniverseError::Decode { source })?;
+                Ok(package.remote_url)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RUniversePackage {
+    #[serde(rename = "RemoteUrl")]
+    remote_url: Option<String>,
 }
 
-#[derive(Default)]
-pub struct RenvDiscoverer;
+pub struct RenvDiscoverer<F: RUniverseFetcher = HttpRUniverseClient> {
+    fetcher: F,
+}
 
-impl RenvDiscoverer {
+impl Default for RenvDiscoverer<HttpRUniverseClient> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenvDiscoverer<HttpRUniverseClient> {
     pub fn new() -> Self {
-        Self
+        Self {
+            fetcher: HttpRUniverseClient::new(),
+        }
+    }
+}
+
+impl<F: RUniverseFetcher> RenvDiscoverer<F> {
+    pub fn with_fetcher(fetcher: F) -> Self {
+        Self { fetcher }
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, RenvDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, RenvDiscoveryError> {
         let path = project_root.join("renv.lock");
-        let contents = fs::read_to_string(&path).map_err(|source| RenvDiscoveryError::Io {
+        let contents = read_manifest(&path).map_err(|source| RenvDiscoveryError::Io {
             path: path.display().to_string(),
             source,
         })?;
@@ -47,20 +174,52 @@ impl RenvDiscoverer {
         let mut seen = BTreeSet::new();
         let mut repositories = Vec::new();
 
-        for package in lock.packages.values() {
-            if let Some((owner, name)) = package.github_owner_repo() {
-                if seen.insert((owner.clone(), name.clone())) {
-                    let url = format!("https://github.com/{owner}/{name}");
-                    if let Some(mut repository) = parse_github_repository(&url) {
-                        repository.via = Some("renv.lock".to_string());
-                        repositories.push(repository);
-                    }
-                }
+        for (key, package) in &lock.packages {
+            trace!(package = %key, "considering package");
+            stats.record_examined();
+            let owner_repo = match package.github_owner_repo() {
+                Some(owner_repo) => Some(owner_repo),
+                None => self.r_universe_owner_repo(key, package)?,
+            };
+            let Some((owner, name)) = owner_repo else {
+                stats.record_unresolved(key);
+                debug!(package = %key, "skipping: no GitHub source");
+                continue;
+            };
+            if !seen.insert((owner.clone(), name.clone())) {
+                continue;
+            }
+            let url = format!("https://github.com/{owner}/{name}");
+            if let Some(mut repository) = parse_github_repository(&url) {
+                debug!(package = %key, url = %repository.url, "resolved");
+                repository.via = Some("renv.lock".to_string());
+                repositories.push(repository);
             }
         }
 
         Ok(repositories)
     }
+
+    /// R-universe builds packages from a git repository, so ask the universe
+    /// for the package's `RemoteUrl` when the lockfile does not record it.
+    fn r_universe_owner_repo(
+        &self,
+        key: &str,
+        package: &RenvPackage,
+    ) -> Result<Option<(String, String)>, RenvDiscoveryError> {
+        let Some(universe) = package.r_universe() else {
+            return Ok(None);
+        };
+        let name = package.package.as_deref().unwrap_or(key);
+        let remote_url = self
+            .fetcher
+            .fetch_remote_url(&universe, name)
+            .map_err(|source| RenvDiscoveryError::RUniverse {
+                package: name.to_string(),
+                source,
+            })?;
+        Ok(remote_url.as_deref().and_then(owner_repo_from_url))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,6 +230,8 @@ struct RenvLock {
 
 #[derive(Debug, Deserialize)]
 struct RenvPackage {
+    #[serde(rename = "Package")]
+    package: Option<String>,
     #[serde(rename = "Source")]
     source: Option<String>,
     #[serde(rename = "RemoteType")]
@@ -93,7 +254,9 @@ struct RenvPackage {
 
 impl RenvPackage {
     fn github_owner_repo(&self) -> Option<(String, String)> {
-        if !self.is_github_source() {
+        // GitLab remotes carry GitLab owner/repo fields that must not be read
+        // as GitHub coordinates.
+        if self.is_gitlab_source() || !self.is_github_source() {
             return None;
         }
 
@@ -155,6 +318,28 @@ impl RenvPackage {
                 .is_some_and(|value| value.contains("github.com"))
     }
 
+    fn is_gitlab_source(&self) -> bool {
+        self.remote_type
+            .as_deref()
+            .is_some_and(|value| value.eq_ignore_ascii_case("gitlab"))
+    }
+
+    /// Name of the R-universe (`<universe>.r-universe.dev`) the package was
+    /// installed from, if any.
+    fn r_universe(&self) -> Option<String> {
+        if self.is_gitlab_source() {
+            return None;
+        }
+        [self.repository.as_deref(), self.remote_url.as_deref()]
+            .into_iter()
+            .flatten()
+            .find_map(|value| {
+                let parsed = Url::parse(value.trim()).ok()?;
+                let universe = parsed.host_str()?.strip_suffix(".r-universe.dev")?;
+                (!universe.is_empty() && !universe.contains('.')).then(|| universe.to_string())
+            })
+    }
+
     fn owner_repo_from_remote_fields(&self) -> Option<(String, String)> {
         let repo = self.remote_repo.as_deref()?.trim().trim_end_matches(".git");
         if repo.is_empty() {
@@ -211,6 +396,7 @@ fn owner_repo_from_url(input: &str) -> Option<(String, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use httpmock::prelude::*;
     use serde_json::json;
     use std::fs;
     use tempfile::tempdir;
@@ -351,4 +537,72 @@ mod tests {
         assert_eq!(repos[0].owner, "example");
         assert_eq!(repos[0].name, "pkg");
     }
+
+    #[test]
+    fn resolves_r_universe_packages_through_the_universe_api() {
+        let server = MockServer::start();
+        let api = server.mock(|when, then| {
+            when.method(GET).path("/ropensci/api/packages/targets");
+            then.status(200).json_body(json!({
+                "Package": "targets",
+                "RemoteUrl": "https://github.com/ropensci/targets"
+            }));
+        });
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("renv.lock"),
+            json!({
+                "Packages": {
+                    "targets": {
+                        "Package": "targets",
+                        "Version": "1.7.0",
+                        "Source": "Repository",
+                        "Repository": "https://ropensci.r-universe.dev"
+                    }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let discoverer =
+            RenvDiscoverer::with_fetcher(HttpRUniverseClient::with_base_url(server.base_url()));
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        api.assert();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].owner, "ropensci");
+        assert_eq!(repos[0].name, "targets");
+        assert_eq!(repos[0].via.as_deref(), Some("renv.lock"));
+    }
+
+    #[test]
+    fn skips_gitlab_packages() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("renv.lock"),
+            json!({
+                "Packages": {
+                    "pkg": {
+                        "Package": "pkg",
+                        "Version": "1.0.0",
+                        "Source": "GitLab",
+                        "RemoteType": "gitlab",
+                        "RemoteHost": "gitlab.com",
+                        "RemoteUsername": "someone",
+                        "RemoteRepo": "pkg",
+                        "BugReports": "https://github.com/someone/pkg/issues"
+                    }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let discoverer = RenvDiscoverer::new();
+        let repos = discoverer.discover(dir.path()).unwrap();
+
+        assert!(repos.is_empty());
+    }
 }
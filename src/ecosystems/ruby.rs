@@ -1,5 +1,4 @@
 use std::collections::BTreeSet;
-use std::fs;
 use std::path::Path;
 
 use regex::Regex;
@@ -7,8 +6,12 @@ use reqwest::blocking::Client;
 use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
 use serde::Deserialize;
+use tracing::{debug, trace};
 
-use crate::discovery::{parse_github_repository, Repository};
+use crate::discovery::{parse_github_repository, DiscoveryStats, Repository};
+use crate::ecosystems::caching::CachingFetcher;
+use crate::ecosystems::fixtures::{FileBackedFetcher, FixtureError};
+use crate::ecosystems::text::read_manifest;
 use crate::http;
 
 #[derive(Debug, thiserror::Error)]
@@ -31,10 +34,27 @@ pub trait RubyGemsFetcher {
     fn fetch(&self, name: &str) -> Result<Option<RubyGem>, RubyGemsError>;
 }
 
+/// RubyGems lookups remembered by a default [`RubyDiscoverer`], or shared by the
+/// discoverers of one run.
+pub type CachedRubyGemsClient = CachingFetcher<HttpRubyGemsClient, Option<RubyGem>>;
+
+impl RubyGemsFetcher for FileBackedFetcher {
+    fn fetch(&self, name: &str) -> Result<Option<RubyGem>, RubyGemsError> {
+        Ok(self.read(name)?)
+    }
+}
+
+impl<F: RubyGemsFetcher> RubyGemsFetcher for CachingFetcher<F, Option<RubyGem>> {
+    fn fetch(&self, name: &str) -> Result<Option<RubyGem>, RubyGemsError> {
+        self.get_or_fetch(name, |inner, name| inner.fetch(name))
+    }
+}
+
 #[derive(Clone)]
 pub struct HttpRubyGemsClient {
     client: Client,
     base_url: String,
+    retries: u32,
 }
 
 impl Default for HttpRubyGemsClient {
@@ -51,7 +71,25 @@ impl HttpRubyGemsClient {
     }
 
     fn with_client_and_base(client: Client, base_url: String) -> Self {
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            retries: http::DEFAULT_RETRIES,
+        }
+    }
+
+    /// Send every request through `client`, such as one built from a run's
+    /// [`HttpOptions`](crate::http::HttpOptions).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Retry transient failures (timeouts, connection errors, 5xx) up to
+    /// `retries` times before giving up.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     #[cfg(test)]
@@ -63,16 +101,20 @@ impl HttpRubyGemsClient {
 impl RubyGemsFetcher for HttpRubyGemsClient {
     fn fetch(&self, name: &str) -> Result<Option<RubyGem>, RubyGemsError> {
         let url = format!("{}/{name}.json", self.base_url.trim_end_matches('/'));
-        let response = self
-            .client
-            .get(&url)
-            .header(ACCEPT, "application/json")
-            .send()?;
+        let response = http::send_with_retry(self.retries, &http::ThreadSleeper, || {
+            self.client
+                .get(&url)
+                .header(ACCEPT, "application/json")
+                .send()
+        })?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(None),
-            status if !status.is_success() => Err(RubyGemsError::UnexpectedStatus { status }),
-            _ => Ok(Some(response.json()?)),
+            status if !status.is_success() => Err(RubyGemsError::UnexpectedStatus { status, url }),
+            _ => Ok(Some(
+                serde_json::from_slice(&response.bytes()?)
+                    .map_err(|source| RubyGemsError::Decode { source })?,
+            )),
         }
     }
 }
@@ -81,24 +123,31 @@ impl RubyGemsFetcher for HttpRubyGemsClient {
 pub enum RubyGemsError {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
-    #[error("unexpected status {status}")]
-    UnexpectedStatus { status: StatusCode },
+    #[error("unexpected status {status} for {url}")]
+    UnexpectedStatus { status: StatusCode, url: String },
+    #[error("failed to decode registry metadata: {source}")]
+    Decode {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Fixture(#[from] FixtureError),
 }
 
 pub struct RubyDiscoverer<F: RubyGemsFetcher> {
     fetcher: F,
 }
 
-impl Default for RubyDiscoverer<HttpRubyGemsClient> {
+impl Default for RubyDiscoverer<CachedRubyGemsClient> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl RubyDiscoverer<HttpRubyGemsClient> {
+impl RubyDiscoverer<CachedRubyGemsClient> {
     pub fn new() -> Self {
         Self {
-            fetcher: HttpRubyGemsClient::new(),
+            fetcher: CachingFetcher::new(HttpRubyGemsClient::new()),
         }
     }
 }
@@ -109,6 +158,14 @@ impl<F: RubyGemsFetcher> RubyDiscoverer<F> {
     }
 
     pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, RubyDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, RubyDiscoveryError> {
         let mut names = BTreeSet::new();
         for name in read_gemfile_lock(project_root)? {
             names.insert(name);
@@ -119,6 +176,8 @@ impl<F: RubyGemsFetcher> RubyDiscoverer<F> {
 
         let mut repositories = Vec::new();
         for name in names {
+            trace!(gem = %name, "considering gem");
+            stats.record_examined();
             let Some(gem) =
                 self.fetcher
                     .fetch(&name)
@@ -127,16 +186,23 @@ impl<F: RubyGemsFetcher> RubyDiscoverer<F> {
                         source,
                     })?
             else {
+                stats.record_unresolved(&name);
+                debug!(gem = %name, "skipping: not found on RubyGems");
                 continue;
             };
 
-            for candidate in gem.candidate_urls() {
-                if let Some(mut repository) = parse_github_repository(candidate) {
-                    repository.via = Some("RubyGems".to_string());
-                    repositories.push(repository);
-                    break;
-                }
-            }
+            let Some(mut repository) = gem
+                .candidate_urls()
+                .into_iter()
+                .find_map(parse_github_repository)
+            else {
+                stats.record_unresolved(&name);
+                debug!(gem = %name, "skipping: no GitHub URL in gem metadata");
+                continue;
+            };
+            debug!(gem = %name, url = %repository.url, "resolved");
+            repository.via = Some("RubyGems".to_string());
+            repositories.push(repository);
         }
 
         Ok(repositories)
@@ -145,7 +211,7 @@ impl<F: RubyGemsFetcher> RubyDiscoverer<F> {
 
 fn read_gemfile_lock(project_root: &Path) -> Result<Vec<String>, RubyDiscoveryError> {
     let lock_path = project_root.join("Gemfile.lock");
-    let content = match fs::read_to_string(&lock_path) {
+    let content = match read_manifest(&lock_path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
         Err(err) => {
@@ -186,7 +252,7 @@ fn read_gemfile_lock(project_root: &Path) -> Result<Vec<String>, RubyDiscoveryEr
 
 fn read_gemfile(project_root: &Path) -> Result<Vec<String>, RubyDiscoveryError> {
     let gemfile_path = project_root.join("Gemfile");
-    let content = match fs::read_to_string(&gemfile_path) {
+    let content = match read_manifest(&gemfile_path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
         Err(err) => {
@@ -225,7 +291,7 @@ fn normalize_dependency_name(raw: &str) -> Option<String> {
     Some(normalized.to_string())
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct RubyGem {
     #[serde(default)]
@@ -257,7 +323,7 @@ impl RubyGem {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct RubyGemMetadata {
     #[serde(default)]
     source_code_uri: Option<String>,
@@ -294,6 +360,7 @@ mod tests {
     use super::*;
     use std::cell::RefCell;
     use std::collections::HashMap;
+    use std::fs;
     use tempfile::tempdir;
 
     struct StubFetcher {
@@ -348,6 +415,20 @@ DEPENDENCIES
         assert_eq!(repos[0].via.as_deref(), Some("RubyGems"));
     }
 
+    #[test]
+    fn reads_bom_prefixed_crlf_gemfile_lock() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Gemfile.lock"),
+            "\u{feff}GEM\r\n  remote: https://rubygems.org/\r\n  specs:\r\n    rack (2.2.3)\r\n\r\nDEPENDENCIES\r\n  rack (= 2.2.3)\r\n  rails!\r\n\r\nBUNDLED WITH\r\n   2.5.0\r\n",
+        )
+        .unwrap();
+
+        let names = read_gemfile_lock(dir.path()).unwrap();
+
+        assert_eq!(names, vec!["rack", "rails"]);
+    }
+
     #[test]
     fn discovers_repositories_from_gemfile_when_lock_missing() {
         let dir = tempdir().unwrap();
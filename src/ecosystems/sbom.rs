@@ -0,0 +1,288 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tracing::{debug, trace};
+
+use crate::discovery::{parse_github_repository, DiscoveryStats, Repository};
+use crate::ecosystems::text::read_manifest;
+
+/// File names recognised as SBOMs in addition to `*.cdx.json` and
+/// `*.spdx.json`.
+const SBOM_FILE_NAMES: &[&str] = &["bom.json", "sbom.json"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum SbomDiscoveryError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path} as JSON: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Reads CycloneDX and SPDX JSON documents and resolves their components
+/// straight from the URLs they record, without asking any registry.
+#[derive(Default)]
+pub struct SbomDiscoverer;
+
+impl SbomDiscoverer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn discover(&self, project_root: &Path) -> Result<Vec<Repository>, SbomDiscoveryError> {
+        self.discover_with_stats(project_root, &DiscoveryStats::default())
+    }
+
+    pub fn discover_with_stats(
+        &self,
+        project_root: &Path,
+        stats: &DiscoveryStats,
+    ) -> Result<Vec<Repository>, SbomDiscoveryError> {
+        let mut repositories = Vec::new();
+        for path in sbom_files(project_root) {
+            let via = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let content = read_manifest(&path).map_err(|source| SbomDiscoveryError::Io {
+                path: path.display().to_string(),
+                source,
+            })?;
+            let document: SbomDocument =
+                serde_json::from_str(&content).map_err(|source| SbomDiscoveryError::Json {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+
+            for (name, urls) in document.components() {
+                trace!(component = %name, sbom = %via, "considering component");
+                stats.record_examined();
+                let Some(mut repository) = urls.iter().find_map(|url| component_repository(url))
+                else {
+                    stats.record_unresolved(&name);
+                    debug!(component = %name, "skipping: no GitHub URL in SBOM");
+                    continue;
+                };
+                debug!(component = %name, url = %repository.url, "resolved");
+                repository.via = Some(via.clone());
+                repositories.push(repository);
+            }
+        }
+
+        Ok(repositories)
+    }
+}
+
+/// SBOM files directly inside `project_root`, in name order.
+pub fn sbom_files(project_root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = project_root.read_dir() else {
+        return Vec::new();
+    };
+    let files: BTreeSet<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                return false;
+            };
+            let name = name.to_ascii_lowercase();
+            path.is_file()
+                && (SBOM_FILE_NAMES.contains(&name.as_str())
+                    || name.ends_with(".cdx.json")
+                    || name.ends_with(".spdx.json"))
+        })
+        .collect();
+    files.into_iter().collect()
+}
+
+/// A component URL or package URL pointing at GitHub.
+fn component_repository(url: &str) -> Option<Repository> {
+    if let Some(purl) = url.strip_prefix("pkg:") {
+        let path = purl.strip_prefix("github/")?;
+        let path = path.split(['@', '?', '#']).next()?;
+        return parse_github_repository(&format!("https://github.com/{path}"));
+    }
+    parse_github_repository(url)
+}
+
+/// The parts of a CycloneDX (`components`) or SPDX (`packages`) JSON document
+/// that can name a repository.
+#[derive(Debug, Default, Deserialize)]
+struct SbomDocument {
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+    #[serde(default)]
+    packages: Vec<SpdxPackage>,
+}
+
+impl SbomDocument {
+    /// Each component's name with its candidate URLs, best first.
+    fn components(&self) -> Vec<(String, Vec<String>)> {
+        let mut found = Vec::new();
+        let mut pending: Vec<&CycloneDxComponent> = self.components.iter().collect();
+        while let Some(component) = pending.pop() {
+            found.push((component.name.clone(), component.candidate_urls()));
+            pending.extend(component.components.iter());
+        }
+        for package in &self.packages {
+            found.push((package.name.clone(), package.candidate_urls()));
+        }
+        found
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxComponent {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    purl: Option<String>,
+    #[serde(default)]
+    external_references: Vec<CycloneDxReference>,
+    /// Nested sub-components.
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+impl CycloneDxComponent {
+    fn candidate_urls(&self) -> Vec<String> {
+        let mut urls = Vec::new();
+        for kind in ["vcs", "website"] {
+            urls.extend(
+                self.external_references
+                    .iter()
+                    .filter(|reference| reference.kind == kind)
+                    .map(|reference| reference.url.clone()),
+            );
+        }
+        urls.extend(self.purl.clone());
+        urls
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CycloneDxReference {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    download_location: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+    #[serde(default)]
+    external_refs: Vec<SpdxExternalRef>,
+}
+
+impl SpdxPackage {
+    fn candidate_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self
+            .download_location
+            .iter()
+            .chain(self.homepage.iter())
+            .cloned()
+            .collect();
+        urls.extend(
+            self.external_refs
+                .iter()
+                .filter(|reference| reference.reference_type == "purl")
+                .map(|reference| reference.reference_locator.clone()),
+        );
+        urls
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxExternalRef {
+    #[serde(default)]
+    reference_type: String,
+    #[serde(default)]
+    reference_locator: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn discovers_github_components_from_cyclonedx_and_spdx() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("app.cdx.json"),
+            json!({
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "components": [
+                    {
+                        "name": "serde",
+                        "purl": "pkg:cargo/serde@1.0.0",
+                        "externalReferences": [
+                            { "type": "website", "url": "https://serde.rs" },
+                            { "type": "vcs", "url": "https://github.com/serde-rs/serde" }
+                        ]
+                    },
+                    { "name": "left-pad", "purl": "pkg:npm/left-pad@1.3.0" },
+                    { "name": "tokio", "purl": "pkg:github/tokio-rs/tokio@v1.0.0" }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("sbom.spdx.json"),
+            json!({
+                "spdxVersion": "SPDX-2.3",
+                "packages": [
+                    {
+                        "name": "requests",
+                        "downloadLocation": "git+https://github.com/psf/requests.git",
+                        "homepage": "NOASSERTION"
+                    }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let stats = DiscoveryStats::default();
+        let mut repos = SbomDiscoverer::new()
+            .discover_with_stats(dir.path(), &stats)
+            .unwrap();
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let found: Vec<_> = repos
+            .iter()
+            .map(|repo| (repo.url.as_str(), repo.via.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("https://github.com/psf/requests", "sbom.spdx.json"),
+                ("https://github.com/serde-rs/serde", "app.cdx.json"),
+                ("https://github.com/tokio-rs/tokio", "app.cdx.json"),
+            ]
+        );
+        assert_eq!(stats.examined(), 4);
+        assert_eq!(stats.unresolved(), 1);
+    }
+}
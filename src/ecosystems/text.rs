@@ -0,0 +1,36 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Read a manifest as text, dropping a leading UTF-8 byte order mark and
+/// turning CRLF line endings into LF, as editors on Windows often leave them.
+pub(crate) fn read_manifest(path: impl AsRef<Path>) -> io::Result<String> {
+    fs::read_to_string(path).map(normalize)
+}
+
+/// Parse the first document of a YAML manifest and resolve `<<` merge keys,
+/// so files starting with `---` or sharing blocks through anchors read like
+/// plain ones. Later documents are ignored; an empty file is `Null`.
+#[cfg(any(feature = "ecosystem-dart", feature = "ecosystem-haskell"))]
+pub(crate) fn parse_yaml(content: &str) -> Result<serde_yaml::Value, serde_yaml::Error> {
+    use serde::Deserialize;
+
+    let Some(document) = serde_yaml::Deserializer::from_str(content).next() else {
+        return Ok(serde_yaml::Value::Null);
+    };
+    let mut value = serde_yaml::Value::deserialize(document)?;
+    value.apply_merge()?;
+    Ok(value)
+}
+
+fn normalize(content: String) -> String {
+    let content = match content.strip_prefix('\u{feff}') {
+        Some(rest) => rest.to_string(),
+        None => content,
+    };
+    if content.contains('\r') {
+        content.replace("\r\n", "\n")
+    } else {
+        content
+    }
+}
@@ -0,0 +1,100 @@
+use crate::discovery::Repository;
+
+/// Hook for inspecting or rewriting the discovered repositories before any of
+/// them are starred. Filters run in order on the deduplicated list; a
+/// repository dropped by one filter is not seen by the next.
+pub trait RepositoryFilter {
+    /// Whether `repo` should be starred.
+    fn keep(&self, _repo: &Repository) -> bool {
+        true
+    }
+
+    /// Replace `repo` with another repository (for example a fork with its
+    /// upstream), or return `None` to drop it. Defaults to [`keep`](Self::keep).
+    fn map(&self, repo: Repository) -> Option<Repository> {
+        self.keep(&repo).then_some(repo)
+    }
+
+    /// Why this filter drops repositories, completing "skipped N
+    /// repositories …". The run summary counts dropped repositories per
+    /// reason.
+    fn reason(&self) -> &str {
+        "dropped by a filter"
+    }
+}
+
+/// Keeps only repositories whose owner is in the list, ignoring ASCII case.
+#[derive(Debug, Clone, Default)]
+pub struct OwnerAllowlist {
+    owners: Vec<String>,
+}
+
+impl OwnerAllowlist {
+    pub fn new(owners: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            owners: owners.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl RepositoryFilter for OwnerAllowlist {
+    fn keep(&self, repo: &Repository) -> bool {
+        self.owners
+            .iter()
+            .any(|owner| owner.eq_ignore_ascii_case(&repo.owner))
+    }
+
+    fn reason(&self) -> &str {
+        "outside the owner allowlist"
+    }
+}
+
+/// Drops the listed `owner/name` repositories, ignoring ASCII case.
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeList {
+    repositories: Vec<(String, String)>,
+}
+
+impl ExcludeList {
+    /// Build the list from `owner/name` strings; entries without a `/` are
+    /// ignored.
+    pub fn new<S: AsRef<str>>(repositories: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            repositories: repositories
+                .into_iter()
+                .filter_map(|entry| {
+                    let (owner, name) = entry.as_ref().trim().split_once('/')?;
+                    Some((owner.to_string(), name.to_string()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RepositoryFilter for ExcludeList {
+    fn keep(&self, repo: &Repository) -> bool {
+        !self.repositories.iter().any(|(owner, name)| {
+            owner.eq_ignore_ascii_case(&repo.owner) && name.eq_ignore_ascii_case(&repo.name)
+        })
+    }
+
+    fn reason(&self) -> &str {
+        "on the exclude list"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::parse_github_repository;
+
+    #[test]
+    fn built_in_filters_ignore_case() {
+        let repo = parse_github_repository("https://github.com/Rust-Lang/Cargo").unwrap();
+
+        assert!(OwnerAllowlist::new(["rust-lang"]).keep(&repo));
+        assert!(!OwnerAllowlist::new(["tokio-rs"]).keep(&repo));
+        assert!(!ExcludeList::new(["rust-lang/cargo"]).keep(&repo));
+        assert!(ExcludeList::new(["rust-lang/rust", "cargo"]).keep(&repo));
+    }
+}
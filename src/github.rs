@@ -1,86 +1,358 @@
-use reqwest::blocking::Client;
-use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, RETRY_AFTER, USER_AGENT,
+};
+use reqwest::{Proxy, StatusCode};
 use serde::Deserialize;
 
+use crate::http;
+pub use crate::http::{Sleeper, ThreadSleeper};
+
 #[derive(Debug, thiserror::Error)]
 pub enum GitHubError {
     #[error("failed to build HTTP client: {0}")]
     ClientBuild(#[from] reqwest::Error),
     #[error("GitHub API responded with status {status}: {body}")]
     Api { status: u16, body: String },
+    #[error(
+        "the GitHub token is not allowed to star {repository} (granted scopes: {})",
+        granted_scopes.as_deref().unwrap_or("unknown")
+    )]
+    InsufficientScope {
+        repository: String,
+        granted_scopes: Option<String>,
+    },
+    #[error(
+        "GitHub secondary rate limit exceeded; retry after {} seconds",
+        retry_after.as_secs()
+    )]
+    SecondaryRateLimit { retry_after: Duration },
+    #[error("GitHub did not respond within {} seconds", .0.as_secs())]
+    Timeout(Duration),
+    #[error("invalid HTTP header {name:?}: {reason}")]
+    InvalidHeader { name: String, reason: String },
+    /// A header given without a `:`. The text is left out of the message
+    /// since it may be a credential.
+    #[error("invalid HTTP header in {origin}: expected `Name: Value`")]
+    MalformedHeader { origin: &'static str },
+    #[error("invalid GitHub URL {url:?}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+}
+
+/// What [`GitHubApi::repository_status`] reports about a repository.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepositoryStatus {
+    pub viewer_has_starred: bool,
+    pub is_archived: bool,
+    /// How many users have starred the repository, when the implementation
+    /// knows.
+    pub stargazer_count: Option<u64>,
+}
+
+/// How many requests a client has sent to GitHub, and the rate-limit budget
+/// reported by the most recent response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApiUsage {
+    pub calls: usize,
+    /// `X-RateLimit-Remaining` of the last response that carried it.
+    pub rate_limit_remaining: Option<u32>,
+    /// `X-RateLimit-Reset` (seconds since the Unix epoch) of the last response
+    /// that carried it.
+    pub rate_limit_reset: Option<u64>,
 }
 
 pub trait GitHubApi {
     fn viewer_has_starred(&self, owner: &str, repo: &str) -> Result<bool, GitHubError>;
     fn star(&self, owner: &str, repo: &str) -> Result<(), GitHubError>;
+
+    /// Whether the viewer has starred the repository, whether it is archived
+    /// and how popular it is. Implementations that cannot tell report it as
+    /// not archived, with no stargazer count.
+    fn repository_status(&self, owner: &str, repo: &str) -> Result<RepositoryStatus, GitHubError> {
+        Ok(RepositoryStatus {
+            viewer_has_starred: self.viewer_has_starred(owner, repo)?,
+            is_archived: false,
+            stargazer_count: None,
+        })
+    }
+
+    /// Requests made so far, or `None` for implementations that do not talk
+    /// to GitHub themselves.
+    fn usage(&self) -> Option<ApiUsage> {
+        None
+    }
 }
 
 pub struct GitHubClient {
     token: String,
     client: Client,
     base_url: String,
+    graphql_url: String,
+    timeout: Duration,
+    retries: u32,
+    extra_headers: HeaderMap,
+    proxy: Option<Proxy>,
+    usage: Mutex<ApiUsage>,
+    sleeper: Box<dyn Sleeper>,
 }
 
 impl GitHubClient {
+    /// How long a single request may take before it is abandoned.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+    /// How many times a request that timed out or hit a server error is retried.
+    pub const DEFAULT_RETRIES: u32 = http::DEFAULT_RETRIES;
+
     pub fn new(token: impl Into<String>) -> Result<Self, GitHubError> {
         Self::with_base_url(token, "https://api.github.com")
     }
 
+    /// Talk to the REST API at `base_url`. GraphQL requests go to
+    /// `{base_url}/graphql`, except for a GitHub Enterprise Server base
+    /// ending in `/api/v3`, whose GraphQL endpoint is `/api/graphql`.
     pub fn with_base_url(
         token: impl Into<String>,
         base_url: impl Into<String>,
     ) -> Result<Self, GitHubError> {
         let token = token.into();
         let base_url = base_url.into().trim_end_matches('/').to_string();
-        let client = Client::builder().user_agent("thanks-stars").build()?;
+        let graphql_url = match base_url.strip_suffix("/api/v3") {
+            Some(host) => format!("{host}/api/graphql"),
+            None => format!("{base_url}/graphql"),
+        };
         Ok(Self {
             token,
-            client,
+            client: build_client(Self::DEFAULT_TIMEOUT, None)?,
             base_url,
+            graphql_url,
+            timeout: Self::DEFAULT_TIMEOUT,
+            retries: Self::DEFAULT_RETRIES,
+            extra_headers: HeaderMap::new(),
+            proxy: None,
+            usage: Mutex::default(),
+            sleeper: Box::new(ThreadSleeper),
         })
     }
 
+    /// Talk to the GitHub instance whose web interface is at `github_url`:
+    /// `api.github.com` for `https://github.com`, and the `/api/v3` and
+    /// `/api/graphql` endpoints of a GitHub Enterprise Server otherwise.
+    pub fn for_github_url(token: impl Into<String>, github_url: &str) -> Result<Self, GitHubError> {
+        let invalid = |reason: String| GitHubError::InvalidUrl {
+            url: github_url.to_string(),
+            reason,
+        };
+        let url = url::Url::parse(github_url).map_err(|err| invalid(err.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid("expected an http or https URL".to_string()));
+        }
+        if url.query().is_some() || url.fragment().is_some() {
+            return Err(invalid("the URL has a query or fragment".to_string()));
+        }
+        match url.host_str() {
+            None => Err(invalid("the URL has no host".to_string())),
+            Some("github.com" | "www.github.com") => Self::new(token),
+            Some(_) => {
+                let root = format!(
+                    "{}{}",
+                    url.origin().ascii_serialization(),
+                    url.path().trim_end_matches('/')
+                );
+                Self::with_base_url(token, format!("{root}/api/v3"))
+            }
+        }
+    }
+
+    /// Abandon requests that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Self, GitHubError> {
+        self.client = build_client(timeout, self.proxy.as_ref())?;
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    /// Send requests through the proxy at `url` rather than the one from
+    /// `HTTP_PROXY`/`HTTPS_PROXY`. Hosts in `NO_PROXY` are still reached
+    /// directly.
+    pub fn with_proxy(mut self, url: &str) -> Result<Self, GitHubError> {
+        let proxy = http::proxy(url)?;
+        self.client = build_client(self.timeout, Some(&proxy))?;
+        self.proxy = Some(proxy);
+        Ok(self)
+    }
+
+    /// Retry requests that time out or fail with a 5xx status up to `retries`
+    /// times, backing off between attempts. Secondary rate limits are waited
+    /// out for as long as GitHub asks, up to `retries` times as well.
+    pub fn with_retry(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Wait between retries with `sleeper` instead of blocking the thread.
+    pub fn with_sleeper(mut self, sleeper: impl Sleeper + 'static) -> Self {
+        self.sleeper = Box::new(sleeper);
+        self
+    }
+
+    /// Send `name: value` with every request, for example a proxy
+    /// authorization header. Later headers with the same name replace
+    /// earlier ones.
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self, GitHubError> {
+        let invalid = |reason: String| GitHubError::InvalidHeader {
+            name: name.to_string(),
+            reason,
+        };
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|err| invalid(err.to_string()))?;
+        let header_value =
+            HeaderValue::from_str(value.trim()).map_err(|err| invalid(err.to_string()))?;
+        self.extra_headers.insert(header_name, header_value);
+        Ok(self)
+    }
+
+    /// Send the request built by `request` and read the whole response,
+    /// retrying transient failures and waiting out secondary rate limits
+    /// through the sleeper.
+    fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Reply, GitHubError> {
+        let mut waits = 0;
+        loop {
+            let response = http::send_with_retry(self.retries, &*self.sleeper, || {
+                let result = request().send();
+                self.record_usage(result.as_ref().ok());
+                result
+            })
+            .map_err(|err| self.transport_error(err))?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().map_err(|err| self.transport_error(err))?;
+            let body = String::from_utf8_lossy(&body).into_owned();
+            match secondary_rate_limit(status.as_u16(), &headers, &body) {
+                Some(retry_after) if waits < self.retries => {
+                    self.sleeper.sleep(retry_after);
+                    waits += 1;
+                }
+                Some(retry_after) => return Err(GitHubError::SecondaryRateLimit { retry_after }),
+                None => {
+                    return Ok(Reply {
+                        status,
+                        headers,
+                        body,
+                    })
+                }
+            }
+        }
+    }
+
+    fn record_usage(&self, response: Option<&Response>) {
+        let mut usage = self
+            .usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        usage.calls += 1;
+        let Some(headers) = response.map(Response::headers) else {
+            return;
+        };
+        if let Some(remaining) = header_number(headers, "x-ratelimit-remaining") {
+            usage.rate_limit_remaining = Some(remaining);
+        }
+        if let Some(reset) = header_number(headers, "x-ratelimit-reset") {
+            usage.rate_limit_reset = Some(reset);
+        }
+    }
+
+    fn transport_error(&self, err: reqwest::Error) -> GitHubError {
+        if err.is_timeout() {
+            GitHubError::Timeout(self.timeout)
+        } else {
+            GitHubError::ClientBuild(err)
+        }
+    }
+
     fn auth_header(&self) -> String {
         format!("token {}", self.token)
     }
+
+    /// Build a [`GitHubError::Api`] whose body never contains the token.
+    ///
+    /// Replacing the raw token also scrubs any echoed `Authorization` header,
+    /// since its value is the token with a `token ` prefix.
+    fn api_error(&self, status: u16, body: impl AsRef<str>) -> GitHubError {
+        GitHubError::Api {
+            status,
+            body: redact(body.as_ref(), &self.token),
+        }
+    }
+
+    /// Return the login of the user the token authenticates as.
+    pub fn current_user(&self) -> Result<String, GitHubError> {
+        let url = format!("{}/user", self.base_url);
+        let Reply { status, body, .. } = self.send(|| {
+            self.client
+                .get(&url)
+                .header(USER_AGENT, "thanks-stars")
+                .header(ACCEPT, "application/vnd.github.v3+json")
+                .header(AUTHORIZATION, self.auth_header())
+                .headers(self.extra_headers.clone())
+        })?;
+
+        if !status.is_success() {
+            return Err(self.api_error(status.as_u16(), body));
+        }
+
+        let user: RestUser = serde_json::from_str(&body).map_err(|err| {
+            self.api_error(
+                status.as_u16(),
+                format!("failed to parse user response: {err}; body: {body}"),
+            )
+        })?;
+
+        Ok(user.login)
+    }
 }
 
 impl GitHubApi for GitHubClient {
+    fn usage(&self) -> Option<ApiUsage> {
+        Some(
+            *self
+                .usage
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+
     fn viewer_has_starred(&self, owner: &str, repo: &str) -> Result<bool, GitHubError> {
-        let url = format!("{}/graphql", self.base_url);
+        Ok(self.repository_status(owner, repo)?.viewer_has_starred)
+    }
+
+    fn repository_status(&self, owner: &str, repo: &str) -> Result<RepositoryStatus, GitHubError> {
+        let url = &self.graphql_url;
         let query = serde_json::json!({
-            "query": "query($owner:String!,$name:String!){repository(owner:$owner,name:$name){viewerHasStarred}}",
+            "query": "query($owner:String!,$name:String!){repository(owner:$owner,name:$name){viewerHasStarred isArchived stargazerCount}}",
             "variables": {"owner": owner, "name": repo}
         });
 
-        let response = self
-            .client
-            .post(url)
-            .header(USER_AGENT, "thanks-stars")
-            .header(ACCEPT, "application/vnd.github+json")
-            .header(AUTHORIZATION, self.auth_header())
-            .json(&query)
-            .send()
-            .map_err(GitHubError::from)?;
-
-        let status = response.status();
-        let body = response.bytes().map_err(GitHubError::from)?;
+        let Reply { status, body, .. } = self.send(|| {
+            self.client
+                .post(url)
+                .header(USER_AGENT, "thanks-stars")
+                .header(ACCEPT, "application/vnd.github+json")
+                .header(AUTHORIZATION, self.auth_header())
+                .headers(self.extra_headers.clone())
+                .json(&query)
+        })?;
 
         if !status.is_success() {
-            return Err(GitHubError::Api {
-                status: status.as_u16(),
-                body: String::from_utf8_lossy(&body).into_owned(),
-            });
+            return Err(self.api_error(status.as_u16(), body));
         }
 
-        let parsed: GraphqlResponse =
-            serde_json::from_slice(&body).map_err(|err| GitHubError::Api {
-                status: status.as_u16(),
-                body: format!(
-                    "failed to parse GraphQL response: {err}; body: {}",
-                    String::from_utf8_lossy(&body)
-                ),
-            })?;
+        let parsed: GraphqlResponse = serde_json::from_str(&body).map_err(|err| {
+            self.api_error(
+                status.as_u16(),
+                format!("failed to parse GraphQL response: {err}; body: {body}"),
+            )
+        })?;
 
         if let Some(errors) = parsed.errors {
             let message = errors
@@ -88,42 +360,124 @@ impl GitHubApi for GitHubClient {
                 .map(|error| error.message)
                 .collect::<Vec<_>>()
                 .join(", ");
-            return Err(GitHubError::Api {
-                status: status.as_u16(),
-                body: message,
-            });
+            return Err(self.api_error(status.as_u16(), message));
         }
 
         let repo_data = parsed
             .data
             .and_then(|data| data.repository)
-            .ok_or_else(|| GitHubError::Api {
-                status: status.as_u16(),
-                body: "repository data missing from GraphQL response".to_string(),
+            .ok_or_else(|| {
+                self.api_error(
+                    status.as_u16(),
+                    "repository data missing from GraphQL response",
+                )
             })?;
 
-        Ok(repo_data.viewer_has_starred)
+        Ok(RepositoryStatus {
+            viewer_has_starred: repo_data.viewer_has_starred,
+            is_archived: repo_data.is_archived,
+            stargazer_count: repo_data.stargazer_count,
+        })
     }
 
     fn star(&self, owner: &str, repo: &str) -> Result<(), GitHubError> {
         let url = format!("{}/user/starred/{}/{}", self.base_url, owner, repo);
-        let response = self
-            .client
-            .put(url)
-            .header(USER_AGENT, "thanks-stars")
-            .header(ACCEPT, "application/vnd.github.v3+json")
-            .header(AUTHORIZATION, self.auth_header())
-            .send()
-            .map_err(GitHubError::from)?;
-
-        if response.status().is_success() || response.status().as_u16() == 304 {
+        let Reply {
+            status,
+            headers,
+            body,
+        } = self.send(|| {
+            self.client
+                .put(&url)
+                .header(USER_AGENT, "thanks-stars")
+                .header(ACCEPT, "application/vnd.github.v3+json")
+                .header(AUTHORIZATION, self.auth_header())
+                .headers(self.extra_headers.clone())
+        })?;
+
+        if status.is_success() || status == StatusCode::NOT_MODIFIED {
             return Ok(());
         }
 
-        let status = response.status().as_u16();
-        let body = response.text().unwrap_or_default();
-        Err(GitHubError::Api { status, body })
+        let granted_scopes = granted_scopes(&headers);
+        let status = status.as_u16();
+        if lacks_star_scope(status, granted_scopes.as_deref(), &body) {
+            return Err(GitHubError::InsufficientScope {
+                repository: format!("{owner}/{repo}"),
+                granted_scopes,
+            });
+        }
+        Err(self.api_error(status, body))
+    }
+}
+
+/// A response read in full by [`GitHubClient::send`].
+struct Reply {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: String,
+}
+
+/// The numeric value of header `name`, if present and well formed.
+fn header_number<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// How long GitHub asks us to back off when a response reports a secondary
+/// rate limit. Falls back to a minute when `Retry-After` is missing, as GitHub
+/// recommends.
+fn secondary_rate_limit(status: u16, headers: &HeaderMap, body: &str) -> Option<Duration> {
+    if !matches!(status, 403 | 429) || !body.to_ascii_lowercase().contains("secondary rate limit") {
+        return None;
+    }
+    let seconds = headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(60);
+    Some(Duration::from_secs(seconds))
+}
+
+/// Scopes a classic personal access token was granted, from `X-OAuth-Scopes`.
+/// Fine-grained tokens do not send the header.
+fn granted_scopes(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-oauth-scopes")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+}
+
+/// GitHub answers a star request from a token without the right scope with a
+/// 404 (classic tokens) or a 403 "Resource not accessible" (fine-grained
+/// tokens) rather than a 401.
+fn lacks_star_scope(status: u16, granted_scopes: Option<&str>, body: &str) -> bool {
+    match status {
+        404 => granted_scopes.is_some_and(|scopes| {
+            !scopes
+                .split(',')
+                .map(str::trim)
+                .any(|scope| matches!(scope, "repo" | "public_repo"))
+        }),
+        403 => body.contains("Resource not accessible"),
+        _ => false,
+    }
+}
+
+fn build_client(timeout: Duration, proxy: Option<&Proxy>) -> Result<Client, GitHubError> {
+    let mut builder = Client::builder()
+        .user_agent("thanks-stars")
+        .timeout(timeout);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    Ok(builder.build()?)
+}
+
+fn redact(text: &str, token: &str) -> String {
+    if token.is_empty() {
+        return text.to_string();
     }
+    text.replace(token, "***")
 }
 
 #[derive(Debug, Deserialize)]
@@ -141,6 +495,15 @@ struct GraphqlData {
 struct GraphqlRepository {
     #[serde(rename = "viewerHasStarred")]
     viewer_has_starred: bool,
+    #[serde(rename = "isArchived", default)]
+    is_archived: bool,
+    #[serde(rename = "stargazerCount", default)]
+    stargazer_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestUser {
+    login: String,
 }
 
 #[derive(Debug, Deserialize)]
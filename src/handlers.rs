@@ -0,0 +1,241 @@
+#[cfg(feature = "progress")]
+use std::time::Duration;
+
+#[cfg(feature = "progress")]
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::discovery::Repository;
+use crate::github::GitHubError;
+use crate::{RunEventHandler, RunSummary};
+
+/// Ignores every event and accepts every confirmation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SilentHandler;
+
+impl RunEventHandler for SilentHandler {}
+
+/// A run event, as recorded by [`CollectingHandler`].
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    Confirm {
+        repositories: Vec<Repository>,
+    },
+    Prechecking {
+        total: usize,
+    },
+    Start {
+        total: usize,
+    },
+    Starred {
+        repository: Repository,
+        already_starred: bool,
+        index: usize,
+        total: usize,
+    },
+    Archived {
+        repository: Repository,
+        index: usize,
+        total: usize,
+    },
+    Failed {
+        repository: Repository,
+        error: String,
+        index: usize,
+        total: usize,
+    },
+    Complete(Box<RunSummary>),
+}
+
+/// Records every event in order, which makes it handy for asserting on what a
+/// run did.
+#[derive(Debug, Clone)]
+pub struct CollectingHandler {
+    events: Vec<RunEvent>,
+    confirm: bool,
+}
+
+impl Default for CollectingHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CollectingHandler {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            confirm: true,
+        }
+    }
+
+    /// Answer [`RunEventHandler::confirm`] with `confirm` instead of `true`.
+    pub fn with_confirmation(mut self, confirm: bool) -> Self {
+        self.confirm = confirm;
+        self
+    }
+
+    pub fn events(&self) -> &[RunEvent] {
+        &self.events
+    }
+
+    pub fn into_events(self) -> Vec<RunEvent> {
+        self.events
+    }
+}
+
+impl RunEventHandler for CollectingHandler {
+    fn confirm(&mut self, repositories: &[Repository]) -> bool {
+        self.events.push(RunEvent::Confirm {
+            repositories: repositories.to_vec(),
+        });
+        self.confirm
+    }
+
+    fn on_prechecking(&mut self, total: usize) {
+        self.events.push(RunEvent::Prechecking { total });
+    }
+
+    fn on_start(&mut self, total: usize) {
+        self.events.push(RunEvent::Start { total });
+    }
+
+    fn on_starred(&mut self, repo: &Repository, already_starred: bool, index: usize, total: usize) {
+        self.events.push(RunEvent::Starred {
+            repository: repo.clone(),
+            already_starred,
+            index,
+            total,
+        });
+    }
+
+    fn on_archived(&mut self, repo: &Repository, index: usize, total: usize) {
+        self.events.push(RunEvent::Archived {
+            repository: repo.clone(),
+            index,
+            total,
+        });
+    }
+
+    fn on_failed(&mut self, repo: &Repository, error: &GitHubError, index: usize, total: usize) {
+        self.events.push(RunEvent::Failed {
+            repository: repo.clone(),
+            error: error.to_string(),
+            index,
+            total,
+        });
+    }
+
+    fn on_complete(&mut self, summary: &RunSummary) {
+        self.events
+            .push(RunEvent::Complete(Box::new(summary.clone())));
+    }
+}
+
+/// A progress bar on stderr counting the repositories handled so far, with
+/// plain ASCII glyphs if `ascii` is set.
+#[cfg(feature = "progress")]
+pub fn progress_bar(total: usize, ascii: bool) -> ProgressBar {
+    let pb = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+    let style = if ascii {
+        ProgressStyle::with_template("{spinner:.green} {pos}/{len} {wide_msg}")
+            .unwrap()
+            .tick_chars("|/-\\ ")
+    } else {
+        ProgressStyle::with_template("{spinner:.green} {pos}/{len} ⭐ {wide_msg}")
+            .unwrap()
+            .tick_chars("⠁⠃⠇⡇⣇⣧⣷⣿")
+    };
+    pb.set_style(style);
+    pb.enable_steady_tick(Duration::from_millis(120));
+    pb
+}
+
+/// Shows a [`progress_bar`] while repositories are starred and clears it once
+/// the run completes. Nothing else is printed, so callers are free to render
+/// the summary their own way.
+#[cfg(feature = "progress")]
+#[derive(Debug, Default)]
+pub struct ProgressReporter {
+    bar: Option<ProgressBar>,
+    ascii: bool,
+}
+
+#[cfg(feature = "progress")]
+impl ProgressReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw the bar without emoji or braille spinner characters.
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// The bar of the run in progress, e.g. to print lines above it with
+    /// [`ProgressBar::println`].
+    pub fn bar(&self) -> Option<&ProgressBar> {
+        self.bar.as_ref()
+    }
+}
+
+#[cfg(feature = "progress")]
+impl RunEventHandler for ProgressReporter {
+    fn on_prechecking(&mut self, total: usize) {
+        if total > 0 {
+            self.bar = Some(progress_bar(total, self.ascii));
+        }
+    }
+
+    fn on_start(&mut self, total: usize) {
+        if total > 0 && self.bar.is_none() {
+            self.bar = Some(progress_bar(total, self.ascii));
+        }
+    }
+
+    fn on_starred(
+        &mut self,
+        repo: &Repository,
+        _already_starred: bool,
+        _index: usize,
+        _total: usize,
+    ) {
+        if let Some(pb) = &self.bar {
+            pb.set_message(repo.url.clone());
+            pb.inc(1);
+        }
+    }
+
+    fn on_archived(&mut self, _repo: &Repository, _index: usize, _total: usize) {
+        if let Some(pb) = &self.bar {
+            pb.inc(1);
+        }
+    }
+
+    fn on_failed(
+        &mut self,
+        _repo: &Repository,
+        _error: &GitHubError,
+        _index: usize,
+        _total: usize,
+    ) {
+        if let Some(pb) = &self.bar {
+            pb.inc(1);
+        }
+    }
+
+    fn on_complete(&mut self, _summary: &RunSummary) {
+        if let Some(pb) = self.bar.take() {
+            pb.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(feature = "progress")]
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        if let Some(pb) = self.bar.take() {
+            pb.finish_and_clear();
+        }
+    }
+}
@@ -1,8 +1,25 @@
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
 use std::sync::LazyLock;
+use std::thread;
+use std::time::Duration;
 
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{NoProxy, Proxy};
 
-static SHARED_CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+/// How many times a request that timed out, failed to connect, or got a 5xx
+/// response is retried, for GitHub and package registries alike.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+static SHARED_CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    Client::builder()
+        .build()
+        .expect("failed to build shared HTTP client")
+});
 
 /// Return a clone of the globally shared blocking [`Client`].
 ///
@@ -12,3 +29,300 @@ static SHARED_CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
 pub fn shared_client() -> Client {
     SHARED_CLIENT.clone()
 }
+
+/// How the registry fetchers of one run reach the network.
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Give up on a request after this long instead of reqwest's default.
+    pub timeout: Option<Duration>,
+    /// Send requests through this proxy, such as one from [`proxy`], instead
+    /// of the one configured by the `HTTP_PROXY`/`HTTPS_PROXY` environment
+    /// variables.
+    pub proxy: Option<Proxy>,
+    /// How many times a request that timed out, failed to connect, or got a
+    /// 5xx response is retried. Defaults to [`DEFAULT_RETRIES`].
+    pub retries: u32,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+}
+
+impl HttpOptions {
+    /// A client with these options, or the [`shared_client`] when they
+    /// change nothing.
+    pub fn client(&self) -> Client {
+        if self.timeout.is_none() && self.proxy.is_none() {
+            return shared_client();
+        }
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        builder.build().expect("failed to build HTTP client")
+    }
+}
+
+/// A proxy for all requests through `url` that still lets hosts listed in
+/// `NO_PROXY` connect directly.
+pub fn proxy(url: &str) -> reqwest::Result<Proxy> {
+    Ok(Proxy::all(url)?.no_proxy(NoProxy::from_env()))
+}
+
+/// Credentials for a private or authenticated package registry.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RegistryCredentials {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer(String),
+}
+
+impl fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::Bearer(_) => f.write_str("Bearer(..)"),
+        }
+    }
+}
+
+impl RegistryCredentials {
+    /// Read the credentials for `registry` (e.g. `PYPI`) from the environment:
+    /// a bearer token in `THANKS_STARS_<REGISTRY>_TOKEN`, or else basic auth
+    /// from `THANKS_STARS_<REGISTRY>_USERNAME` and `..._PASSWORD`.
+    pub fn from_env(registry: &str) -> Option<Self> {
+        Self::from_lookup(registry, |name| env::var(name).ok())
+    }
+
+    /// Like [`from_env`](Self::from_env), reading variables through `env`.
+    pub fn from_lookup(registry: &str, env: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let var = |suffix: &str| {
+            env(&format!("THANKS_STARS_{registry}_{suffix}")).filter(|value| !value.is_empty())
+        };
+        if let Some(token) = var("TOKEN") {
+            return Some(Self::Bearer(token));
+        }
+        var("USERNAME").map(|username| Self::Basic {
+            username,
+            password: var("PASSWORD"),
+        })
+    }
+}
+
+/// A private registry that replaces a public one, such as an internal PyPI
+/// index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateRegistry {
+    pub url: String,
+    pub credentials: Option<RegistryCredentials>,
+}
+
+impl PrivateRegistry {
+    /// Read `registry`'s base URL from `THANKS_STARS_<REGISTRY>_URL` and its
+    /// [credentials](RegistryCredentials::from_env). `None` when no URL is
+    /// set: credentials are only ever sent to the configured registry, never
+    /// to the public one.
+    pub fn from_env(registry: &str) -> Option<Self> {
+        Self::from_lookup(registry, |name| env::var(name).ok())
+    }
+
+    /// Like [`from_env`](Self::from_env), reading variables through `env`.
+    pub fn from_lookup(registry: &str, env: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let url = env(&format!("THANKS_STARS_{registry}_URL"))
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())?;
+        Some(Self {
+            url,
+            credentials: RegistryCredentials::from_lookup(registry, env),
+        })
+    }
+}
+
+/// Add `credentials`, if any, to `request`.
+pub fn authenticate(
+    request: RequestBuilder,
+    credentials: Option<&RegistryCredentials>,
+) -> RequestBuilder {
+    match credentials {
+        Some(RegistryCredentials::Basic { username, password }) => {
+            request.basic_auth(username, password.as_ref())
+        }
+        Some(RegistryCredentials::Bearer(token)) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// Waits between retries. Tests can swap in one that records the requested
+/// durations instead of sleeping.
+pub trait Sleeper: Send + Sync {
+    fn sleep(&self, duration: Duration);
+}
+
+/// A [`Sleeper`] that blocks the current thread.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Call `send`, retrying up to `retries` times when the request times out,
+/// cannot connect, or gets a server error, and waiting through `sleeper`
+/// between attempts. Attempts back off exponentially with random jitter so
+/// parallel lookups do not retry in lock step. Any other response, including a
+/// 404, is returned immediately; after the last attempt the final response or
+/// error is returned as is.
+pub fn send_with_retry(
+    retries: u32,
+    sleeper: &dyn Sleeper,
+    mut send: impl FnMut() -> reqwest::Result<Response>,
+) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let result = send();
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(err) => err.is_timeout() || err.is_connect(),
+        };
+        if !retryable || attempt >= retries {
+            return result;
+        }
+        sleeper.sleep(backoff(attempt));
+        attempt += 1;
+    }
+}
+
+/// Delay before retry number `attempt + 1`: the base delay doubled per
+/// attempt, plus up to half of it again as jitter.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_BACKOFF * 2u32.saturating_pow(attempt.min(8));
+    let jitter = RandomState::new().build_hasher().finish() % (delay.as_millis() as u64 / 2 + 1);
+    delay + Duration::from_millis(jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_with_bounded_jitter() {
+        for attempt in 0..4 {
+            let base = BASE_BACKOFF * 2u32.pow(attempt);
+            let delay = backoff(attempt);
+            assert!(delay >= base && delay <= base * 3 / 2, "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn private_registry_requires_a_url() {
+        let token_only = |name: &str| (name == "THANKS_STARS_PYPI_TOKEN").then(|| "s3cret".into());
+        assert_eq!(PrivateRegistry::from_lookup("PYPI", token_only), None);
+
+        let configured = |name: &str| match name {
+            "THANKS_STARS_PYPI_URL" => Some(" https://pypi.example.com/pypi ".to_string()),
+            "THANKS_STARS_PYPI_TOKEN" => Some("s3cret".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            PrivateRegistry::from_lookup("PYPI", configured),
+            Some(PrivateRegistry {
+                url: "https://pypi.example.com/pypi".to_string(),
+                credentials: Some(RegistryCredentials::Bearer("s3cret".to_string())),
+            })
+        );
+    }
+
+    #[test]
+    fn send_with_retry_waits_through_the_sleeper() {
+        use httpmock::prelude::*;
+        use std::sync::Mutex;
+
+        struct RecordingSleeper(Mutex<Vec<Duration>>);
+
+        impl Sleeper for RecordingSleeper {
+            fn sleep(&self, duration: Duration) {
+                self.0.lock().unwrap().push(duration);
+            }
+        }
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/flaky");
+            then.status(503);
+        });
+
+        let sleeper = RecordingSleeper(Mutex::default());
+        let client = Client::new();
+        let response =
+            send_with_retry(2, &sleeper, || client.get(server.url("/flaky")).send()).unwrap();
+
+        assert_eq!(response.status(), 503);
+        mock.assert_calls(3);
+        assert_eq!(sleeper.0.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn client_gives_up_after_the_configured_timeout() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/slow");
+            then.status(200).delay(Duration::from_secs(5));
+        });
+
+        let options = HttpOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..HttpOptions::default()
+        };
+        let err = options
+            .client()
+            .get(server.url("/slow"))
+            .send()
+            .unwrap_err();
+
+        assert!(err.is_timeout(), "{err}");
+    }
+
+    #[test]
+    fn client_sends_requests_through_the_configured_proxy() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .host("registry.invalid")
+                .path("/package.json");
+            then.status(200);
+        });
+
+        let options = HttpOptions {
+            proxy: Some(proxy(&server.base_url()).unwrap()),
+            ..HttpOptions::default()
+        };
+        let response = options
+            .client()
+            .get("http://registry.invalid/package.json")
+            .send()
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        mock.assert();
+    }
+}
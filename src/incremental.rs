@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::CacheError;
+use crate::discovery::Repository;
+
+const STATE_ENV: &str = "THANKS_STARS_STATE_DIR";
+const STATE_FILE: &str = "incremental.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawState {
+    #[serde(default)]
+    projects: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// On-disk record of the repositories each project has already been through,
+/// so `--incremental` runs only look at dependencies added since.
+#[derive(Debug)]
+pub struct IncrementalState {
+    path: PathBuf,
+    projects: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl IncrementalState {
+    /// Load the state from the default location, honoring `THANKS_STARS_STATE_DIR`.
+    pub fn load_default() -> Result<Self, CacheError> {
+        let dir = determine_state_dir()?;
+        Self::load(dir.join(STATE_FILE))
+    }
+
+    /// Load the state stored at `path`. A missing or unreadable file yields an
+    /// empty state, which makes the next run a full one.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, CacheError> {
+        let path = path.into();
+        let projects = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<RawState>(&contents)
+                .map(|raw| raw.projects)
+                .unwrap_or_default(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(source) => {
+                return Err(CacheError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        Ok(Self { path, projects })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keys (see [`repository_key`]) of the repositories already processed
+    /// for `project_root`.
+    pub fn seen(&self, project_root: &Path) -> BTreeSet<String> {
+        self.projects
+            .get(&project_key(project_root))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remember `repositories` as processed for `project_root`.
+    pub fn record<'r>(
+        &mut self,
+        project_root: &Path,
+        repositories: impl IntoIterator<Item = &'r Repository>,
+    ) {
+        self.projects
+            .entry(project_key(project_root))
+            .or_default()
+            .extend(repositories.into_iter().map(repository_key));
+    }
+
+    pub fn save(&self) -> Result<(), CacheError> {
+        let contents = serde_json::to_string_pretty(&RawState {
+            projects: self.projects.clone(),
+        })?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|source| CacheError::Io {
+                path: parent.display().to_string(),
+                source,
+            })?;
+        }
+        fs::write(&self.path, contents).map_err(|source| CacheError::Io {
+            path: self.path.display().to_string(),
+            source,
+        })
+    }
+}
+
+/// Case-insensitive `owner/name` key, matching how GitHub compares names.
+pub fn repository_key(repo: &Repository) -> String {
+    format!(
+        "{}/{}",
+        repo.owner.to_ascii_lowercase(),
+        repo.name.to_ascii_lowercase()
+    )
+}
+
+fn project_key(project_root: &Path) -> String {
+    fs::canonicalize(project_root)
+        .unwrap_or_else(|_| project_root.to_path_buf())
+        .display()
+        .to_string()
+}
+
+fn determine_state_dir() -> Result<PathBuf, CacheError> {
+    if let Ok(path) = env::var(STATE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let dirs = ProjectDirs::from("dev", "thanks-stars", "thanks-stars")
+        .ok_or(CacheError::MissingDirectory)?;
+    // Only Linux has a dedicated state directory.
+    Ok(dirs
+        .state_dir()
+        .unwrap_or_else(|| dirs.data_local_dir())
+        .to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::parse_github_repository;
+    use tempfile::tempdir;
+
+    #[test]
+    fn records_repositories_per_project() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(STATE_FILE);
+        let project = dir.path().join("project");
+        let other = dir.path().join("other");
+        fs::create_dir_all(&project).unwrap();
+
+        let mut state = IncrementalState::load(&path).unwrap();
+        assert!(state.seen(&project).is_empty());
+        let repo = parse_github_repository("https://github.com/Owner/Repo").unwrap();
+        state.record(&project, [&repo]);
+        state.save().unwrap();
+
+        let state = IncrementalState::load(&path).unwrap();
+        assert_eq!(
+            state.seen(&project).into_iter().collect::<Vec<_>>(),
+            ["owner/repo"]
+        );
+        assert!(state.seen(&other).is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::Repository;
+use crate::filter::RepositoryFilter;
+use crate::incremental::repository_key;
+use crate::RunEventHandler;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LedgerError {
+    #[error("failed to read ledger {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid entry on line {line} of ledger {path}: {source}")]
+    Json {
+        path: String,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to write ledger {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// One line of the ledger file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// `owner/name` as it was starred.
+    pub repository: String,
+    /// Seconds since the Unix epoch.
+    pub starred_at: u64,
+    /// The project whose dependencies led to the star.
+    pub project: String,
+}
+
+/// Every repository starred by any project sharing the same JSON Lines
+/// ledger file. As a [`RepositoryFilter`] it drops the repositories already
+/// recorded, so they are not starred again from another project.
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    starred: BTreeSet<String>,
+}
+
+impl Ledger {
+    /// Read the ledger at `path`; a missing file is an empty ledger.
+    pub fn load(path: &Path) -> Result<Self, LedgerError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(LedgerError::Read {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+
+        let mut starred = BTreeSet::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: LedgerEntry =
+                serde_json::from_str(line).map_err(|source| LedgerError::Json {
+                    path: path.display().to_string(),
+                    line: index + 1,
+                    source,
+                })?;
+            starred.insert(entry.repository.to_ascii_lowercase());
+        }
+        Ok(Self { starred })
+    }
+
+    pub fn contains(&self, repo: &Repository) -> bool {
+        self.starred.contains(&repository_key(repo))
+    }
+
+    pub fn len(&self) -> usize {
+        self.starred.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starred.is_empty()
+    }
+}
+
+impl RepositoryFilter for Ledger {
+    fn keep(&self, repo: &Repository) -> bool {
+        !self.contains(repo)
+    }
+
+    fn reason(&self) -> &str {
+        "already starred according to the ledger"
+    }
+}
+
+/// [`RunEventHandler`] that appends every newly starred repository to the
+/// ledger as soon as it is starred, so an interrupted run still records what
+/// it did. Dry runs write nothing.
+pub struct LedgerHandler {
+    path: PathBuf,
+    project: String,
+    dry_run: bool,
+    error: Option<LedgerError>,
+}
+
+impl LedgerHandler {
+    pub fn new(path: impl Into<PathBuf>, project: &Path, dry_run: bool) -> Self {
+        Self {
+            path: path.into(),
+            project: project.display().to_string(),
+            dry_run,
+            error: None,
+        }
+    }
+
+    /// Return the first error raised while appending to the ledger, if any.
+    pub fn take_error(&mut self) -> Option<LedgerError> {
+        self.error.take()
+    }
+
+    fn append(&self, repo: &Repository) -> Result<(), LedgerError> {
+        let entry = LedgerEntry {
+            repository: format!("{}/{}", repo.owner, repo.name),
+            starred_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            project: self.project.clone(),
+        };
+        let line = serde_json::to_string(&entry).expect("ledger entries serialize");
+
+        let write_error = |source| LedgerError::Write {
+            path: self.path.display().to_string(),
+            source,
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(write_error)?;
+        writeln!(file, "{line}").map_err(write_error)
+    }
+}
+
+impl RunEventHandler for LedgerHandler {
+    fn on_starred(
+        &mut self,
+        repo: &Repository,
+        already_starred: bool,
+        _index: usize,
+        _total: usize,
+    ) {
+        if already_starred || self.dry_run || self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.append(repo) {
+            self.error = Some(err);
+        }
+    }
+}
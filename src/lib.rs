@@ -1,23 +1,44 @@
+pub mod cache;
 pub mod config;
+#[cfg(feature = "dependabot")]
+pub mod dependabot;
 pub mod discovery;
 pub mod ecosystems;
+pub mod filter;
 pub mod github;
+pub mod handlers;
 pub mod http;
+pub mod incremental;
+pub mod ledger;
+pub mod project_config;
+pub mod report;
+pub mod token;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 
-use discovery::{DiscoveryError, Framework, Repository};
-use github::GitHubApi;
+use discovery::{
+    DependencyScope, DiscovererRegistry, DiscoveryError, DiscoveryOptions, DiscoveryStats,
+    Framework, Repository,
+};
+use filter::RepositoryFilter;
+use github::{ApiUsage, GitHubApi, GitHubError};
+use handlers::SilentHandler;
+use http::HttpOptions;
 
 #[derive(Debug, thiserror::Error)]
 pub enum RunError {
     #[error(transparent)]
     Discovery(Box<DiscoveryError>),
     #[error(transparent)]
-    GitHub(#[from] github::GitHubError),
+    GitHub(#[from] GitHubError),
     #[error("no supported package managers found in project root {0}")]
     NoFrameworks(String),
+    #[error("starring was cancelled")]
+    Cancelled,
+    #[cfg(feature = "dependabot")]
+    #[error(transparent)]
+    Dependabot(#[from] dependabot::DependabotError),
 }
 
 impl From<DiscoveryError> for RunError {
@@ -32,12 +53,63 @@ pub struct StarredRepository {
     pub already_starred: bool,
 }
 
+/// A repository GitHub refused to check or star during a run with
+/// [`RunOptions::continue_on_error`].
+#[derive(Debug, Clone)]
+pub struct FailedRepository {
+    pub repository: Repository,
+    /// The error GitHub answered with.
+    pub error: String,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct RunSummary {
     pub starred: Vec<StarredRepository>,
+    /// Dependencies the discoverers looked at.
+    pub examined: usize,
+    /// Examined dependencies that did not resolve to a GitHub repository.
+    pub unresolved: usize,
+    /// Names of the [`unresolved`](Self::unresolved) dependencies.
+    pub unresolved_dependencies: Vec<String>,
+    /// Problems discovery worked around, such as a missing `cargo` binary.
+    pub warnings: Vec<String>,
+    /// Whether [`RunOptions::limit`] stopped the run before every repository
+    /// was handled.
+    pub limit_reached: bool,
+    /// Repositories dropped by [`RunOptions::filters`].
+    pub filtered_out: usize,
+    /// The [`filtered_out`](Self::filtered_out) repositories counted per
+    /// [`RepositoryFilter::reason`].
+    pub filtered_out_by: BTreeMap<String, usize>,
+    /// Archived repositories left alone because of [`RunOptions::skip_archived`].
+    pub archived: Vec<Repository>,
+    /// Repositories skipped because they are in [`RunOptions::seen`].
+    pub previously_seen: usize,
+    /// The project's own repositories, skipped unless
+    /// [`RunOptions::include_self`] is set.
+    pub skipped_own: Vec<Repository>,
+    /// GitHub requests made during the run, if the client keeps count.
+    pub api_usage: Option<ApiUsage>,
+    /// Whether the run starred without checking, per
+    /// [`RunOptions::assume_unstarred`], so no repository is reported as
+    /// already starred.
+    pub star_check_skipped: bool,
+    /// Repositories GitHub refused to check or star; only filled when
+    /// [`RunOptions::continue_on_error`] kept the run going past them.
+    pub failed: Vec<FailedRepository>,
 }
 
 pub trait RunEventHandler {
+    /// Called with the repositories about to be starred, before anything is
+    /// sent to GitHub. Returning `false` cancels the run with
+    /// [`RunError::Cancelled`].
+    fn confirm(&mut self, _repositories: &[Repository]) -> bool {
+        true
+    }
+    /// Called before GitHub is asked which of the `total` repositories are
+    /// already starred, so handlers can show that phase apart from starring.
+    /// Not called when [`RunOptions::assume_unstarred`] skips the check.
+    fn on_prechecking(&mut self, _total: usize) {}
     fn on_start(&mut self, _total: usize) {}
     fn on_starred(
         &mut self,
@@ -47,13 +119,113 @@ pub trait RunEventHandler {
         _total: usize,
     ) {
     }
+    /// Called instead of [`on_starred`](Self::on_starred) for a repository
+    /// skipped because it is archived.
+    fn on_archived(&mut self, _repo: &Repository, _index: usize, _total: usize) {}
+    /// Called instead of [`on_starred`](Self::on_starred) for a repository
+    /// GitHub refused to check or star, when
+    /// [`RunOptions::continue_on_error`] is set.
+    fn on_failed(
+        &mut self,
+        _repo: &Repository,
+        _error: &GitHubError,
+        _index: usize,
+        _total: usize,
+    ) {
+    }
     fn on_complete(&mut self, _summary: &RunSummary) {}
 }
 
-#[derive(Default)]
-struct NoopHandler;
+impl<A: RunEventHandler, B: RunEventHandler> RunEventHandler for (A, B) {
+    fn confirm(&mut self, repositories: &[Repository]) -> bool {
+        self.0.confirm(repositories) && self.1.confirm(repositories)
+    }
+
+    fn on_prechecking(&mut self, total: usize) {
+        self.0.on_prechecking(total);
+        self.1.on_prechecking(total);
+    }
+
+    fn on_start(&mut self, total: usize) {
+        self.0.on_start(total);
+        self.1.on_start(total);
+    }
+
+    fn on_starred(&mut self, repo: &Repository, already_starred: bool, index: usize, total: usize) {
+        self.0.on_starred(repo, already_starred, index, total);
+        self.1.on_starred(repo, already_starred, index, total);
+    }
+
+    fn on_archived(&mut self, repo: &Repository, index: usize, total: usize) {
+        self.0.on_archived(repo, index, total);
+        self.1.on_archived(repo, index, total);
+    }
+
+    fn on_failed(&mut self, repo: &Repository, error: &GitHubError, index: usize, total: usize) {
+        self.0.on_failed(repo, error, index, total);
+        self.1.on_failed(repo, error, index, total);
+    }
+
+    fn on_complete(&mut self, summary: &RunSummary) {
+        self.0.on_complete(summary);
+        self.1.on_complete(summary);
+    }
+}
+
+impl<H: RunEventHandler> RunEventHandler for Option<H> {
+    fn confirm(&mut self, repositories: &[Repository]) -> bool {
+        match self {
+            Some(handler) => handler.confirm(repositories),
+            None => true,
+        }
+    }
+
+    fn on_prechecking(&mut self, total: usize) {
+        if let Some(handler) = self {
+            handler.on_prechecking(total);
+        }
+    }
+
+    fn on_start(&mut self, total: usize) {
+        if let Some(handler) = self {
+            handler.on_start(total);
+        }
+    }
+
+    fn on_starred(&mut self, repo: &Repository, already_starred: bool, index: usize, total: usize) {
+        if let Some(handler) = self {
+            handler.on_starred(repo, already_starred, index, total);
+        }
+    }
+
+    fn on_archived(&mut self, repo: &Repository, index: usize, total: usize) {
+        if let Some(handler) = self {
+            handler.on_archived(repo, index, total);
+        }
+    }
+
+    fn on_failed(&mut self, repo: &Repository, error: &GitHubError, index: usize, total: usize) {
+        if let Some(handler) = self {
+            handler.on_failed(repo, error, index, total);
+        }
+    }
 
-impl RunEventHandler for NoopHandler {}
+    fn on_complete(&mut self, summary: &RunSummary) {
+        if let Some(handler) = self {
+            handler.on_complete(summary);
+        }
+    }
+}
+
+/// Discover the GitHub repositories of the dependencies in `project_root`
+/// without talking to GitHub. Repositories found through several manifests are
+/// only returned once; a project with no supported manifests yields an empty
+/// list.
+pub fn discover(project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+    let frameworks = discovery::detect_frameworks(project_root);
+    let repos = discovery::discover_for_frameworks(project_root, &frameworks)?;
+    Ok(deduplicate(repos))
+}
 
 pub fn run(project_root: &Path, api: &dyn GitHubApi) -> Result<RunSummary, RunError> {
     let frameworks = discovery::detect_frameworks(project_root);
@@ -61,7 +233,7 @@ pub fn run(project_root: &Path, api: &dyn GitHubApi) -> Result<RunSummary, RunEr
         return Err(RunError::NoFrameworks(project_root.display().to_string()));
     }
 
-    run_with_frameworks_and_handler(project_root, &frameworks, api, &mut NoopHandler)
+    run_with_frameworks_and_handler(project_root, &frameworks, api, &mut SilentHandler)
 }
 
 pub fn run_with_frameworks(
@@ -72,7 +244,7 @@ pub fn run_with_frameworks(
     if frameworks.is_empty() {
         return Err(RunError::NoFrameworks(project_root.display().to_string()));
     }
-    run_with_frameworks_and_handler(project_root, frameworks, api, &mut NoopHandler)
+    run_with_frameworks_and_handler(project_root, frameworks, api, &mut SilentHandler)
 }
 
 pub fn run_with_handler(
@@ -88,30 +260,328 @@ pub fn run_with_handler(
     run_with_frameworks_and_handler(project_root, &frameworks, api, handler)
 }
 
+/// Order in which discovered repositories are starred and reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Keep the order the discoverers returned them in.
+    #[default]
+    Discovery,
+    /// Sort by owner, then name, ignoring ASCII case.
+    Name,
+}
+
+/// Knobs for [`run_with_options`].
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    /// Discoverers to run instead of the built-in [`DiscovererRegistry`].
+    pub registry: Option<&'a DiscovererRegistry>,
+    /// Order of the repositories in the [`RunSummary`] and handler events.
+    pub sort_order: SortOrder,
+    /// Stop after starring this many repositories. Already-starred
+    /// repositories do not count, since they cost no write.
+    pub limit: Option<usize>,
+    /// Filters applied, in order, to the deduplicated repositories before
+    /// anything is starred.
+    pub filters: Vec<Box<dyn RepositoryFilter + 'a>>,
+    /// Do not star archived repositories. The archived flag comes back with
+    /// the star check, so this costs no extra requests.
+    pub skip_archived: bool,
+    /// Repositories processed by an earlier run, as
+    /// [`incremental::repository_key`]s. They are skipped without asking
+    /// GitHub anything.
+    pub seen: Option<&'a BTreeSet<String>>,
+    /// Star every repository without first asking whether it already is.
+    /// Starring is idempotent, so this halves the requests, but every
+    /// repository is then reported as newly starred. Has no effect together
+    /// with [`skip_archived`](Self::skip_archived) or
+    /// [`fetch_stargazers`](Self::fetch_stargazers), whose status query
+    /// already answers the star check.
+    pub assume_unstarred: bool,
+    /// Only run the discoverers of these frameworks, in this order rather
+    /// than that of [`Framework::ALL`]. Applies to the built-in discoverers,
+    /// not to a custom [`registry`](Self::registry).
+    pub frameworks: Option<&'a [Framework]>,
+    /// Frameworks that win over the ones they
+    /// [overlap](Framework::overlaps) with when both are detected. By default
+    /// both run and the repositories they share are merged.
+    pub prefer: &'a [Framework],
+    /// Also ask for each repository's stargazer count and record it in
+    /// [`Repository::stargazer_count`]. It comes back with the star check,
+    /// so this costs no extra requests unless
+    /// [`assume_unstarred`](Self::assume_unstarred) skipped that check.
+    pub fetch_stargazers: bool,
+    /// Star the project's own repository too when a dependency resolves to
+    /// it. By default the repository named in the root manifest (see
+    /// [`discovery::own_repositories`]) is skipped and reported in
+    /// [`RunSummary::skipped_own`]. [`run`], [`run_with_frameworks`],
+    /// [`run_with_handler`] and [`run_with_frameworks_and_handler`] skip it
+    /// too; earlier releases starred it.
+    pub include_self: bool,
+    /// Whether the built-in discoverers read lockfiles or only the declared
+    /// dependencies. Not applied to a custom [`registry`](Self::registry).
+    pub dependency_scope: DependencyScope,
+    /// The `cargo` binary the built-in Cargo discoverer runs, over
+    /// `THANKS_STARS_CARGO_BIN` and the one on `PATH`. Not applied to a
+    /// custom [`registry`](Self::registry).
+    pub cargo_bin: Option<&'a Path>,
+    /// How the built-in discoverers reach package registries. Not applied to
+    /// a custom [`registry`](Self::registry).
+    pub http: HttpOptions,
+    /// Move on to the next repository when GitHub refuses to check or star
+    /// one, recording it in [`RunSummary::failed`], instead of failing the
+    /// run. Authentication and rate-limit errors still stop the run, since
+    /// every later repository would fail the same way.
+    pub continue_on_error: bool,
+}
+
+impl RunOptions<'_> {
+    /// The options the built-in discoverers run with.
+    fn discovery_options(&self) -> DiscoveryOptions {
+        DiscoveryOptions {
+            scope: self.dependency_scope,
+            cargo_bin: self.cargo_bin.map(Path::to_path_buf),
+            http: self.http.clone(),
+            ..DiscoveryOptions::default()
+        }
+    }
+}
+
+/// Discover dependencies with the registry in `options` (or the built-in
+/// ecosystems) and star them.
+pub fn run_with_options(
+    project_root: &Path,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    let default_registry;
+    let registry = match options.registry {
+        Some(registry) => registry,
+        None => {
+            let mut detected: Vec<Framework> = options
+                .frameworks
+                .unwrap_or(Framework::ALL)
+                .iter()
+                .copied()
+                .filter(|framework| framework.is_present(project_root))
+                .collect();
+            discovery::apply_preference(&mut detected, options.prefer);
+            default_registry = DiscovererRegistry::for_frameworks_with_options(
+                &detected,
+                &options.discovery_options(),
+            );
+            &default_registry
+        }
+    };
+    if !registry.detects(project_root) {
+        return Err(RunError::NoFrameworks(project_root.display().to_string()));
+    }
+
+    let stats = DiscoveryStats::default();
+    let repos = registry.discover_with_stats(project_root, &stats)?;
+    let own = own_repositories(project_root, options);
+    star_repositories(repos, &own, &stats, api, handler, options)
+}
+
+/// Like [`run_with_handler`], but also discovers manifests in subdirectories of
+/// `project_root` up to `max_depth` levels deep.
+pub fn run_recursive_with_handler(
+    project_root: &Path,
+    max_depth: usize,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+) -> Result<RunSummary, RunError> {
+    run_recursive_with_options(
+        project_root,
+        max_depth,
+        api,
+        handler,
+        &RunOptions::default(),
+    )
+}
+
+/// Like [`run_recursive_with_handler`], honoring the sort order and limit in
+/// `options`. Directories are searched for the built-in ecosystems;
+/// `options.registry` is not consulted.
+pub fn run_recursive_with_options(
+    project_root: &Path,
+    max_depth: usize,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    let mut directories = discovery::detect_frameworks_recursive(project_root, max_depth);
+    select_frameworks(&mut directories, options);
+    run_directories(project_root, &directories, api, handler, options)
+}
+
+/// Like [`run_recursive_with_options`], but only searches the directories and
+/// ecosystems listed in the project's `.github/dependabot.yml`.
+#[cfg(feature = "dependabot")]
+pub fn run_dependabot_with_options(
+    project_root: &Path,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    let mut directories = dependabot::directories(project_root)?;
+    select_frameworks(&mut directories, options);
+    run_directories(project_root, &directories, api, handler, options)
+}
+
+/// Apply [`RunOptions::frameworks`] and [`RunOptions::prefer`] to every
+/// directory, dropping directories left without a framework.
+fn select_frameworks(directories: &mut Vec<discovery::ProjectDirectory>, options: &RunOptions<'_>) {
+    for directory in directories.iter_mut() {
+        if let Some(frameworks) = options.frameworks {
+            directory.frameworks = frameworks
+                .iter()
+                .copied()
+                .filter(|framework| directory.frameworks.contains(framework))
+                .collect();
+        }
+        discovery::apply_preference(&mut directory.frameworks, options.prefer);
+    }
+    directories.retain(|directory| !directory.frameworks.is_empty());
+}
+
+fn run_directories(
+    project_root: &Path,
+    directories: &[discovery::ProjectDirectory],
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    if directories.is_empty() {
+        return Err(RunError::NoFrameworks(project_root.display().to_string()));
+    }
+
+    let stats = DiscoveryStats::default();
+    let repos = discovery::discover_recursive_with_options(
+        project_root,
+        directories,
+        &options.discovery_options(),
+        &stats,
+    )?;
+    let own = own_repositories(project_root, options);
+    star_repositories(repos, &own, &stats, api, handler, options)
+}
+
 pub fn run_with_frameworks_and_handler(
     project_root: &Path,
     frameworks: &[Framework],
     api: &dyn GitHubApi,
     handler: &mut impl RunEventHandler,
 ) -> Result<RunSummary, RunError> {
-    let repos = discovery::discover_for_frameworks(project_root, frameworks)?;
+    let stats = DiscoveryStats::default();
+    let repos = discovery::discover_for_frameworks_with_stats(project_root, frameworks, &stats)?;
+    let own = discovery::own_repositories(project_root);
+    star_repositories(repos, &own, &stats, api, handler, &RunOptions::default())
+}
 
-    let mut unique = Vec::new();
-    let mut seen = HashSet::new();
-    for repo in repos {
-        if seen.insert((repo.owner.clone(), repo.name.clone())) {
-            unique.push(repo);
+/// The repositories to leave out as the project's own, per
+/// [`RunOptions::include_self`].
+fn own_repositories(project_root: &Path, options: &RunOptions<'_>) -> Vec<Repository> {
+    if options.include_self {
+        Vec::new()
+    } else {
+        discovery::own_repositories(project_root)
+    }
+}
+
+/// Star `repos`, leaving out any that are the same repository as one in `own`.
+fn star_repositories(
+    repos: Vec<Repository>,
+    own: &[Repository],
+    stats: &DiscoveryStats,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    let mut unique = deduplicate(repos);
+    let mut skipped_own = Vec::new();
+    if !own.is_empty() {
+        let (mine, others) = unique
+            .into_iter()
+            .partition(|repo| own.iter().any(|own| same_repository(own, repo)));
+        skipped_own = mine;
+        unique = others;
+    }
+    let mut filtered_out_by: BTreeMap<String, usize> = BTreeMap::new();
+    if !options.filters.is_empty() {
+        let mut kept = Vec::new();
+        'repositories: for mut repo in unique {
+            for filter in &options.filters {
+                match filter.map(repo) {
+                    Some(mapped) => repo = mapped,
+                    None => {
+                        *filtered_out_by
+                            .entry(filter.reason().to_string())
+                            .or_default() += 1;
+                        continue 'repositories;
+                    }
+                }
+            }
+            kept.push(repo);
         }
+        // A filter may map two repositories onto the same upstream.
+        unique = deduplicate(kept);
+    }
+    let filtered_out = filtered_out_by.values().sum();
+    let mut previously_seen = 0;
+    if let Some(seen) = options.seen {
+        let before = unique.len();
+        unique.retain(|repo| !seen.contains(&incremental::repository_key(repo)));
+        previously_seen = before - unique.len();
+    }
+    if options.sort_order == SortOrder::Name {
+        unique.sort_by_cached_key(|repo| {
+            (
+                repo.owner.to_ascii_lowercase(),
+                repo.name.to_ascii_lowercase(),
+            )
+        });
     }
 
+    if !handler.confirm(&unique) {
+        return Err(RunError::Cancelled);
+    }
+    let query_status = options.skip_archived || options.fetch_stargazers;
+    if query_status || !options.assume_unstarred {
+        handler.on_prechecking(unique.len());
+    }
     handler.on_start(unique.len());
 
     let total = unique.len();
     let mut starred = Vec::new();
-    for (index, repo) in unique.into_iter().enumerate() {
-        let already_starred = api.viewer_has_starred(&repo.owner, &repo.name)?;
+    let mut newly_starred = 0;
+    let mut limit_reached = false;
+    let mut archived = Vec::new();
+    let mut failed = Vec::new();
+    for (index, mut repo) in unique.into_iter().enumerate() {
+        if options.limit.is_some_and(|limit| newly_starred >= limit) {
+            limit_reached = true;
+            break;
+        }
+        let already_starred = match star_repository(&mut repo, api, options, query_status) {
+            Ok(Some(already_starred)) => already_starred,
+            Ok(None) => {
+                handler.on_archived(&repo, index + 1, total);
+                archived.push(repo);
+                continue;
+            }
+            Err(err) if options.continue_on_error && concerns_one_repository(&err) => {
+                handler.on_failed(&repo, &err, index + 1, total);
+                failed.push(FailedRepository {
+                    repository: repo,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
         if !already_starred {
-            api.star(&repo.owner, &repo.name)?;
+            newly_starred += 1;
         }
         handler.on_starred(&repo, already_starred, index + 1, total);
         starred.push(StarredRepository {
@@ -120,17 +590,106 @@ pub fn run_with_frameworks_and_handler(
         });
     }
 
-    let summary = RunSummary { starred };
+    let summary = RunSummary {
+        starred,
+        examined: stats.examined(),
+        unresolved: stats.unresolved(),
+        unresolved_dependencies: stats.unresolved_dependencies(),
+        warnings: stats.warnings(),
+        limit_reached,
+        filtered_out,
+        filtered_out_by,
+        archived,
+        previously_seen,
+        skipped_own,
+        api_usage: api.usage(),
+        star_check_skipped: options.assume_unstarred && !query_status,
+        failed,
+    };
     handler.on_complete(&summary);
 
     Ok(summary)
 }
 
+/// Star `repo` unless it already is, returning whether it already was, or
+/// `None` for an archived repository left alone per
+/// [`RunOptions::skip_archived`].
+fn star_repository(
+    repo: &mut Repository,
+    api: &dyn GitHubApi,
+    options: &RunOptions<'_>,
+    query_status: bool,
+) -> Result<Option<bool>, GitHubError> {
+    let already_starred = if query_status {
+        let status = api.repository_status(&repo.owner, &repo.name)?;
+        if options.skip_archived && status.is_archived {
+            return Ok(None);
+        }
+        if options.fetch_stargazers {
+            repo.stargazer_count = status.stargazer_count;
+        }
+        status.viewer_has_starred
+    } else if options.assume_unstarred {
+        false
+    } else {
+        api.viewer_has_starred(&repo.owner, &repo.name)?
+    };
+    if !already_starred {
+        api.star(&repo.owner, &repo.name)?;
+    }
+    Ok(Some(already_starred))
+}
+
+/// Whether `err` is about one repository rather than the token or the rate
+/// limit, so [`RunOptions::continue_on_error`] may move past it.
+fn concerns_one_repository(err: &GitHubError) -> bool {
+    match err {
+        GitHubError::Api { status, .. } => *status != 401,
+        GitHubError::Timeout(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether `a` and `b` name the same repository, ignoring case like GitHub.
+fn same_repository(a: &Repository, b: &Repository) -> bool {
+    a.owner.eq_ignore_ascii_case(&b.owner) && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+/// Drop repeated `owner/name` pairs, keeping the first occurrence and adding
+/// the sources of later ones to its [`Repository::also_via`].
+///
+/// GitHub treats owners and names case-insensitively, so `Owner/Repo` and
+/// `owner/repo` are the same repository; the first spelling seen is kept.
+fn deduplicate(repos: Vec<Repository>) -> Vec<Repository> {
+    let mut unique: Vec<Repository> = Vec::new();
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    for repo in repos {
+        let key = (
+            repo.owner.to_ascii_lowercase(),
+            repo.name.to_ascii_lowercase(),
+        );
+        match seen.get(&key) {
+            Some(&index) => {
+                let kept = &mut unique[index];
+                for via in repo.sources() {
+                    kept.add_via(via);
+                }
+            }
+            None => {
+                seen.insert(key, unique.len());
+                unique.push(repo);
+            }
+        }
+    }
+    unique
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::discovery::Framework;
     use crate::github::GitHubError;
+    use crate::handlers::{CollectingHandler, RunEvent};
     use serde_json::json;
     use std::cell::RefCell;
     use std::fs;
@@ -170,6 +729,427 @@ mod tests {
         }
     }
 
+    fn repository(owner: &str, name: &str) -> Repository {
+        Repository {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            url: format!("https://github.com/{owner}/{name}"),
+            via: None,
+            also_via: Vec::new(),
+            original_url: None,
+            directory: None,
+            stargazer_count: None,
+            framework: None,
+        }
+    }
+
+    #[test]
+    fn deduplication_keeps_every_source() {
+        let found_via = |via: &str| {
+            let mut repo = repository("psf", "requests");
+            repo.via = Some(via.to_string());
+            repo
+        };
+        let repos = vec![
+            found_via("pyproject.toml"),
+            found_via("requirements.txt"),
+            found_via("pyproject.toml"),
+        ];
+
+        let unique = deduplicate(repos);
+
+        assert_eq!(unique.len(), 1);
+        assert_eq!(
+            unique[0].via_label().as_deref(),
+            Some("pyproject.toml, requirements.txt")
+        );
+    }
+
+    #[test]
+    fn deduplication_keeps_the_first_framework() {
+        let found_by = |framework| {
+            let mut repo = repository("protocolbuffers", "protobuf");
+            repo.framework = Some(framework);
+            repo
+        };
+
+        let unique = deduplicate(vec![
+            found_by(Framework::Python),
+            found_by(Framework::Cargo),
+        ]);
+
+        assert_eq!(unique.len(), 1);
+        assert_eq!(unique[0].framework, Some(Framework::Python));
+    }
+
+    #[test]
+    fn preferred_build_tool_wins_in_hybrid_builds() {
+        use httpmock::prelude::*;
+
+        let dir = tempdir().unwrap();
+        let server = MockServer::start();
+        fs::write(
+            dir.path().join("pom.xml"),
+            "<project><dependencies><dependency>\
+             <groupId>com.example</groupId><artifactId>library</artifactId>\
+             <version>1.2.3</version></dependency></dependencies></project>",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("build.gradle"),
+            "dependencies { implementation 'com.example:library:1.2.3' }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("settings.xml"),
+            format!(
+                "<settings><mirrors><mirror><mirrorOf>*</mirrorOf><url>{}</url></mirror></mirrors></settings>",
+                server.base_url()
+            ),
+        )
+        .unwrap();
+        let pom = server.mock(|when, then| {
+            when.method(GET)
+                .path("/com/example/library/1.2.3/library-1.2.3.pom");
+            then.status(200)
+                .body("<project><url>https://github.com/example/library</url></project>");
+        });
+
+        let api = MockGitHub::new();
+        let options = RunOptions {
+            prefer: &[Framework::Maven],
+            ..RunOptions::default()
+        };
+        let summary = run_with_options(dir.path(), &api, &mut SilentHandler, &options).unwrap();
+
+        pom.assert();
+        assert_eq!(summary.examined, 1);
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(api.calls.borrow().len(), 1);
+        assert_eq!(
+            summary.starred[0].repository.via.as_deref(),
+            Some("pom.xml")
+        );
+    }
+
+    #[test]
+    fn collecting_handler_records_events_in_order() {
+        let api = MockGitHub::new();
+        api.starred
+            .borrow_mut()
+            .push(("owner".to_string(), "known".to_string()));
+        let mut handler = CollectingHandler::new();
+
+        star_repositories(
+            vec![repository("owner", "known"), repository("owner", "new")],
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut handler,
+            &RunOptions::default(),
+        )
+        .unwrap();
+
+        let events = handler.into_events();
+        assert_eq!(events.len(), 6);
+        assert!(
+            matches!(&events[0], RunEvent::Confirm { repositories } if repositories.len() == 2)
+        );
+        assert!(matches!(events[1], RunEvent::Prechecking { total: 2 }));
+        assert!(matches!(events[2], RunEvent::Start { total: 2 }));
+        assert!(matches!(
+            &events[3],
+            RunEvent::Starred { repository, already_starred: true, index: 1, total: 2 }
+                if repository.name == "known"
+        ));
+        assert!(matches!(
+            &events[4],
+            RunEvent::Starred { repository, already_starred: false, index: 2, total: 2 }
+                if repository.name == "new"
+        ));
+        assert!(matches!(&events[5], RunEvent::Complete(summary) if summary.starred.len() == 2));
+    }
+
+    #[test]
+    fn continue_on_error_records_refused_repositories_and_moves_on() {
+        struct Refusing(MockGitHub);
+
+        impl GitHubApi for Refusing {
+            fn viewer_has_starred(&self, owner: &str, repo: &str) -> Result<bool, GitHubError> {
+                self.0.viewer_has_starred(owner, repo)
+            }
+
+            fn star(&self, owner: &str, repo: &str) -> Result<(), GitHubError> {
+                match repo {
+                    "blocked" => Err(GitHubError::Api {
+                        status: 451,
+                        body: "Unavailable For Legal Reasons".to_string(),
+                    }),
+                    "unauthorized" => Err(GitHubError::Api {
+                        status: 401,
+                        body: "Bad credentials".to_string(),
+                    }),
+                    _ => self.0.star(owner, repo),
+                }
+            }
+        }
+
+        let api = Refusing(MockGitHub::new());
+        let mut handler = CollectingHandler::new();
+        let options = RunOptions {
+            continue_on_error: true,
+            ..RunOptions::default()
+        };
+        let summary = star_repositories(
+            vec![repository("owner", "blocked"), repository("owner", "fine")],
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut handler,
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(summary.starred[0].repository.name, "fine");
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].repository.name, "blocked");
+        assert!(handler.events().iter().any(|event| matches!(
+            event,
+            RunEvent::Failed { repository, index: 1, total: 2, .. } if repository.name == "blocked"
+        )));
+
+        let err = star_repositories(
+            vec![
+                repository("owner", "unauthorized"),
+                repository("owner", "other"),
+            ],
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut SilentHandler,
+            &options,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            RunError::GitHub(GitHubError::Api { status: 401, .. })
+        ));
+        assert_eq!(api.0.calls.borrow().len(), 1);
+    }
+
+    #[test]
+    fn prechecking_is_skipped_when_stars_are_not_checked() {
+        let mut handler = CollectingHandler::new();
+        let options = RunOptions {
+            assume_unstarred: true,
+            ..RunOptions::default()
+        };
+
+        star_repositories(
+            vec![repository("owner", "repo")],
+            &[],
+            &DiscoveryStats::default(),
+            &MockGitHub::new(),
+            &mut handler,
+            &options,
+        )
+        .unwrap();
+
+        assert!(!handler
+            .events()
+            .iter()
+            .any(|event| matches!(event, RunEvent::Prechecking { .. })));
+    }
+
+    #[test]
+    fn collecting_handler_can_decline_the_run() {
+        let api = MockGitHub::new();
+        let mut handler = CollectingHandler::new().with_confirmation(false);
+
+        let result = star_repositories(
+            vec![repository("owner", "repo")],
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut handler,
+            &RunOptions::default(),
+        );
+
+        assert!(matches!(result, Err(RunError::Cancelled)));
+        assert!(api.calls.borrow().is_empty());
+        assert_eq!(handler.events().len(), 1);
+    }
+
+    #[test]
+    fn deduplicates_repositories_differing_only_in_case() {
+        let api = MockGitHub::new();
+        let repos = vec![
+            repository("Owner", "Repo"),
+            repository("owner", "repo"),
+            repository("OWNER", "REPO"),
+        ];
+
+        let summary = star_repositories(
+            repos,
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut SilentHandler,
+            &RunOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(api.calls.borrow().len(), 1);
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(
+            summary.starred[0].repository.url,
+            "https://github.com/Owner/Repo"
+        );
+    }
+
+    #[test]
+    fn limit_skips_already_starred_repositories() {
+        let api = MockGitHub::new();
+        api.starred
+            .borrow_mut()
+            .push(("owner".to_string(), "known".to_string()));
+        let repos = vec![
+            repository("owner", "known"),
+            repository("owner", "first"),
+            repository("owner", "second"),
+        ];
+        let options = RunOptions {
+            limit: Some(1),
+            ..RunOptions::default()
+        };
+
+        let summary = star_repositories(
+            repos,
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut SilentHandler,
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            *api.calls.borrow(),
+            [("owner".to_string(), "first".to_string())]
+        );
+        assert_eq!(summary.starred.len(), 2);
+        assert!(summary.limit_reached);
+    }
+
+    #[test]
+    fn owner_allowlist_filters_repositories() {
+        let api = MockGitHub::new();
+        let repos = vec![
+            repository("rust-lang", "cargo"),
+            repository("serde-rs", "serde"),
+            repository("Rust-Lang", "rust"),
+        ];
+        let options = RunOptions {
+            filters: vec![Box::new(filter::OwnerAllowlist::new(["rust-lang"]))],
+            ..RunOptions::default()
+        };
+
+        let summary = star_repositories(
+            repos,
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut SilentHandler,
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(summary.starred.len(), 2);
+        assert_eq!(summary.filtered_out, 1);
+        assert_eq!(
+            summary.filtered_out_by,
+            BTreeMap::from([("outside the owner allowlist".to_string(), 1)])
+        );
+        assert!(api
+            .calls
+            .borrow()
+            .iter()
+            .all(|(owner, _)| owner != "serde-rs"));
+    }
+
+    #[test]
+    fn custom_filters_drop_and_rewrite_repositories() {
+        struct UpstreamForks;
+
+        impl filter::RepositoryFilter for UpstreamForks {
+            fn map(&self, repo: Repository) -> Option<Repository> {
+                match (repo.owner.as_str(), repo.name.as_str()) {
+                    ("owner", "abandoned") => None,
+                    ("fork", name) => Some(repository("upstream", name)),
+                    _ => Some(repo),
+                }
+            }
+        }
+
+        let api = MockGitHub::new();
+        let repos = vec![
+            repository("owner", "kept"),
+            repository("owner", "abandoned"),
+            repository("fork", "lib"),
+            repository("upstream", "lib"),
+        ];
+        let options = RunOptions {
+            filters: vec![Box::new(UpstreamForks)],
+            ..RunOptions::default()
+        };
+
+        let summary = star_repositories(
+            repos,
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut SilentHandler,
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            *api.calls.borrow(),
+            [
+                ("owner".to_string(), "kept".to_string()),
+                ("upstream".to_string(), "lib".to_string()),
+            ]
+        );
+        assert_eq!(summary.filtered_out, 1);
+    }
+
+    #[test]
+    fn declined_confirmation_cancels_before_starring() {
+        struct Decline;
+
+        impl RunEventHandler for Decline {
+            fn confirm(&mut self, repositories: &[Repository]) -> bool {
+                assert_eq!(repositories.len(), 1);
+                false
+            }
+        }
+
+        let api = MockGitHub::new();
+        let err = star_repositories(
+            vec![repository("owner", "repo")],
+            &[],
+            &DiscoveryStats::default(),
+            &api,
+            &mut Decline,
+            &RunOptions::default(),
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, RunError::Cancelled));
+        assert!(api.calls.borrow().is_empty());
+    }
+
     #[test]
     fn stars_unique_repositories_once() {
         let dir = tempdir().unwrap();
@@ -205,4 +1185,206 @@ mod tests {
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0], ("example".to_string(), "repo".to_string()));
     }
+
+    #[test]
+    fn summary_counts_examined_and_unresolved_dependencies() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            json!({
+                "dependencies": {
+                    "resolvable": "^1.0.0",
+                    "unresolvable": "^1.0.0"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let resolvable = dir.path().join("node_modules/resolvable");
+        let unresolvable = dir.path().join("node_modules/unresolvable");
+        fs::create_dir_all(&resolvable).unwrap();
+        fs::create_dir_all(&unresolvable).unwrap();
+        fs::write(
+            resolvable.join("package.json"),
+            json!({ "repository": "https://github.com/example/repo" }).to_string(),
+        )
+        .unwrap();
+        fs::write(
+            unresolvable.join("package.json"),
+            json!({ "repository": "https://gitlab.com/example/repo" }).to_string(),
+        )
+        .unwrap();
+
+        let mock = MockGitHub::new();
+        let summary = run_with_frameworks(dir.path(), &[Framework::Node], &mock).unwrap();
+
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(summary.examined, 2);
+        assert_eq!(summary.unresolved, 1);
+    }
+
+    #[test]
+    fn discover_returns_unique_repositories_without_github() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            json!({
+                "dependencies": {
+                    "dep-one": "^1.0.0",
+                    "dep-two": "^1.0.0"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let package_json = json!({ "repository": "https://github.com/example/repo" }).to_string();
+        for dep in ["dep-one", "dep-two"] {
+            let dep_dir = dir.path().join("node_modules").join(dep);
+            fs::create_dir_all(&dep_dir).unwrap();
+            fs::write(dep_dir.join("package.json"), &package_json).unwrap();
+        }
+
+        let repos = discover(dir.path()).unwrap();
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].owner, "example");
+        assert_eq!(repos[0].name, "repo");
+        assert_eq!(repos[0].via.as_deref(), Some("package.json"));
+    }
+
+    #[test]
+    fn recursive_run_deduplicates_across_directories() {
+        let dir = tempdir().unwrap();
+        let package_json = json!({ "dependencies": { "dep": "^1.0.0" } }).to_string();
+        let dep_manifest = json!({ "repository": "https://github.com/example/repo" }).to_string();
+        for member in ["", "packages/web"] {
+            let member_dir = dir.path().join(member);
+            fs::create_dir_all(member_dir.join("node_modules/dep")).unwrap();
+            fs::write(member_dir.join("package.json"), &package_json).unwrap();
+            fs::write(
+                member_dir.join("node_modules/dep/package.json"),
+                &dep_manifest,
+            )
+            .unwrap();
+        }
+
+        let mock = MockGitHub::new();
+        let summary = run_recursive_with_handler(dir.path(), 2, &mock, &mut SilentHandler).unwrap();
+
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(mock.calls.borrow().len(), 1);
+    }
+
+    #[test]
+    fn run_with_options_uses_custom_registry() {
+        struct FixedDiscoverer;
+
+        impl discovery::Discoverer for FixedDiscoverer {
+            fn discover(&self, _project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+                Ok(vec![
+                    discovery::parse_github_repository("custom/one").unwrap()
+                ])
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let mut registry = DiscovererRegistry::empty();
+        registry.register(|_: &Path| true, FixedDiscoverer);
+        let options = RunOptions {
+            registry: Some(&registry),
+            ..RunOptions::default()
+        };
+
+        let mock = MockGitHub::new();
+        let summary = run_with_options(dir.path(), &mock, &mut SilentHandler, &options).unwrap();
+
+        assert_eq!(summary.starred.len(), 1);
+        assert_eq!(
+            mock.calls.borrow()[0],
+            ("custom".to_string(), "one".to_string())
+        );
+    }
+
+    #[test]
+    fn skips_the_repository_the_root_manifest_declares() {
+        struct FixedDiscoverer;
+
+        impl discovery::Discoverer for FixedDiscoverer {
+            fn discover(&self, _project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+                Ok(["example/project", "serde-rs/serde"]
+                    .into_iter()
+                    .filter_map(discovery::parse_github_repository)
+                    .collect())
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"project\"\nrepository = \"https://github.com/Example/project\"\n",
+        )
+        .unwrap();
+        let mut registry = DiscovererRegistry::empty();
+        registry.register(|_: &Path| true, FixedDiscoverer);
+
+        let mock = MockGitHub::new();
+        let options = RunOptions {
+            registry: Some(&registry),
+            ..RunOptions::default()
+        };
+        let summary = run_with_options(dir.path(), &mock, &mut SilentHandler, &options).unwrap();
+
+        assert_eq!(
+            *mock.calls.borrow(),
+            [("serde-rs".to_string(), "serde".to_string())]
+        );
+        assert_eq!(summary.skipped_own.len(), 1);
+        assert_eq!(summary.skipped_own[0].name, "project");
+
+        let mock = MockGitHub::new();
+        let options = RunOptions {
+            registry: Some(&registry),
+            include_self: true,
+            ..RunOptions::default()
+        };
+        let summary = run_with_options(dir.path(), &mock, &mut SilentHandler, &options).unwrap();
+
+        assert_eq!(mock.calls.borrow().len(), 2);
+        assert!(summary.skipped_own.is_empty());
+    }
+
+    #[test]
+    fn run_with_options_sorts_by_name_when_requested() {
+        struct FixedDiscoverer;
+
+        impl discovery::Discoverer for FixedDiscoverer {
+            fn discover(&self, _project_root: &Path) -> Result<Vec<Repository>, DiscoveryError> {
+                Ok(["zeta/app", "Alpha/tool", "alpha/lib", "beta/core"]
+                    .into_iter()
+                    .filter_map(discovery::parse_github_repository)
+                    .collect())
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let mut registry = DiscovererRegistry::empty();
+        registry.register(|_: &Path| true, FixedDiscoverer);
+
+        let mock = MockGitHub::new();
+        let options = RunOptions {
+            registry: Some(&registry),
+            sort_order: SortOrder::Name,
+            ..RunOptions::default()
+        };
+        let summary = run_with_options(dir.path(), &mock, &mut SilentHandler, &options).unwrap();
+
+        let names: Vec<_> = summary
+            .starred
+            .iter()
+            .map(|starred| format!("{}/{}", starred.repository.owner, starred.repository.name))
+            .collect();
+        assert_eq!(names, ["alpha/lib", "Alpha/tool", "beta/core", "zeta/app"]);
+    }
 }
@@ -1,17 +1,68 @@
-use std::io::{self, IsTerminal, Write};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use clap::{Args, Parser, Subcommand};
-use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+#[cfg(feature = "completions")]
+use clap::CommandFactory;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "completions")]
+use clap_complete::Shell;
+use console::Term;
+#[cfg(feature = "progress")]
+use indicatif::ProgressBar;
 use owo_colors::OwoColorize;
 use supports_color::Stream as ColorStream;
+use tracing_subscriber::filter::LevelFilter;
 
-use thanks_stars::config::{ConfigError, ConfigManager};
-use thanks_stars::discovery::Repository;
-use thanks_stars::github::{GitHubApi, GitHubClient, GitHubError};
-use thanks_stars::{run_with_handler, RunError, RunEventHandler, RunSummary};
+use thanks_stars::cache::{CachedGitHubApi, StarCache};
+use thanks_stars::config::{ConfigManager, DEFAULT_PROFILE};
+use thanks_stars::discovery::{DependencyScope, Framework, Repository};
+use thanks_stars::filter::{ExcludeList, OwnerAllowlist};
+use thanks_stars::github::{ApiUsage, GitHubApi, GitHubClient, GitHubError, RepositoryStatus};
+#[cfg(feature = "progress")]
+use thanks_stars::handlers::progress_bar;
+use thanks_stars::http::HttpOptions;
+use thanks_stars::incremental::IncrementalState;
+use thanks_stars::ledger::{Ledger, LedgerHandler};
+use thanks_stars::project_config::ProjectConfig;
+use thanks_stars::report::ReportHandler;
+use thanks_stars::token::{TokenError, TokenResolver, TokenSource};
+use thanks_stars::{
+    run_recursive_with_options, run_with_options, RunError, RunEventHandler, RunOptions, RunSummary,
+};
+
+#[cfg(not(feature = "progress"))]
+use no_progress::{progress_bar, ProgressBar};
+
+/// Stand-in for the indicatif progress bar when the `progress` feature is
+/// disabled. It is always hidden, so lines are printed directly instead.
+#[cfg(not(feature = "progress"))]
+mod no_progress {
+    pub struct ProgressBar;
+
+    impl ProgressBar {
+        pub fn is_hidden(&self) -> bool {
+            true
+        }
+
+        pub fn println(&self, _line: String) {}
+
+        pub fn set_message(&self, _message: impl Into<String>) {}
+
+        pub fn inc(&self, _delta: u64) {}
+
+        pub fn finish_and_clear(&self) {}
+    }
+
+    pub fn progress_bar(_total: usize, _ascii: bool) -> ProgressBar {
+        ProgressBar
+    }
+}
 
 #[derive(Parser)]
 #[command(
@@ -20,6 +71,25 @@ use thanks_stars::{run_with_handler, RunError, RunEventHandler, RunSummary};
     about = "Star the GitHub repositories of your dependencies."
 )]
 struct Cli {
+    /// Log why dependencies are kept or skipped (-v for debug, -vv for trace).
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Read and write this configuration file instead of the default one.
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+    /// Send an extra `Name: Value` header with every GitHub request, for
+    /// proxies or SSO (repeatable; also `THANKS_STARS_EXTRA_HEADER`).
+    #[arg(long = "header", value_name = "NAME: VALUE", global = true)]
+    headers: Vec<String>,
+    /// Send all HTTP requests through this proxy instead of the one from
+    /// `HTTP_PROXY`/`HTTPS_PROXY`; `NO_PROXY` still applies.
+    #[arg(long, value_name = "URL", global = true)]
+    proxy: Option<String>,
+    /// Web address of the GitHub instance to talk to, such as a GitHub
+    /// Enterprise Server; its REST and GraphQL endpoints are derived from it.
+    /// Overrides `THANKS_STARS_API_BASE`.
+    #[arg(long, value_name = "URL", global = true)]
+    github_url: Option<String>,
     #[command(flatten)]
     run: RunArgs,
     #[command(subcommand)]
@@ -31,7 +101,25 @@ enum Commands {
     /// Configure the GitHub personal access token used for starring repositories.
     Auth(AuthArgs),
     /// Star dependencies for the current project.
-    Run(RunArgs),
+    Run(Box<RunArgs>),
+    /// Inspect where and what thanks-stars has stored.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a completion script for the given shell to stdout.
+    #[cfg(feature = "completions")]
+    Completions {
+        /// Shell to generate completions for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the path of the configuration file.
+    Path,
+    /// Print the stored configuration with tokens redacted.
+    Show,
 }
 
 #[derive(Args, Default)]
@@ -39,82 +127,678 @@ struct AuthArgs {
     /// GitHub personal access token (if omitted, you will be prompted).
     #[arg(long)]
     token: Option<String>,
+    /// Read the token from stdin without prompting, e.g. `echo $TOKEN | thanks-stars auth --stdin`.
+    #[arg(long, conflicts_with = "token")]
+    stdin: bool,
+    /// Store the token under a named profile instead of the default one.
+    #[arg(long)]
+    profile: Option<String>,
+    /// Save the token without checking it against the GitHub API.
+    #[arg(long = "no-verify")]
+    no_verify: bool,
 }
 
 #[derive(Args, Default, Clone)]
 struct RunArgs {
-    /// Path to the project root. Defaults to the current directory.
+    /// Path to the project root (also `THANKS_STARS_PATH`). Defaults to the
+    /// current directory.
     #[arg(short, long)]
     path: Option<PathBuf>,
     /// Simulate starring repositories without issuing star requests to GitHub.
     #[arg(long = "dry-run")]
     dry_run: bool,
+    /// Use the token stored under a named profile.
+    #[arg(long)]
+    profile: Option<String>,
+    /// Read the GitHub token from a file (also `GITHUB_TOKEN_FILE`).
+    #[arg(long, value_name = "PATH", conflicts_with = "profile")]
+    token_file: Option<PathBuf>,
+    /// Print where the GitHub token was found (never the token itself).
+    #[arg(long)]
+    print_token_source: bool,
+    /// Remember already-starred repositories between runs to skip repeated checks.
+    #[arg(long, overrides_with = "no_cache")]
+    cache: bool,
+    /// Query GitHub for every repository, ignoring the local star cache.
+    #[arg(long = "no-cache", overrides_with = "cache")]
+    no_cache: bool,
+    /// Only look at repositories this project has not processed before.
+    #[arg(long, overrides_with = "full")]
+    incremental: bool,
+    /// Process every repository, ignoring what earlier runs recorded (default).
+    #[arg(long, overrides_with = "incremental")]
+    full: bool,
+    /// Also look for manifests in subdirectories of the project root.
+    #[arg(long)]
+    recursive: bool,
+    /// How many directory levels `--recursive` descends into.
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        default_value_t = 3,
+        requires = "recursive"
+    )]
+    max_depth: usize,
+    /// Only search the directories and ecosystems listed in `.github/dependabot.yml`.
+    #[cfg(feature = "dependabot")]
+    #[arg(long, conflicts_with = "recursive")]
+    dependabot: bool,
+    /// When manifests of overlapping ecosystems (Maven and Gradle) are both
+    /// present, only read this one (repeatable). By default both are read.
+    #[arg(long, value_name = "ECOSYSTEM")]
+    prefer: Vec<Framework>,
+    /// Only read the manifests of these ecosystems (repeatable or
+    /// comma-separated; also `THANKS_STARS_ONLY`).
+    #[arg(long, value_name = "ECOSYSTEM", value_delimiter = ',')]
+    only: Vec<Framework>,
+    /// Never read the manifests of these ecosystems (repeatable or
+    /// comma-separated; also `THANKS_STARS_EXCLUDE`).
+    #[arg(long, value_name = "ECOSYSTEM", value_delimiter = ',')]
+    exclude_ecosystem: Vec<Framework>,
+    /// Record newly starred repositories in this JSON Lines file, shared across
+    /// projects, and skip anything it already lists.
+    #[arg(long, value_name = "PATH")]
+    ledger: Option<PathBuf>,
+    /// Write a report of thanked repositories (format inferred from `.md` or `.csv`).
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+    /// Write the per-repository lines and the summary to this file instead of
+    /// the terminal; the progress bar is still shown.
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Only print the final summary, without the progress bar or per-repository lines.
+    #[arg(short, long)]
+    quiet: bool,
+    /// Print plain ASCII instead of emoji and spinner glyphs. Also turned on
+    /// when the locale is not UTF-8.
+    #[arg(long, visible_alias = "no-emoji")]
+    ascii: bool,
+    /// Only list repositories that are (or would be) newly starred.
+    #[arg(long = "new-only")]
+    new_only: bool,
+    /// Only star repositories owned by this user or organization (repeatable).
+    #[arg(long = "only-owner", value_name = "OWNER")]
+    only_owner: Vec<String>,
+    /// Never star this `owner/name` repository (repeatable).
+    #[arg(long, value_name = "OWNER/NAME")]
+    exclude: Vec<String>,
+    /// Exit with an error, listing them, if any dependency did not resolve to a
+    /// GitHub repository.
+    #[arg(long = "fail-on-unresolved")]
+    fail_on_unresolved: bool,
+    /// Keep starring the remaining repositories when GitHub refuses one, then
+    /// exit with code 6 listing the failures.
+    #[arg(long = "continue-on-error")]
+    continue_on_error: bool,
+    /// Do not star repositories that have been archived.
+    #[arg(long = "skip-archived")]
+    skip_archived: bool,
+    /// Also star the project's own repository when a dependency resolves to
+    /// it; by default the repository named in the root manifest is skipped.
+    #[arg(long = "include-self")]
+    include_self: bool,
+    /// Only star the dependencies the manifests declare, not the transitive
+    /// ones lockfiles pin. Applies to Python, Composer, Dart, Go, Haskell,
+    /// Gradle and Deno projects.
+    #[arg(long = "direct-only")]
+    direct_only: bool,
+    /// Star without checking whether each repository already is, halving the
+    /// requests at the cost of not reporting already-starred ones.
+    #[arg(long = "skip-star-check")]
+    skip_star_check: bool,
+    /// Show how many stars each repository already has.
+    #[arg(long = "show-stars")]
+    show_stars: bool,
+    /// Print the repositories in sections once the run completes instead of
+    /// as they are handled.
+    #[arg(long = "group-by", value_enum, value_name = "GROUP", default_value_t)]
+    group_by: GroupBy,
+    /// Star at most this many repositories; already-starred ones do not count.
+    #[arg(long, value_name = "N")]
+    max: Option<usize>,
+    /// Star without asking for confirmation first.
+    #[arg(short, long)]
+    yes: bool,
+    /// Run this `cargo` binary for `cargo metadata` instead of the one on
+    /// `PATH` (also `THANKS_STARS_CARGO_BIN`).
+    #[cfg(feature = "ecosystem-cargo")]
+    #[arg(long, value_name = "PATH")]
+    cargo_bin: Option<PathBuf>,
+    /// Seconds to wait for each HTTP request before giving up.
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    /// How many times to retry GitHub and registry requests that time out or fail with a server error, and to wait out a GitHub secondary rate limit.
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+}
+
+/// How `--group-by` arranges the per-repository lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    /// One section per ecosystem.
+    Ecosystem,
+    /// One section per manifest the repositories were first found through.
+    Via,
+    /// Print each repository as it is handled.
+    #[default]
+    None,
+}
+
+/// Exit code for errors without a more specific code.
+const EXIT_FAILURE: u8 = 1;
+/// Exit code when the project has no supported dependency manifests.
+const EXIT_NO_MANIFESTS: u8 = 3;
+/// Exit code when no usable GitHub token is available or GitHub rejects it.
+const EXIT_AUTH: u8 = 4;
+/// Exit code for `--fail-on-unresolved` when some dependency did not resolve.
+const EXIT_UNRESOLVED: u8 = 5;
+/// Exit code for `--continue-on-error` when GitHub refused some repositories.
+const EXIT_PARTIAL: u8 = 6;
+
+/// An error that exits the process with a specific code instead of
+/// [`EXIT_FAILURE`].
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct CodedError {
+    code: u8,
+    message: String,
+}
+
+fn coded(code: u8, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(CodedError {
+        code,
+        message: message.into(),
+    })
+}
+
+/// Pick the exit code for `err` from the first cause that carries one.
+fn exit_code(err: &anyhow::Error) -> u8 {
+    for cause in err.chain() {
+        if let Some(coded) = cause.downcast_ref::<CodedError>() {
+            return coded.code;
+        }
+        if let Some(GitHubError::InsufficientScope { .. } | GitHubError::Api { status: 401, .. }) =
+            cause.downcast_ref::<GitHubError>()
+        {
+            return EXIT_AUTH;
+        }
+    }
+    EXIT_FAILURE
 }
 
-fn main() -> Result<()> {
-    let Cli { run, command } = Cli::parse();
-    let config = ConfigManager::new()?;
+fn main() -> ExitCode {
+    match run_cli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+fn run_cli() -> Result<()> {
+    let Cli {
+        verbose,
+        config,
+        headers,
+        proxy,
+        github_url,
+        run,
+        command,
+    } = Cli::parse();
+    init_logging(verbose);
+    // Only read where the configuration lives once a command needs it, so
+    // `completions` works without a home directory.
+    let config = || -> Result<ConfigManager> {
+        Ok(match config {
+            Some(path) => ConfigManager::with_config_file(path),
+            None => ConfigManager::new()?,
+        })
+    };
+
+    let settings = HttpSettings {
+        headers,
+        proxy,
+        github_url,
+        ..HttpSettings::default()
+    };
 
     match command {
-        Some(Commands::Auth(args)) => handle_auth(args, &config),
-        Some(Commands::Run(args)) => handle_run(args, &config),
-        None => handle_run(run, &config),
+        Some(Commands::Auth(args)) => handle_auth(args, &config()?, &settings),
+        Some(Commands::Run(args)) => handle_run(*args, &config()?, settings),
+        Some(Commands::Config(command)) => handle_config(command, &config()?),
+        #[cfg(feature = "completions")]
+        Some(Commands::Completions { shell }) => {
+            let mut cli = Cli::command();
+            let name = cli.get_name().to_string();
+            clap_complete::generate(shell, &mut cli, name, &mut io::stdout());
+            Ok(())
+        }
+        None => handle_run(run, &config()?, settings),
     }
 }
 
-fn handle_auth(args: AuthArgs, config: &ConfigManager) -> Result<()> {
+/// Send discovery logs to stderr when `-v` is given; stay silent otherwise.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => return,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_writer(io::stderr)
+        .init();
+}
+
+fn handle_auth(args: AuthArgs, config: &ConfigManager, settings: &HttpSettings) -> Result<()> {
     let token = match args.token {
+        _ if args.stdin => read_token_from_stdin()?,
         Some(token) if !token.trim().is_empty() => token.trim().to_string(),
         _ => prompt_for_token()?,
     };
 
-    config
-        .save_token(&token)
-        .context("failed to save GitHub token")?;
+    if !args.no_verify {
+        let login = verify_token(&token, settings)?;
+        println!("Authenticated as {login}");
+    }
+
+    match args.profile.as_deref() {
+        Some(profile) => config.save_token_for_profile(profile, &token),
+        None => config.save_token(&token),
+    }
+    .context("failed to save GitHub token")?;
     println!("Token saved to {}", config.config_file().display());
 
-    if let Err(err) = invite_to_star_thanks_stars(&token) {
+    if let Err(err) = invite_to_star_thanks_stars(&token, settings) {
         eprintln!("Warning: failed to check thanks-stars star status: {err}");
     }
     Ok(())
 }
 
-fn handle_run(args: RunArgs, config: &ConfigManager) -> Result<()> {
-    let root = args
-        .path
-        .unwrap_or(std::env::current_dir().context("failed to determine current directory")?);
+fn handle_config(command: ConfigCommand, config: &ConfigManager) -> Result<()> {
+    match command {
+        ConfigCommand::Path => println!("{}", config.config_file().display()),
+        ConfigCommand::Show => {
+            let summary = config
+                .summary()
+                .context("failed to read the configuration file")?;
+            println!("config file: {}", config.config_file().display());
+            println!(
+                "default profile: {}",
+                summary
+                    .default_profile
+                    .as_deref()
+                    .unwrap_or(DEFAULT_PROFILE)
+            );
+            if summary.has_token {
+                println!("token: ***");
+            }
+            for profile in &summary.profiles {
+                println!("profile {profile}: token ***");
+            }
+            if !summary.owners.is_empty() {
+                println!("owners: {}", summary.owners.join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_run(args: RunArgs, config: &ConfigManager, settings: HttpSettings) -> Result<()> {
+    let root = match args.path.clone().or_else(|| {
+        std::env::var_os("THANKS_STARS_PATH")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }) {
+        Some(path) => path,
+        None => std::env::current_dir().context("failed to determine current directory")?,
+    };
 
-    let token = load_token(config)?;
-    let client = create_client(token).context("failed to initialize GitHub client")?;
+    let (token, source) = load_token(config, args.profile.as_deref(), args.token_file.as_deref())?;
+    if args.print_token_source {
+        eprintln!("Using the GitHub token from {source}.");
+    }
+    let settings = HttpSettings {
+        timeout: args.timeout.map(Duration::from_secs),
+        retries: args.retries,
+        ..settings
+    };
+    let proxy = settings
+        .proxy
+        .as_deref()
+        .map(thanks_stars::http::proxy)
+        .transpose()
+        .context("invalid --proxy URL")?;
+    let client = create_client(token, &settings).context("failed to initialize GitHub client")?;
 
-    let mut handler = CliRunHandler::new(args.dry_run);
-    let adapter = MaybeDryRunClient::new(&client, args.dry_run);
-    run_with_handler(&root, &adapter, &mut handler).map_err(|err| match err {
-        RunError::NoFrameworks(path) => {
-            anyhow!("no supported dependency definitions found in {path}")
+    let report = args
+        .report
+        .as_ref()
+        .map(|path| ReportHandler::new(path, args.dry_run))
+        .transpose()?;
+    let scope = if args.recursive {
+        Scope::Recursive(args.max_depth)
+    } else {
+        Scope::Root
+    };
+    #[cfg(feature = "dependabot")]
+    let scope = if args.dependabot {
+        Scope::Dependabot
+    } else {
+        scope
+    };
+    let project_config =
+        ProjectConfig::load(&root).context("failed to read the project configuration")?;
+    let owners = if !args.only_owner.is_empty() {
+        args.only_owner.clone()
+    } else if !project_config.owners.is_empty() {
+        project_config.owners.clone()
+    } else {
+        config
+            .owners()
+            .context("failed to read the owner allowlist")?
+    };
+    let mut incremental = if args.incremental && !args.full {
+        Some(IncrementalState::load_default().context("failed to load incremental state")?)
+    } else {
+        None
+    };
+    let seen = incremental.as_ref().map(|state| state.seen(&root));
+    let only = if args.only.is_empty() {
+        frameworks_from_env("THANKS_STARS_ONLY")?.or(project_config.ecosystems.clone())
+    } else {
+        Some(args.only.clone())
+    };
+    let excluded = if args.exclude_ecosystem.is_empty() {
+        frameworks_from_env("THANKS_STARS_EXCLUDE")?.unwrap_or_default()
+    } else {
+        args.exclude_ecosystem.clone()
+    };
+    let frameworks = if excluded.is_empty() {
+        only
+    } else {
+        let mut frameworks = only.unwrap_or_else(|| Framework::ALL.to_vec());
+        frameworks.retain(|framework| !excluded.contains(framework));
+        Some(frameworks)
+    };
+    let mut options = RunOptions {
+        limit: args.max,
+        skip_archived: args.skip_archived,
+        assume_unstarred: args.skip_star_check,
+        fetch_stargazers: args.show_stars,
+        include_self: args.include_self,
+        continue_on_error: args.continue_on_error,
+        dependency_scope: if args.direct_only {
+            DependencyScope::Direct
+        } else {
+            DependencyScope::All
+        },
+        seen: seen.as_ref(),
+        http: HttpOptions {
+            timeout: settings.timeout,
+            proxy,
+            retries: settings
+                .retries
+                .unwrap_or(thanks_stars::http::DEFAULT_RETRIES),
+        },
+        frameworks: frameworks.as_deref(),
+        prefer: if args.prefer.is_empty() {
+            &project_config.prefer
+        } else {
+            &args.prefer
+        },
+        ..RunOptions::default()
+    };
+    #[cfg(feature = "ecosystem-cargo")]
+    {
+        options.cargo_bin = args.cargo_bin.as_deref();
+    }
+    if !owners.is_empty() {
+        options.filters.push(Box::new(OwnerAllowlist::new(owners)));
+    }
+    // Like --only-owner, --exclude replaces the project's list.
+    let exclude = if args.exclude.is_empty() {
+        &project_config.exclude
+    } else {
+        &args.exclude
+    };
+    if !exclude.is_empty() {
+        options.filters.push(Box::new(ExcludeList::new(exclude)));
+    }
+    if let Some(path) = &args.ledger {
+        let ledger = Ledger::load(path).context("failed to load the ledger")?;
+        options.filters.push(Box::new(ledger));
+    }
+    let mut cli_handler = CliRunHandler::new(args.dry_run, args.quiet, args.new_only);
+    cli_handler.ascii = args.ascii || !locale_is_utf8();
+    cli_handler.show_stars = args.show_stars;
+    cli_handler.group_by = args.group_by;
+    if let Some(path) = &args.output {
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        cli_handler = cli_handler.with_output(file);
+    }
+    // Only ask when someone can answer: dry runs are harmless and scripts or
+    // CI without a terminal keep starring as before.
+    cli_handler.confirm = !args.yes && !args.dry_run && io::stdin().is_terminal();
+    let ledger = args
+        .ledger
+        .as_ref()
+        .map(|path| LedgerHandler::new(path, &root, args.dry_run));
+    let mut handler = (cli_handler, (report, ledger));
+    let summary = if args.cache && !args.no_cache {
+        let login = client
+            .current_user()
+            .context("failed to look up the account for the star cache")?;
+        let cache = StarCache::load_default(&login).context("failed to load star cache")?;
+        let cached = CachedGitHubApi::new(&client, cache);
+        let adapter = MaybeDryRunClient::new(&cached, args.dry_run);
+        let result = run_project(&root, scope, &adapter, &mut handler, &options);
+        if let Err(err) = cached.persist() {
+            eprintln!("Warning: failed to save star cache: {err}");
         }
+        result.map_err(map_run_error)?
+    } else {
+        let adapter = MaybeDryRunClient::new(&client, args.dry_run);
+        run_project(&root, scope, &adapter, &mut handler, &options).map_err(map_run_error)?
+    };
+
+    // A dry run changes nothing, so the next run must still look at everything.
+    if let Some(state) = incremental.as_mut().filter(|_| !args.dry_run) {
+        let processed = summary.starred.iter().map(|starred| &starred.repository);
+        state.record(&root, processed.chain(&summary.archived));
+        if let Err(err) = state.save() {
+            eprintln!("Warning: failed to save incremental state: {err}");
+        }
+    }
+
+    if let (Some(err), Some(path)) = (handler.0.take_output_error(), &args.output) {
+        return Err(anyhow!(err).context(format!("failed to write {}", path.display())));
+    }
+    let (report, ledger) = &mut handler.1;
+    if let Some(err) = report.as_mut().and_then(ReportHandler::take_error) {
+        return Err(anyhow!(err));
+    }
+    if let Some(err) = ledger.as_mut().and_then(LedgerHandler::take_error) {
+        return Err(anyhow!(err));
+    }
+    if !summary.failed.is_empty() {
+        let failed: Vec<_> = summary
+            .failed
+            .iter()
+            .map(|failed| format!("{} ({})", failed.repository.url, failed.error))
+            .collect();
+        return Err(coded(
+            EXIT_PARTIAL,
+            format!(
+                "{} {} could not be starred: {}",
+                failed.len(),
+                if failed.len() == 1 {
+                    "repository"
+                } else {
+                    "repositories"
+                },
+                failed.join(", ")
+            ),
+        ));
+    }
+    if args.fail_on_unresolved && summary.unresolved > 0 {
+        let mut names = summary.unresolved_dependencies.clone();
+        names.sort();
+        names.dedup();
+        return Err(coded(
+            EXIT_UNRESOLVED,
+            format!(
+                "{} {} did not resolve to a GitHub repository: {}",
+                summary.unresolved,
+                if summary.unresolved == 1 {
+                    "dependency"
+                } else {
+                    "dependencies"
+                },
+                names.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Which directories of the project a run searches for manifests.
+#[derive(Clone, Copy)]
+enum Scope {
+    Root,
+    Recursive(usize),
+    #[cfg(feature = "dependabot")]
+    Dependabot,
+}
+
+fn run_project(
+    root: &Path,
+    scope: Scope,
+    api: &dyn GitHubApi,
+    handler: &mut impl RunEventHandler,
+    options: &RunOptions<'_>,
+) -> Result<RunSummary, RunError> {
+    match scope {
+        Scope::Root => run_with_options(root, api, handler, options),
+        Scope::Recursive(max_depth) => {
+            run_recursive_with_options(root, max_depth, api, handler, options)
+        }
+        #[cfg(feature = "dependabot")]
+        Scope::Dependabot => thanks_stars::run_dependabot_with_options(root, api, handler, options),
+    }
+}
+
+fn map_run_error(err: RunError) -> anyhow::Error {
+    match err {
+        RunError::NoFrameworks(path) => coded(
+            EXIT_NO_MANIFESTS,
+            format!("no supported dependency definitions found in {path}"),
+        ),
         RunError::Discovery(inner) => anyhow!(*inner),
+        RunError::Cancelled => anyhow!("aborted; no repositories were starred"),
+        #[cfg(feature = "dependabot")]
+        RunError::Dependabot(inner) => anyhow!(inner),
+        RunError::GitHub(inner @ GitHubError::InsufficientScope { .. }) => anyhow!(inner).context(
+            "starring needs a classic token with the `public_repo` scope (or `repo` for private \
+             repositories), or a fine-grained token with the \"Starring\" user permission set to \
+             read and write; create one and run `thanks-stars auth` again",
+        ),
+        RunError::GitHub(inner @ GitHubError::SecondaryRateLimit { .. }) => anyhow!(inner)
+            .context("GitHub is throttling star requests; wait a moment and run the command again"),
+        RunError::GitHub(inner @ GitHubError::Timeout(_)) => anyhow!(inner)
+            .context("GitHub took too long to respond; raise the limit with --timeout"),
         RunError::GitHub(inner) => anyhow!(inner),
-    })?;
-    Ok(())
+    }
+}
+
+fn verify_token(token: &str, settings: &HttpSettings) -> Result<String> {
+    let client =
+        create_client(token.to_string(), settings).context("failed to initialize GitHub client")?;
+    client.current_user().map_err(|err| match err {
+        GitHubError::Api { status: 401, .. } => coded(
+            EXIT_AUTH,
+            "GitHub rejected the token (401 Unauthorized). Check that it was copied correctly, or pass --no-verify to save it anyway.",
+        ),
+        other => anyhow!(other).context("failed to verify GitHub token"),
+    })
+}
+
+/// HTTP overrides from the command line; `None` keeps the library default.
+#[derive(Default, Clone)]
+struct HttpSettings {
+    timeout: Option<Duration>,
+    retries: Option<u32>,
+    /// Extra headers as given on the command line, in `Name: Value` form.
+    headers: Vec<String>,
+    proxy: Option<String>,
+    github_url: Option<String>,
 }
 
-fn create_client(token: String) -> Result<GitHubClient, GitHubError> {
-    if let Ok(base) = std::env::var("THANKS_STARS_API_BASE") {
-        GitHubClient::with_base_url(token, base)
+fn create_client(token: String, settings: &HttpSettings) -> Result<GitHubClient, GitHubError> {
+    let mut client = if let Some(url) = &settings.github_url {
+        GitHubClient::for_github_url(token, url)?
+    } else if let Ok(base) = std::env::var("THANKS_STARS_API_BASE") {
+        GitHubClient::with_base_url(token, base)?
     } else {
-        GitHubClient::new(token)
+        GitHubClient::new(token)?
+    };
+    if let Some(timeout) = settings.timeout {
+        client = client.with_timeout(timeout)?;
+    }
+    if let Some(retries) = settings.retries {
+        client = client.with_retry(retries);
+    }
+    if let Some(proxy) = &settings.proxy {
+        client = client.with_proxy(proxy)?;
     }
+    let from_env = std::env::var("THANKS_STARS_EXTRA_HEADER")
+        .ok()
+        .filter(|value| !value.trim().is_empty());
+    let headers = from_env
+        .iter()
+        .map(|raw| ("THANKS_STARS_EXTRA_HEADER", raw))
+        .chain(settings.headers.iter().map(|raw| ("--header", raw)));
+    for (origin, raw) in headers {
+        let Some((name, value)) = raw.split_once(':') else {
+            return Err(GitHubError::MalformedHeader { origin });
+        };
+        client = client.with_header(name, value)?;
+    }
+    Ok(client)
 }
 
-fn prompt_for_token() -> Result<String> {
-    print!("GitHub personal access token: ");
-    io::stdout().flush().ok();
+fn read_token_from_stdin() -> Result<String> {
     let mut token = String::new();
     io::stdin()
-        .read_line(&mut token)
+        .read_to_string(&mut token)
         .context("failed to read token from stdin")?;
     let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err(anyhow!("no token was provided on stdin"));
+    }
+    Ok(token)
+}
+
+fn prompt_for_token() -> Result<String> {
+    let term = Term::stdout();
+    print!("GitHub personal access token: ");
+    io::stdout().flush().ok();
+    // Mask the token while it is typed; fall back to a plain read when the
+    // input is piped so scripted invocations keep working.
+    let token = if io::stdin().is_terminal() && term.is_term() {
+        term.read_secure_line()
+            .context("failed to read token from the terminal")?
+    } else {
+        let mut token = String::new();
+        io::stdin()
+            .read_line(&mut token)
+            .context("failed to read token from stdin")?;
+        token
+    };
+    let token = token.trim().to_string();
     if token.is_empty() {
         return Err(anyhow!("token must not be empty"));
     }
@@ -124,12 +808,12 @@ fn prompt_for_token() -> Result<String> {
 const THANKS_STARS_OWNER: &str = "Kenzo-Wada";
 const THANKS_STARS_REPO: &str = "thanks-stars";
 
-fn invite_to_star_thanks_stars(token: &str) -> Result<()> {
+fn invite_to_star_thanks_stars(token: &str, settings: &HttpSettings) -> Result<()> {
     if !io::stdin().is_terminal() {
         return Ok(());
     }
 
-    let client = create_client(token.to_string())?;
+    let client = create_client(token.to_string(), settings)?;
     let already_starred = client.viewer_has_starred(THANKS_STARS_OWNER, THANKS_STARS_REPO)?;
 
     if already_starred {
@@ -171,44 +855,134 @@ fn prompt_yes_no(prompt: &str) -> Result<bool> {
     }
 }
 
+/// Prints human-readable progress and status lines to stderr, leaving stdout
+/// free for machine-readable output.
 struct CliRunHandler {
     progress: Option<ProgressBar>,
     dry_run: bool,
+    quiet: bool,
+    new_only: bool,
+    hidden_already_starred: usize,
+    confirm: bool,
+    ascii: bool,
+    show_stars: bool,
+    group_by: GroupBy,
+    /// Lines held back for their `--group-by` section.
+    groups: BTreeMap<String, Vec<String>>,
+    /// The `--output` file that takes the lines otherwise printed to stderr.
+    output: Option<BufWriter<File>>,
+    /// The first error raised while writing to [`output`](Self::output).
+    output_error: Option<io::Error>,
 }
 
 impl CliRunHandler {
-    fn new(dry_run: bool) -> Self {
+    fn new(dry_run: bool, quiet: bool, new_only: bool) -> Self {
         Self {
             progress: None,
             dry_run,
+            quiet,
+            new_only,
+            hidden_already_starred: 0,
+            confirm: false,
+            ascii: false,
+            show_stars: false,
+            group_by: GroupBy::None,
+            groups: BTreeMap::new(),
+            output: None,
+            output_error: None,
+        }
+    }
+
+    /// Write the per-repository lines and the summary to `file` instead of
+    /// stderr, without colors. The progress bar stays on stderr.
+    fn with_output(mut self, file: File) -> Self {
+        self.output = Some(BufWriter::new(file));
+        self
+    }
+
+    /// Return the error raised while writing the `--output` file, if any.
+    fn take_output_error(&mut self) -> Option<io::Error> {
+        self.output_error.take()
+    }
+
+    /// Print `line` to the `--output` file, or to stderr without one.
+    fn print(&mut self, line: impl fmt::Display) {
+        let Some(output) = &mut self.output else {
+            eprintln!("{line}");
+            return;
+        };
+        if let Err(err) = writeln!(output, "{line}") {
+            self.output_error.get_or_insert(err);
         }
     }
 
     fn message_prefix(&self, already_starred: bool) -> &'static str {
-        if already_starred {
-            "✅ Already starred"
-        } else if self.dry_run {
-            "⭐ Would star"
+        match (self.ascii, already_starred, self.dry_run) {
+            (false, true, _) => "✅ Already starred",
+            (false, false, true) => "⭐ Would star",
+            (false, false, false) => "⭐ Starred",
+            (true, true, _) => "[already starred]",
+            (true, false, true) => "[would star]",
+            (true, false, false) => "[starred]",
+        }
+    }
+
+    /// `icon` (including its trailing spacing), or nothing in ASCII mode.
+    fn icon(&self, icon: &'static str) -> &'static str {
+        if self.ascii {
+            ""
         } else {
-            "⭐ Starred"
+            icon
+        }
+    }
+
+    /// ` (⭐ 12.3k)` for a repository whose stargazer count is known and
+    /// should be shown, otherwise nothing.
+    fn stars_suffix(&self, repo: &Repository) -> String {
+        match repo.stargazer_count {
+            Some(count) if self.show_stars => {
+                let count = compact_count(count);
+                if self.ascii {
+                    format!(" ({count} stars)")
+                } else {
+                    format!(" (⭐ {count})")
+                }
+            }
+            _ => String::new(),
         }
     }
 
-    fn create_progress(total: usize) -> ProgressBar {
-        let pb = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stdout());
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.green} {pos}/{len} ⭐ {wide_msg}")
-                .unwrap()
-                .tick_chars("⠁⠃⠇⡇⣇⣧⣷⣿"),
-        );
-        pb.enable_steady_tick(Duration::from_millis(120));
-        pb
+    /// The `--group-by` section `repo` belongs in, if lines are grouped.
+    fn group_key(&self, repo: &Repository) -> Option<String> {
+        match self.group_by {
+            GroupBy::None => None,
+            GroupBy::Ecosystem => Some(repo.framework.map_or("other", Framework::name).to_string()),
+            GroupBy::Via => Some(
+                repo.via
+                    .clone()
+                    .unwrap_or_else(|| "unknown source".to_string()),
+            ),
+        }
     }
 
-    fn color_enabled() -> bool {
-        supports_color::on_cached(ColorStream::Stdout)
-            .map(|level| level.has_basic)
-            .unwrap_or(false)
+    /// Print `line` above the progress bar, or hold it back for the
+    /// `--group-by` section of `repo`.
+    fn emit(&mut self, repo: &Repository, line: String) {
+        if let Some(key) = self.group_key(repo) {
+            self.groups.entry(key).or_default().push(line);
+            return;
+        }
+        match &self.progress {
+            Some(pb) if !pb.is_hidden() && self.output.is_none() => pb.println(line),
+            _ => self.print(line),
+        }
+    }
+
+    fn color_enabled(&self) -> bool {
+        self.output.is_none()
+            && supports_color::on_cached(ColorStream::Stderr)
+                .map(|level| level.has_basic)
+                .unwrap_or(false)
     }
 }
 
@@ -221,17 +995,52 @@ impl Drop for CliRunHandler {
 }
 
 impl RunEventHandler for CliRunHandler {
+    fn confirm(&mut self, repositories: &[Repository]) -> bool {
+        if !self.confirm || repositories.is_empty() {
+            return true;
+        }
+        let plural = if repositories.len() == 1 {
+            "repository"
+        } else {
+            "repositories"
+        };
+        eprint!("Star {} {plural}? [y/N] ", repositories.len());
+        io::stderr().flush().ok();
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+    }
+
+    fn on_prechecking(&mut self, total: usize) {
+        if total == 0 || self.quiet {
+            return;
+        }
+        let pb = progress_bar(total, self.ascii);
+        pb.set_message(format!(
+            "Checking {total} {}...",
+            if total == 1 {
+                "repository"
+            } else {
+                "repositories"
+            }
+        ));
+        self.progress = Some(pb);
+    }
+
     fn on_start(&mut self, total: usize) {
-        if total == 0 {
+        if total == 0 || self.quiet {
             return;
         }
-        let pb = Self::create_progress(total);
+        let pb = self
+            .progress
+            .get_or_insert_with(|| progress_bar(total, self.ascii));
         if self.dry_run {
             pb.set_message("Dry run: evaluating repositories...");
         } else {
             pb.set_message("Preparing to star repositories...");
         }
-        self.progress = Some(pb);
     }
 
     fn on_starred(
@@ -241,7 +1050,17 @@ impl RunEventHandler for CliRunHandler {
         _index: usize,
         _total: usize,
     ) {
-        let use_color = Self::color_enabled();
+        if self.quiet {
+            return;
+        }
+        if self.new_only && already_starred {
+            self.hidden_already_starred += 1;
+            if let Some(pb) = &self.progress {
+                pb.inc(1);
+            }
+            return;
+        }
+        let use_color = self.color_enabled();
         let prefix = self.message_prefix(already_starred);
         let label = if use_color {
             if already_starred {
@@ -261,7 +1080,10 @@ impl RunEventHandler for CliRunHandler {
             repo_url_source
         };
 
-        let via_label_raw = repo.via.as_deref().unwrap_or("unknown source");
+        let stars_text = self.stars_suffix(repo);
+        let via_label_raw = repo
+            .via_label()
+            .unwrap_or_else(|| "unknown source".to_string());
         let via_text = if use_color {
             format!(" via {}", via_label_raw.cyan())
         } else {
@@ -277,23 +1099,53 @@ impl RunEventHandler for CliRunHandler {
         if let Some(pb) = &self.progress {
             pb.set_message(format!("{}{}{}", repo.url, status_suffix, via_text));
             pb.inc(1);
-            let line = format!("{label} {repo_url}{status_suffix}{via_text}");
-            if pb.is_hidden() {
-                println!("{line}");
-            } else {
-                pb.println(line);
-            }
-        } else {
-            println!("{label} {repo_url}{status_suffix}{via_text}");
         }
+        let line = format!("{label} {repo_url}{stars_text}{status_suffix}{via_text}");
+        self.emit(repo, line);
+    }
+
+    fn on_archived(&mut self, repo: &Repository, _index: usize, _total: usize) {
+        if self.quiet {
+            return;
+        }
+        if let Some(pb) = &self.progress {
+            pb.inc(1);
+        }
+        let line = format!("{}Skipped {} (archived)", self.icon("🗄️  "), repo.url);
+        self.emit(repo, line);
+    }
+
+    fn on_failed(&mut self, repo: &Repository, error: &GitHubError, _index: usize, _total: usize) {
+        if self.quiet {
+            return;
+        }
+        if let Some(pb) = &self.progress {
+            pb.inc(1);
+        }
+        let line = format!("{}Failed {}: {error}", self.icon("❌ "), repo.url);
+        self.emit(repo, line);
     }
 
     fn on_complete(&mut self, summary: &RunSummary) {
         if let Some(pb) = self.progress.take() {
             pb.finish_and_clear();
         }
+        for warning in &summary.warnings {
+            self.print(format!("Warning: {warning}."));
+        }
 
-        let use_color = Self::color_enabled();
+        let use_color = self.color_enabled();
+        for (group, lines) in std::mem::take(&mut self.groups) {
+            let header = format!("{group} ({})", lines.len());
+            if use_color {
+                self.print(header.bold());
+            } else {
+                self.print(header);
+            }
+            for line in lines {
+                self.print(format!("  {line}"));
+            }
+        }
 
         let already_starred_count = summary
             .starred
@@ -302,14 +1154,53 @@ impl RunEventHandler for CliRunHandler {
             .count();
         let newly_starred_count = summary.starred.len().saturating_sub(already_starred_count);
 
-        if summary.starred.is_empty() {
+        // Nothing was starred because discovery came up empty, rather than
+        // because everything was filtered out or already handled.
+        let nothing_resolved = summary.starred.is_empty()
+            && summary.archived.is_empty()
+            && summary.filtered_out == 0
+            && summary.previously_seen == 0
+            && summary.skipped_own.is_empty()
+            && summary.failed.is_empty()
+            && summary.unresolved == summary.examined;
+
+        if nothing_resolved {
+            let text = if summary.examined == 0 {
+                format!(
+                    "{}No dependencies were found in the detected manifests.",
+                    self.icon("🌱 ")
+                )
+            } else {
+                format!(
+                    "{}None of the {} {} resolved to a GitHub repository; run with --verbose to see why.",
+                    self.icon("🤷 "),
+                    summary.examined,
+                    if summary.examined == 1 {
+                        "dependency"
+                    } else {
+                        "dependencies"
+                    }
+                )
+            };
             let msg = if use_color {
-                format!("{}", "🌱 No repositories required starring today.".yellow())
+                format!("{}", text.yellow())
             } else {
-                "🌱 No repositories required starring today.".to_string()
+                text
             };
-            println!("{msg}");
+            self.print(msg);
+        } else if summary.starred.is_empty() {
+            let text = format!(
+                "{}No repositories required starring today.",
+                self.icon("🌱 ")
+            );
+            let msg = if use_color {
+                format!("{}", text.yellow())
+            } else {
+                text
+            };
+            self.print(msg);
         } else {
+            let (star, check) = (self.icon("⭐ "), self.icon("✅ "));
             let pluralize = |count: usize| {
                 if count == 1 {
                     "repository"
@@ -319,24 +1210,25 @@ impl RunEventHandler for CliRunHandler {
             };
 
             if self.dry_run {
+                let done = format!("{}Dry run complete!", self.icon("✨ "));
                 let done = if use_color {
-                    format!("{}", "✨ Dry run complete!".yellow().bold())
+                    format!("{}", done.yellow().bold())
                 } else {
-                    "✨ Dry run complete!".to_string()
+                    done
                 };
                 let detail = if newly_starred_count > 0 && already_starred_count > 0 {
                     format!(
-                        "⭐ {newly_starred_count} {new_plural} would be starred, ✅ {already_starred_count} already starred.",
+                        "{star}{newly_starred_count} {new_plural} would be starred, {check}{already_starred_count} already starred.",
                         new_plural = pluralize(newly_starred_count)
                     )
                 } else if newly_starred_count > 0 {
                     format!(
-                        "⭐ {newly_starred_count} {new_plural} would be starred.",
+                        "{star}{newly_starred_count} {new_plural} would be starred.",
                         new_plural = pluralize(newly_starred_count)
                     )
                 } else {
                     format!(
-                        "✅ All {already_starred_count} {already_plural} are already starred.",
+                        "{check}All {already_starred_count} {already_plural} are already starred.",
                         already_plural = pluralize(already_starred_count)
                     )
                 };
@@ -345,26 +1237,37 @@ impl RunEventHandler for CliRunHandler {
                 } else {
                     detail
                 };
-                println!("{done} {detail}");
+                self.print(format!("{done} {detail}"));
             } else {
+                let done = format!("{}Completed!", self.icon("✨ "));
                 let done = if use_color {
-                    format!("{}", "✨ Completed!".green().bold())
+                    format!("{}", done.green().bold())
                 } else {
-                    "✨ Completed!".to_string()
+                    done
                 };
-                let detail = if newly_starred_count > 0 && already_starred_count > 0 {
+                let detail = if summary.star_check_skipped {
                     format!(
-                        "⭐ Starred {newly_starred_count} {new_plural}, ✅ {already_starred_count} already starred.",
+                        "{star}Ensured {newly_starred_count} {new_plural} {verb} starred.",
+                        new_plural = pluralize(newly_starred_count),
+                        verb = if newly_starred_count == 1 {
+                            "is"
+                        } else {
+                            "are"
+                        }
+                    )
+                } else if newly_starred_count > 0 && already_starred_count > 0 {
+                    format!(
+                        "{star}Starred {newly_starred_count} {new_plural}, {check}{already_starred_count} already starred.",
                         new_plural = pluralize(newly_starred_count)
                     )
                 } else if newly_starred_count > 0 {
                     format!(
-                        "⭐ Starred {newly_starred_count} {new_plural}.",
+                        "{star}Starred {newly_starred_count} {new_plural}.",
                         new_plural = pluralize(newly_starred_count)
                     )
                 } else {
                     format!(
-                        "✅ All {already_starred_count} {already_plural} were already starred.",
+                        "{check}All {already_starred_count} {already_plural} were already starred.",
                         already_plural = pluralize(already_starred_count)
                     )
                 };
@@ -373,12 +1276,173 @@ impl RunEventHandler for CliRunHandler {
                 } else {
                     detail
                 };
-                println!("{done} {detail}");
+                self.print(format!("{done} {detail}"));
             }
         }
+
+        if !summary.archived.is_empty() {
+            let count = summary.archived.len();
+            self.print(format!(
+                "{}Skipped {count} archived {}.",
+                self.icon("🗄️  "),
+                if count == 1 {
+                    "repository"
+                } else {
+                    "repositories"
+                }
+            ));
+        }
+
+        if !summary.failed.is_empty() {
+            let count = summary.failed.len();
+            self.print(format!(
+                "{}Failed to star {count} {}.",
+                self.icon("❌ "),
+                if count == 1 {
+                    "repository"
+                } else {
+                    "repositories"
+                }
+            ));
+        }
+
+        for repo in &summary.skipped_own {
+            self.print(format!(
+                "{}Skipped {} (this project's own repository; pass --include-self to star it).",
+                self.icon("🏠 "),
+                repo.url
+            ));
+        }
+
+        if summary.previously_seen > 0 {
+            self.print(format!(
+                "{}Skipped {} {} handled by an earlier --incremental run.",
+                self.icon("⏭️  "),
+                summary.previously_seen,
+                if summary.previously_seen == 1 {
+                    "repository"
+                } else {
+                    "repositories"
+                }
+            ));
+        }
+
+        for (reason, count) in &summary.filtered_out_by {
+            self.print(format!(
+                "{}Skipped {count} {} {reason}.",
+                self.icon("🚫 "),
+                if *count == 1 {
+                    "repository"
+                } else {
+                    "repositories"
+                }
+            ));
+        }
+
+        if summary.limit_reached {
+            self.print(format!(
+                "{}Stopped after {newly_starred_count} new {}; run again to continue.",
+                self.icon("🛑 "),
+                if newly_starred_count == 1 {
+                    "star"
+                } else {
+                    "stars"
+                }
+            ));
+        }
+
+        if self.hidden_already_starred > 0 {
+            let plural = if self.hidden_already_starred == 1 {
+                "repository"
+            } else {
+                "repositories"
+            };
+            self.print(format!(
+                "{}Hid {} already-starred {plural}.",
+                self.icon("🙈 "),
+                self.hidden_already_starred
+            ));
+        }
+
+        if summary.examined > 0 {
+            let resolved = summary.examined.saturating_sub(summary.unresolved);
+            let plural = if summary.examined == 1 {
+                "dependency"
+            } else {
+                "dependencies"
+            };
+            self.print(format!(
+                "{}Scanned {} {plural}, {resolved} had GitHub repositories.",
+                self.icon("🔎 "),
+                summary.examined
+            ));
+        }
+
+        if let Some(usage) = summary.api_usage.filter(|usage| usage.calls > 0) {
+            let calls = format!(
+                "{}Used {} API {}",
+                self.icon("📡 "),
+                usage.calls,
+                if usage.calls == 1 { "call" } else { "calls" }
+            );
+            match usage.rate_limit_remaining {
+                Some(remaining) => self.print(format!(
+                    "{calls}; {remaining} requests remaining this hour."
+                )),
+                None => self.print(format!("{calls}.")),
+            }
+        }
+
+        if let Some(Err(err)) = self.output.as_mut().map(Write::flush) {
+            self.output_error.get_or_insert(err);
+        }
+    }
+}
+
+/// The comma-separated ecosystems in the environment variable `name`, or
+/// `None` if it is unset or empty.
+fn frameworks_from_env(name: &str) -> Result<Option<Vec<Framework>>> {
+    let Some(value) = std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    value
+        .split(',')
+        .map(|name| name.trim().parse())
+        .collect::<Result<Vec<Framework>, _>>()
+        .map(Some)
+        .with_context(|| format!("invalid {name}"))
+}
+
+/// `count` in at most four digits and a suffix, e.g. `950`, `12.3k` or
+/// `1.2M`. Rounds down, so a count never looks larger than it is.
+fn compact_count(count: u64) -> String {
+    let (tenths, unit) = match count {
+        0..=999 => return count.to_string(),
+        1_000..=999_999 => (count / 100, "k"),
+        _ => (count / 100_000, "M"),
+    };
+    match tenths % 10 {
+        0 => format!("{}{unit}", tenths / 10),
+        fraction => format!("{}.{fraction}{unit}", tenths / 10),
     }
 }
 
+/// Whether the locale (`LC_ALL`, `LC_CTYPE`, then `LANG`) can display
+/// non-ASCII glyphs. An unset locale is assumed to be UTF-8.
+fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .map(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+        .unwrap_or(true)
+}
+
 struct MaybeDryRunClient<'a, T: GitHubApi> {
     inner: &'a T,
     dry_run: bool,
@@ -395,6 +1459,10 @@ impl<'a, T: GitHubApi> GitHubApi for MaybeDryRunClient<'a, T> {
         self.inner.viewer_has_starred(owner, repo)
     }
 
+    fn repository_status(&self, owner: &str, repo: &str) -> Result<RepositoryStatus, GitHubError> {
+        self.inner.repository_status(owner, repo)
+    }
+
     fn star(&self, owner: &str, repo: &str) -> Result<(), GitHubError> {
         if self.dry_run {
             Ok(())
@@ -402,20 +1470,33 @@ impl<'a, T: GitHubApi> GitHubApi for MaybeDryRunClient<'a, T> {
             self.inner.star(owner, repo)
         }
     }
-}
 
-fn load_token(config: &ConfigManager) -> Result<String> {
-    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-        if !token.trim().is_empty() {
-            return Ok(token);
-        }
+    fn usage(&self) -> Option<ApiUsage> {
+        self.inner.usage()
     }
+}
 
-    match config.load_token() {
-        Ok(token) => Ok(token),
-        Err(ConfigError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Err(anyhow!(
-            "GitHub token not found. Run `thanks-stars auth --token <token>` or set GITHUB_TOKEN."
-        )),
+fn load_token(
+    config: &ConfigManager,
+    profile: Option<&str>,
+    token_file: Option<&Path>,
+) -> Result<(String, TokenSource)> {
+    let resolver = TokenResolver::from_environment(profile, token_file.map(Path::to_path_buf));
+    match resolver.resolve(config) {
+        Ok((token, source)) => {
+            tracing::debug!("using token from {source}");
+            Ok((token, source))
+        }
+        Err(TokenError::NotFound) => match profile {
+            Some(profile) => Err(coded(
+                EXIT_AUTH,
+                format!("GitHub token not found for profile `{profile}`. Run `thanks-stars auth --profile {profile} --token <token>`."),
+            )),
+            None => Err(coded(
+                EXIT_AUTH,
+                "GitHub token not found. Run `thanks-stars auth --token <token>` or set GITHUB_TOKEN.",
+            )),
+        },
         Err(err) => Err(anyhow!(err)),
     }
 }
@@ -0,0 +1,196 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::discovery::{Framework, UnknownFramework};
+
+/// Standalone project configuration file, read from the project root.
+pub const PROJECT_CONFIG_FILE: &str = ".thanks-stars.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectConfigError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Toml {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("{path}: {source}")]
+    Ecosystem {
+        path: String,
+        #[source]
+        source: UnknownFramework,
+    },
+}
+
+/// Preferences a project commits for everyone running `thanks-stars` in it.
+///
+/// They come from a `[tool.thanks-stars]` table in `pyproject.toml` and from
+/// [`PROJECT_CONFIG_FILE`], whose settings take precedence. Exclusions from
+/// both files are combined.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    /// `owner/name` repositories that are never starred.
+    pub exclude: Vec<String>,
+    /// Ecosystems to discover; `None` means every detected one.
+    pub ecosystems: Option<Vec<Framework>>,
+    /// Owners to restrict starring to; empty means every owner.
+    pub owners: Vec<String>,
+    /// Frameworks that win over the ones they overlap with, see
+    /// [`Framework::overlaps`].
+    pub prefer: Vec<Framework>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawProjectConfig {
+    exclude: Vec<String>,
+    ecosystems: Option<Vec<String>>,
+    owners: Vec<String>,
+    prefer: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PyProject {
+    #[serde(default)]
+    tool: PyProjectTools,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PyProjectTools {
+    #[serde(rename = "thanks-stars")]
+    thanks_stars: Option<RawProjectConfig>,
+}
+
+impl ProjectConfig {
+    /// Read the configuration of the project in `project_root`; the default
+    /// when neither file exists.
+    pub fn load(project_root: &Path) -> Result<Self, ProjectConfigError> {
+        let mut config = Self::default();
+
+        let pyproject = project_root.join("pyproject.toml");
+        if let Some(contents) = read_optional(&pyproject)? {
+            let parsed: PyProject = parse(&pyproject, &contents)?;
+            if let Some(raw) = parsed.tool.thanks_stars {
+                config.merge(&pyproject, raw)?;
+            }
+        }
+
+        let standalone = project_root.join(PROJECT_CONFIG_FILE);
+        if let Some(contents) = read_optional(&standalone)? {
+            let raw: RawProjectConfig = parse(&standalone, &contents)?;
+            config.merge(&standalone, raw)?;
+        }
+
+        Ok(config)
+    }
+
+    fn merge(&mut self, path: &Path, raw: RawProjectConfig) -> Result<(), ProjectConfigError> {
+        self.exclude.extend(raw.exclude);
+        if !raw.owners.is_empty() {
+            self.owners = raw.owners;
+        }
+        if let Some(names) = raw.ecosystems {
+            self.ecosystems = Some(parse_frameworks(path, &names)?);
+        }
+        if !raw.prefer.is_empty() {
+            self.prefer = parse_frameworks(path, &raw.prefer)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_frameworks(path: &Path, names: &[String]) -> Result<Vec<Framework>, ProjectConfigError> {
+    names
+        .iter()
+        .map(|name| name.parse())
+        .collect::<Result<Vec<Framework>, _>>()
+        .map_err(|source| ProjectConfigError::Ecosystem {
+            path: path.display().to_string(),
+            source,
+        })
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, ProjectConfigError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(ProjectConfigError::Io {
+            path: path.display().to_string(),
+            source,
+        }),
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    contents: &str,
+) -> Result<T, ProjectConfigError> {
+    toml::from_str(contents).map_err(|source| ProjectConfigError::Toml {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reads_excludes_and_ecosystems_from_both_files() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("pyproject.toml"),
+            r#"
+[project]
+name = "app"
+
+[tool.thanks-stars]
+exclude = ["psf/requests"]
+ecosystems = ["python"]
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"
+exclude = ["rust-lang/cargo"]
+ecosystems = ["Cargo", "node"]
+owners = ["rust-lang"]
+"#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(dir.path()).unwrap();
+
+        assert_eq!(config.exclude, vec!["psf/requests", "rust-lang/cargo"]);
+        assert_eq!(
+            config.ecosystems,
+            Some(vec![Framework::Cargo, Framework::Node])
+        );
+        assert_eq!(config.owners, vec!["rust-lang"]);
+    }
+
+    #[test]
+    fn rejects_unknown_ecosystems() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "ecosystems = [\"cobol\"]\n",
+        )
+        .unwrap();
+
+        let err = ProjectConfig::load(dir.path()).unwrap_err();
+
+        assert!(err.to_string().contains("unknown ecosystem `cobol`"));
+    }
+}
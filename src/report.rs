@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::discovery::Repository;
+use crate::{RunEventHandler, RunSummary};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReportError {
+    #[error("unsupported report format for {0}; use a .md or .csv file")]
+    UnsupportedFormat(String),
+    #[error("failed to write report to {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Csv,
+}
+
+impl ReportFormat {
+    /// Infer the report format from the file extension of `path`.
+    pub fn from_path(path: &Path) -> Result<Self, ReportError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("md" | "markdown") => Ok(Self::Markdown),
+            Some("csv") => Ok(Self::Csv),
+            _ => Err(ReportError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryStatus {
+    Starred,
+    AlreadyStarred,
+    Archived,
+}
+
+#[derive(Debug, Clone)]
+struct ReportEntry {
+    repository: Repository,
+    status: EntryStatus,
+}
+
+/// [`RunEventHandler`] that records every repository handled during a run and
+/// writes a Markdown or CSV report once the run completes.
+pub struct ReportHandler {
+    path: PathBuf,
+    format: ReportFormat,
+    dry_run: bool,
+    entries: Vec<ReportEntry>,
+    error: Option<ReportError>,
+}
+
+impl ReportHandler {
+    pub fn new(path: impl Into<PathBuf>, dry_run: bool) -> Result<Self, ReportError> {
+        let path = path.into();
+        let format = ReportFormat::from_path(&path)?;
+        Ok(Self {
+            path,
+            format,
+            dry_run,
+            entries: Vec::new(),
+            error: None,
+        })
+    }
+
+    /// Return the error raised while writing the report, if any.
+    pub fn take_error(&mut self) -> Option<ReportError> {
+        self.error.take()
+    }
+
+    fn render(&self) -> String {
+        match self.format {
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Csv => self.render_csv(),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut groups: BTreeMap<&str, Vec<&ReportEntry>> = BTreeMap::new();
+        for entry in &self.entries {
+            let via = entry.repository.via.as_deref().unwrap_or("unknown source");
+            groups.entry(via).or_default().push(entry);
+        }
+
+        let mut output = String::from("# Thanks Stars report\n");
+        if self.entries.is_empty() {
+            output.push_str("\nNo repositories required starring.\n");
+        }
+        for (via, entries) in groups {
+            output.push_str(&format!("\n## {via}\n\n"));
+            for entry in entries {
+                let status = match entry.status {
+                    EntryStatus::AlreadyStarred => "already starred",
+                    EntryStatus::Archived => "skipped, archived",
+                    EntryStatus::Starred if self.dry_run => "would star",
+                    EntryStatus::Starred => "starred",
+                };
+                let location = entry
+                    .repository
+                    .subdirectory()
+                    .map(|directory| format!(", in `{directory}`"))
+                    .unwrap_or_default();
+                output.push_str(&format!(
+                    "- [{}/{}]({}) ({status}{location})\n",
+                    entry.repository.owner, entry.repository.name, entry.repository.url
+                ));
+            }
+        }
+        output
+    }
+
+    fn render_csv(&self) -> String {
+        let mut output = String::from("owner,name,url,via,already_starred,archived,source_url\n");
+        for entry in &self.entries {
+            let repo = &entry.repository;
+            let row = [
+                csv_field(&repo.owner),
+                csv_field(&repo.name),
+                csv_field(&repo.url),
+                csv_field(&repo.via_label().unwrap_or_default()),
+                (entry.status == EntryStatus::AlreadyStarred).to_string(),
+                (entry.status == EntryStatus::Archived).to_string(),
+                csv_field(repo.original_url.as_deref().unwrap_or("")),
+            ];
+            output.push_str(&row.join(","));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl RunEventHandler for ReportHandler {
+    fn on_starred(
+        &mut self,
+        repo: &Repository,
+        already_starred: bool,
+        _index: usize,
+        _total: usize,
+    ) {
+        let status = if already_starred {
+            EntryStatus::AlreadyStarred
+        } else {
+            EntryStatus::Starred
+        };
+        self.entries.push(ReportEntry {
+            repository: repo.clone(),
+            status,
+        });
+    }
+
+    fn on_archived(&mut self, repo: &Repository, _index: usize, _total: usize) {
+        self.entries.push(ReportEntry {
+            repository: repo.clone(),
+            status: EntryStatus::Archived,
+        });
+    }
+
+    fn on_complete(&mut self, _summary: &RunSummary) {
+        if let Err(source) = fs::write(&self.path, self.render()) {
+            self.error = Some(ReportError::Io {
+                path: self.path.display().to_string(),
+                source,
+            });
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn repository(owner: &str, name: &str, via: &str) -> Repository {
+        Repository {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            url: format!("https://github.com/{owner}/{name}"),
+            via: Some(via.to_string()),
+            also_via: Vec::new(),
+            original_url: None,
+            directory: None,
+            stargazer_count: None,
+            framework: None,
+        }
+    }
+
+    #[test]
+    fn infers_format_from_extension() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("thanks.md")).unwrap(),
+            ReportFormat::Markdown
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("thanks.CSV")).unwrap(),
+            ReportFormat::Csv
+        );
+        assert!(ReportFormat::from_path(Path::new("thanks.txt")).is_err());
+    }
+
+    #[test]
+    fn writes_markdown_grouped_by_via() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("report.md");
+        let mut handler = ReportHandler::new(&path, true).unwrap();
+
+        handler.on_starred(&repository("a", "one", "package.json"), false, 1, 3);
+        handler.on_starred(&repository("b", "two", "Cargo.toml"), true, 2, 3);
+        let mut monorepo = repository("c", "three", "package.json");
+        monorepo.directory = Some("packages/three".to_string());
+        handler.on_starred(&monorepo, false, 3, 3);
+        handler.on_archived(&repository("d", "four", "package.json"), 4, 4);
+        handler.on_complete(&RunSummary::default());
+
+        assert!(handler.take_error().is_none());
+        let contents = fs::read_to_string(path).unwrap();
+        assert_eq!(
+            contents,
+            "# Thanks Stars report\n\
+             \n## Cargo.toml\n\n\
+             - [b/two](https://github.com/b/two) (already starred)\n\
+             \n## package.json\n\n\
+             - [a/one](https://github.com/a/one) (would star)\n\
+             - [c/three](https://github.com/c/three) (would star, in `packages/three`)\n\
+             - [d/four](https://github.com/d/four) (skipped, archived)\n"
+        );
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,260 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{ConfigError, ConfigManager};
+
+pub const TOKEN_ENV: &str = "GITHUB_TOKEN";
+pub const TOKEN_FILE_ENV: &str = "GITHUB_TOKEN_FILE";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("failed to read GitHub token file {path}: {source}")]
+    File {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("GitHub token file {0} is empty")]
+    EmptyFile(String),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("no GitHub token found")]
+    NotFound,
+}
+
+/// Where a GitHub token can come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    /// A file passed with `--token-file`.
+    Flag(PathBuf),
+    /// A file named by the `GITHUB_TOKEN_FILE` environment variable.
+    File(PathBuf),
+    /// An environment variable holding the token itself.
+    EnvVar(String),
+    /// A profile stored in `config.toml`; `None` is the default profile.
+    ConfigProfile(Option<String>),
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Flag(path) => write!(f, "--token-file {}", path.display()),
+            TokenSource::File(path) => write!(f, "{TOKEN_FILE_ENV} ({})", path.display()),
+            TokenSource::EnvVar(name) => f.write_str(name),
+            TokenSource::ConfigProfile(Some(name)) => write!(f, "profile `{name}`"),
+            TokenSource::ConfigProfile(None) => f.write_str("the stored configuration"),
+        }
+    }
+}
+
+impl TokenSource {
+    /// Load the token from this source. `Ok(None)` means the source has no
+    /// token to offer and the next one should be tried.
+    pub fn load(&self, config: &ConfigManager) -> Result<Option<String>, TokenError> {
+        self.load_with_env(config, |name| env::var(name).ok())
+    }
+
+    /// Like [`load`](Self::load), reading environment variables through `env`
+    /// instead of the process environment.
+    pub fn load_with_env(
+        &self,
+        config: &ConfigManager,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<String>, TokenError> {
+        match self {
+            TokenSource::Flag(path) | TokenSource::File(path) => {
+                let contents = fs::read_to_string(path).map_err(|source| TokenError::File {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+                let token = contents.trim();
+                if token.is_empty() {
+                    return Err(TokenError::EmptyFile(path.display().to_string()));
+                }
+                Ok(Some(token.to_string()))
+            }
+            TokenSource::EnvVar(name) => Ok(env(name).filter(|token| !token.trim().is_empty())),
+            TokenSource::ConfigProfile(profile) => {
+                let loaded = match profile {
+                    Some(name) => config.load_token_for_profile(name),
+                    None => config.load_token(),
+                };
+                match loaded {
+                    Ok(token) => Ok(Some(token)),
+                    Err(ConfigError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                        Ok(None)
+                    }
+                    Err(ConfigError::MissingProfile(_)) => Ok(None),
+                    Err(err) => Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+/// Tries a list of [`TokenSource`]s in order and reports which one won.
+#[derive(Debug, Clone)]
+pub struct TokenResolver {
+    sources: Vec<TokenSource>,
+}
+
+impl TokenResolver {
+    pub fn new(sources: Vec<TokenSource>) -> Self {
+        Self { sources }
+    }
+
+    /// The order `run` uses: an explicit `--profile` is the only source when
+    /// given; otherwise `--token-file`, `GITHUB_TOKEN_FILE`, `GITHUB_TOKEN`
+    /// and finally the stored configuration.
+    pub fn from_environment(profile: Option<&str>, token_file: Option<PathBuf>) -> Self {
+        if let Some(profile) = profile {
+            return Self::new(vec![TokenSource::ConfigProfile(Some(profile.to_string()))]);
+        }
+
+        let mut sources = Vec::new();
+        if let Some(path) = token_file {
+            sources.push(TokenSource::Flag(path));
+        } else if let Some(path) = env::var_os(TOKEN_FILE_ENV) {
+            sources.push(TokenSource::File(PathBuf::from(path)));
+        }
+        sources.push(TokenSource::EnvVar(TOKEN_ENV.to_string()));
+        sources.push(TokenSource::ConfigProfile(None));
+        Self::new(sources)
+    }
+
+    pub fn sources(&self) -> &[TokenSource] {
+        &self.sources
+    }
+
+    /// Return the first available token along with the source it came from.
+    pub fn resolve(&self, config: &ConfigManager) -> Result<(String, TokenSource), TokenError> {
+        self.resolve_with_env(config, |name| env::var(name).ok())
+    }
+
+    /// Like [`resolve`](Self::resolve), reading environment variables through
+    /// `env` instead of the process environment.
+    pub fn resolve_with_env(
+        &self,
+        config: &ConfigManager,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<(String, TokenSource), TokenError> {
+        for source in &self.sources {
+            if let Some(token) = source.load_with_env(config, &env)? {
+                return Ok((token, source.clone()));
+            }
+        }
+        Err(TokenError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// An environment holding only `name`, set to `value` when given.
+    fn fake_env<'a>(name: &'a str, value: Option<&'a str>) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| (key == name).then_some(value).flatten().map(str::to_string)
+    }
+
+    #[test]
+    fn flag_file_wins_over_everything_else() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("token");
+        fs::write(&path, "file-token\n").unwrap();
+        let config = ConfigManager::with_base_dir(dir.path());
+        config.save_token("config-token").unwrap();
+
+        let resolver = TokenResolver::new(vec![
+            TokenSource::Flag(path.clone()),
+            TokenSource::EnvVar("THANKS_STARS_TEST_TOKEN_FLAG".to_string()),
+            TokenSource::ConfigProfile(None),
+        ]);
+
+        let (token, source) = resolver
+            .resolve_with_env(
+                &config,
+                fake_env("THANKS_STARS_TEST_TOKEN_FLAG", Some("env-token")),
+            )
+            .unwrap();
+        assert_eq!(token, "file-token");
+        assert_eq!(source, TokenSource::Flag(path));
+    }
+
+    #[test]
+    fn env_var_wins_over_config() {
+        let dir = tempdir().unwrap();
+        let config = ConfigManager::with_base_dir(dir.path());
+        config.save_token("config-token").unwrap();
+
+        let resolver = TokenResolver::new(vec![
+            TokenSource::EnvVar("THANKS_STARS_TEST_TOKEN_ENV".to_string()),
+            TokenSource::ConfigProfile(None),
+        ]);
+
+        let (token, source) = resolver
+            .resolve_with_env(
+                &config,
+                fake_env("THANKS_STARS_TEST_TOKEN_ENV", Some("env-token")),
+            )
+            .unwrap();
+        assert_eq!(token, "env-token");
+        assert_eq!(source.to_string(), "THANKS_STARS_TEST_TOKEN_ENV");
+    }
+
+    #[test]
+    fn falls_back_to_config_when_env_is_blank() {
+        let dir = tempdir().unwrap();
+        let config = ConfigManager::with_base_dir(dir.path());
+        config.save_token_for_profile("work", "work-token").unwrap();
+
+        let resolver = TokenResolver::new(vec![
+            TokenSource::EnvVar("THANKS_STARS_TEST_TOKEN_BLANK".to_string()),
+            TokenSource::ConfigProfile(Some("work".to_string())),
+        ]);
+
+        let (token, source) = resolver
+            .resolve_with_env(
+                &config,
+                fake_env("THANKS_STARS_TEST_TOKEN_BLANK", Some("  ")),
+            )
+            .unwrap();
+        assert_eq!(token, "work-token");
+        assert_eq!(source, TokenSource::ConfigProfile(Some("work".to_string())));
+    }
+
+    #[test]
+    fn reports_not_found_when_no_source_has_a_token() {
+        let dir = tempdir().unwrap();
+        let config = ConfigManager::with_base_dir(dir.path());
+
+        let resolver = TokenResolver::new(vec![
+            TokenSource::EnvVar("THANKS_STARS_TEST_TOKEN_MISSING".to_string()),
+            TokenSource::ConfigProfile(None),
+        ]);
+
+        assert!(matches!(
+            resolver
+                .resolve_with_env(&config, fake_env("THANKS_STARS_TEST_TOKEN_MISSING", None))
+                .unwrap_err(),
+            TokenError::NotFound
+        ));
+    }
+
+    #[test]
+    fn empty_token_file_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("token");
+        fs::write(&path, "\n").unwrap();
+        let config = ConfigManager::with_base_dir(dir.path());
+
+        let resolver = TokenResolver::new(vec![TokenSource::File(path)]);
+
+        assert!(matches!(
+            resolver.resolve(&config).unwrap_err(),
+            TokenError::EmptyFile(_)
+        ));
+    }
+}
@@ -1,30 +1,1145 @@
 use assert_cmd::Command;
 use httpmock::prelude::*;
+use httpmock::Mock;
 use predicates::prelude::*;
 use serde_json::json;
 use std::fs;
-use tempfile::tempdir;
+use tempfile::{tempdir, TempDir};
+
+/// The `thanks-stars` binary under a UTF-8 locale, so output does not switch
+/// to ASCII depending on the environment running the tests.
+fn thanks_stars() -> Command {
+    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    cmd.env("LC_ALL", "C.UTF-8");
+    cmd
+}
+
+/// A Node project depending on `dep`, whose installed `package.json` points
+/// at `https://github.com/example/dep`.
+fn node_project_with_dep() -> TempDir {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+    project
+}
+
+/// Answer every star check on `server` with "not starred yet".
+fn mock_graphql_unstarred(server: &MockServer) -> Mock<'_> {
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    })
+}
 
 #[test]
 fn auth_command_saves_token() {
     let dir = tempdir().unwrap();
-    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    let server = httpmock::MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET)
+            .path("/user")
+            .header("authorization", "token abc123");
+        then.status(200).json_body(json!({ "login": "octocat" }));
+    });
+
+    let mut cmd = thanks_stars();
     cmd.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
         .arg("auth")
         .arg("--token")
         .arg("abc123");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Authenticated as octocat"))
+        .stdout(predicate::str::contains("Token saved"));
+
+    user.assert();
+    let config_path = dir.path().join("config.toml");
+    assert!(config_path.exists());
+    let contents = fs::read_to_string(config_path).unwrap();
+    assert!(contents.contains("abc123"));
+}
+
+#[test]
+fn auth_command_rejects_invalid_token() {
+    let dir = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/user");
+        then.status(401)
+            .json_body(json!({ "message": "Bad credentials" }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .arg("auth")
+        .arg("--token")
+        .arg("typo");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("401 Unauthorized"));
+
+    assert!(!dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn auth_command_skips_verification_with_no_verify() {
+    let dir = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let user = server.mock(|when, then| {
+        when.method(GET).path("/user");
+        then.status(401);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .arg("auth")
+        .arg("--token")
+        .arg("offline-token")
+        .arg("--no-verify");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Token saved"));
+
+    user.assert_calls(0);
+    let contents = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(contents.contains("offline-token"));
+}
+
+#[test]
+fn config_commands_print_path_and_redact_tokens() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "token = \"secret-token\"\n\n[profiles.work]\ntoken = \"work-secret\"\n",
+    )
+    .unwrap();
+
+    let mut path = thanks_stars();
+    path.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .args(["config", "path"]);
+    path.assert().success().stdout(predicate::str::contains(
+        dir.path().join("config.toml").display().to_string(),
+    ));
+
+    let mut show = thanks_stars();
+    show.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .args(["config", "show"]);
+    show.assert()
+        .success()
+        .stdout(predicate::str::contains("profile work: token ***"))
+        .stdout(predicate::str::contains("secret").not());
+}
+
+#[test]
+fn auth_prompt_falls_back_to_plain_read_when_stdin_is_piped() {
+    let dir = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .arg("auth")
+        .arg("--no-verify")
+        .write_stdin("  prompted-token \n");
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Token saved"));
 
-    let config_path = dir.path().join("config.toml");
-    assert!(config_path.exists());
-    let contents = fs::read_to_string(config_path).unwrap();
-    assert!(contents.contains("abc123"));
+    let contents = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(contents.contains("\"prompted-token\""));
+}
+
+#[test]
+fn auth_command_reads_token_from_stdin() {
+    let dir = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/user")
+            .header("authorization", "token piped-token");
+        then.status(200).json_body(json!({ "login": "octocat" }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_CONFIG_DIR", dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .arg("auth")
+        .arg("--stdin")
+        .write_stdin("piped-token\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Token saved"))
+        .stdout(predicate::str::contains("piped-token").not());
+
+    let contents = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(contents.contains("piped-token"));
+}
+
+#[test]
+fn malformed_header_flag_is_rejected() {
+    let project = tempdir().unwrap();
+    fs::write(project.path().join("package.json"), "{}").unwrap();
+
+    let mut cmd = thanks_stars();
+    cmd.env("GITHUB_TOKEN", "cli-token")
+        .current_dir(project.path())
+        .args(["run", "--header", "X-Proxy-Token"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid HTTP header in --header: expected `Name: Value`",
+        ))
+        .stderr(predicate::str::contains("X-Proxy-Token").not());
+}
+
+#[test]
+fn config_flag_overrides_the_configuration_file() {
+    let default_dir = tempdir().unwrap();
+    let custom_dir = tempdir().unwrap();
+    let config_file = custom_dir.path().join("nested").join("custom.toml");
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let star = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/user/starred/example/dep")
+            .header("authorization", "token custom-token");
+        then.status(204);
+    });
+
+    let mut auth = thanks_stars();
+    auth.env("THANKS_STARS_CONFIG_DIR", default_dir.path())
+        .args(["auth", "--no-verify", "--token", "custom-token", "--config"])
+        .arg(&config_file);
+    auth.assert().success();
+    assert!(fs::read_to_string(&config_file)
+        .unwrap()
+        .contains("custom-token"));
+    assert!(!default_dir.path().join("config.toml").exists());
+
+    let mut run = thanks_stars();
+    run.env("THANKS_STARS_CONFIG_DIR", default_dir.path())
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GITHUB_TOKEN_FILE")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("--config")
+        .arg(&config_file)
+        .arg("run");
+    run.assert().success();
+
+    star.assert();
+}
+
+#[cfg(feature = "completions")]
+#[test]
+fn completions_command_prints_a_bash_script() {
+    let mut cmd = thanks_stars();
+    cmd.args(["completions", "bash"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("complete -F _thanks__stars"))
+        .stdout(predicate::str::contains("thanks-stars"))
+        .stdout(predicate::str::contains("--skip-archived"))
+        .stdout(predicate::str::contains("auth"));
+}
+
+#[test]
+fn run_command_stars_dependencies() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .header("authorization", "token cli-token");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    let mock = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/user/starred/example/dep")
+            .header("authorization", "token cli-token");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("run");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Starred https://github.com/example/dep via package.json",
+        ))
+        .stderr(predicate::str::contains(
+            "✨ Completed! ⭐ Starred 1 repository.",
+        ))
+        .stderr(predicate::str::contains(
+            "🔎 Scanned 1 dependency, 1 had GitHub repositories.",
+        ))
+        .stdout(predicate::str::is_empty())
+        .code(0);
+
+    mock.assert();
+    graphql.assert();
+}
+
+#[test]
+fn github_url_flag_targets_the_enterprise_endpoints() {
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/graphql")
+            .header("authorization", "token cli-token");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+    let star = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/api/v3/user/starred/example/dep")
+            .header("authorization", "token cli-token");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", "http://github.invalid")
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--yes", "--github-url", &server.base_url()]);
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "✨ Completed! ⭐ Starred 1 repository.",
+    ));
+
+    graphql.assert();
+    star.assert();
+}
+
+#[test]
+fn output_flag_writes_the_summary_to_a_file() {
+    let project = node_project_with_dep();
+    let output = project.path().join("run.log");
+    fs::write(&output, "stale contents\n").unwrap();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .current_dir(project.path())
+        .args(["run", "--yes", "--output"])
+        .arg(&output);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Completed!").not());
+
+    let contents = fs::read_to_string(&output).unwrap();
+    assert!(!contents.contains("stale contents"));
+    assert!(contents.contains("⭐ Starred https://github.com/example/dep via package.json"));
+    assert!(contents.contains("✨ Completed! ⭐ Starred 1 repository."));
+}
+
+#[test]
+fn skip_star_check_stars_without_querying_graphql() {
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": true}}
+        }));
+    });
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--yes", "--skip-star-check"]);
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "✨ Completed! ⭐ Ensured 1 repository is starred.",
+    ));
+
+    star.assert();
+    graphql.assert_calls(0);
+}
+
+#[test]
+fn yes_flag_stars_without_prompting() {
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--yes"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("[y/N]").not())
+        .stderr(predicate::str::contains(
+            "✨ Completed! ⭐ Starred 1 repository.",
+        ));
+
+    star.assert();
+}
+
+#[test]
+fn run_command_exits_with_code_3_without_manifests() {
+    let project = tempdir().unwrap();
+
+    let mut cmd = thanks_stars();
+    cmd.env("GITHUB_TOKEN", "cli-token")
+        .current_dir(project.path())
+        .arg("run");
+
+    cmd.assert().code(3).stderr(predicate::str::contains(
+        "no supported dependency definitions found",
+    ));
+}
+
+#[test]
+fn print_token_source_names_the_environment_variable() {
+    let project = tempdir().unwrap();
+
+    let mut cmd = thanks_stars();
+    cmd.env("GITHUB_TOKEN", "cli-token")
+        .env_remove("GITHUB_TOKEN_FILE")
+        .current_dir(project.path())
+        .args(["run", "--print-token-source"]);
+
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Using the GitHub token from GITHUB_TOKEN.",
+        ))
+        .stderr(predicate::str::contains("cli-token").not());
+}
+
+#[test]
+fn run_command_exits_with_code_4_without_a_token() {
+    let project = tempdir().unwrap();
+    let config_dir = tempdir().unwrap();
+
+    let mut cmd = thanks_stars();
+    cmd.env_remove("GITHUB_TOKEN")
+        .env_remove("GITHUB_TOKEN_FILE")
+        .env("THANKS_STARS_CONFIG_DIR", config_dir.path())
+        .current_dir(project.path())
+        .arg("run");
+
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("GitHub token not found"));
+}
+
+#[test]
+fn continue_on_error_stars_the_rest_and_exits_with_code_6() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep-a": "^1", "dep-b": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for dep in ["dep-a", "dep-b"] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/example/{dep}") }).to_string(),
+        )
+        .unwrap();
+    }
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let refused = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep-a");
+        then.status(404).body(r#"{"message":"Not Found"}"#);
+    });
+    let starred = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep-b");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--continue-on-error"]);
+
+    cmd.assert()
+        .code(6)
+        .stderr(predicate::str::contains(
+            "❌ Failed https://github.com/example/dep-a",
+        ))
+        .stderr(predicate::str::contains("⭐ Starred 1 repository."))
+        .stderr(predicate::str::contains(
+            "1 repository could not be starred: https://github.com/example/dep-a",
+        ));
+
+    refused.assert();
+    starred.assert();
+}
+
+#[test]
+fn max_flag_limits_new_stars() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep-a": "^1", "dep-b": "^1", "dep-c": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for dep in ["dep-a", "dep-b", "dep-c"] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/example/{dep}") }).to_string(),
+        )
+        .unwrap();
+    }
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let first = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep-a");
+        then.status(204);
+    });
+    let rest = server.mock(|when, then| {
+        when.method(PUT)
+            .path_matches(r"^/user/starred/example/dep-[bc]$");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--max", "1"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "✨ Completed! ⭐ Starred 1 repository.",
+        ))
+        .stderr(predicate::str::contains(
+            "🛑 Stopped after 1 new star; run again to continue.",
+        ));
+
+    first.assert();
+    rest.assert_calls(0);
+}
+
+#[test]
+fn only_owner_flag_restricts_starring() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep-a": "^1", "dep-b": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for (dep, owner) in [("dep-a", "Example"), ("dep-b", "other")] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/{owner}/{dep}") }).to_string(),
+        )
+        .unwrap();
+    }
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let allowed = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/Example/dep-a");
+        then.status(204);
+    });
+    let filtered = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/other/dep-b");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--only-owner", "example"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "✨ Completed! ⭐ Starred 1 repository.",
+        ))
+        .stderr(predicate::str::contains(
+            "🚫 Skipped 1 repository outside the owner allowlist.",
+        ));
+
+    allowed.assert();
+    filtered.assert_calls(0);
+}
+
+#[test]
+fn exclude_flag_skips_listed_repositories() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep-a": "^1", "dep-b": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for dep in ["dep-a", "dep-b"] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/example/{dep}") }).to_string(),
+        )
+        .unwrap();
+    }
+    // The flag replaces the project's own list.
+    fs::write(
+        project.path().join(".thanks-stars.toml"),
+        "exclude = [\"example/dep-a\"]\n",
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+    let kept = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep-a");
+        then.status(204);
+    });
+    let excluded = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep-b");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--exclude", "Example/dep-b"]);
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "🚫 Skipped 1 repository on the exclude list.",
+    ));
+
+    kept.assert();
+    excluded.assert_calls(0);
+}
+
+#[test]
+fn skip_archived_flag_leaves_archived_repositories_alone() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "active": "^1", "archived": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for dep in ["active", "archived"] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/example/{dep}") }).to_string(),
+        )
+        .unwrap();
+    }
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .json_body_includes(r#"{"variables": {"name": "active"}}"#);
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false, "isArchived": false}}
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .json_body_includes(r#"{"variables": {"name": "archived"}}"#);
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false, "isArchived": true}}
+        }));
+    });
+    let active = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/active");
+        then.status(204);
+    });
+    let archived = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/archived");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--skip-archived"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Skipped https://github.com/example/archived (archived)",
+        ))
+        .stderr(predicate::str::contains("Skipped 1 archived repository."));
+
+    active.assert();
+    archived.assert_calls(0);
+}
+
+#[test]
+fn incremental_run_skips_previously_seen_repositories() {
+    let project = tempdir().unwrap();
+    let state_dir = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules").join("dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    let run = || {
+        let mut cmd = thanks_stars();
+        cmd.env("THANKS_STARS_API_BASE", server.base_url())
+            .env("THANKS_STARS_STATE_DIR", state_dir.path())
+            .env("GITHUB_TOKEN", "cli-token")
+            .env("NO_COLOR", "1")
+            .current_dir(project.path())
+            .args(["run", "--incremental"]);
+        cmd.assert().success()
+    };
+
+    run().stderr(predicate::str::contains(
+        "✨ Completed! ⭐ Starred 1 repository.",
+    ));
+    run().stderr(predicate::str::contains(
+        "Skipped 1 repository handled by an earlier --incremental run.",
+    ));
+
+    graphql.assert_calls(1);
+    star.assert_calls(1);
+}
+
+#[test]
+fn run_explains_when_no_dependency_resolves_to_github() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules").join("dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://gitlab.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("run");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "🤷 None of the 1 dependency resolved to a GitHub repository; run with --verbose to see why.",
+        ))
+        .stderr(predicate::str::contains("No repositories required starring").not());
+
+    graphql.assert_calls(0);
+}
+
+#[test]
+fn fixtures_dir_replaces_registry_lookups() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("requirements.txt"),
+        "requests==2.31.0\nunpublished\n",
+    )
+    .unwrap();
+    let fixtures = tempdir().unwrap();
+    fs::create_dir_all(fixtures.path().join("pypi")).unwrap();
+    fs::write(
+        fixtures.path().join("pypi").join("requests.json"),
+        json!({
+            "info": { "project_urls": { "Source": "https://github.com/psf/requests" } }
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("THANKS_STARS_FIXTURES_DIR", fixtures.path())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--dry-run"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/psf/requests via requirements.txt",
+        ))
+        .stderr(predicate::str::contains(
+            "🔎 Scanned 2 dependencies, 1 had GitHub repositories.",
+        ));
+}
+
+#[test]
+fn missing_cargo_binary_falls_back_to_cargo_toml() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n",
+    )
+    .unwrap();
+    let fixtures = tempdir().unwrap();
+    fs::create_dir_all(fixtures.path().join("crates")).unwrap();
+    fs::write(
+        fixtures.path().join("crates").join("serde.json"),
+        json!({ "crate": { "repository": "https://github.com/serde-rs/serde" } }).to_string(),
+    )
+    .unwrap();
+    let empty_path = tempdir().unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("THANKS_STARS_FIXTURES_DIR", fixtures.path())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .env("PATH", empty_path.path())
+        .current_dir(project.path())
+        .args(["run", "--dry-run"]);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/serde-rs/serde via Cargo.toml",
+        ))
+        .stderr(predicate::str::contains(
+            "Warning: `cargo` was not found on PATH",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_cargo_metadata_warns_with_its_error() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n",
+    )
+    .unwrap();
+    let fixtures = tempdir().unwrap();
+    fs::create_dir_all(fixtures.path().join("crates")).unwrap();
+    fs::write(
+        fixtures.path().join("crates").join("serde.json"),
+        json!({ "crate": { "repository": "https://github.com/serde-rs/serde" } }).to_string(),
+    )
+    .unwrap();
+    let bin = tempdir().unwrap();
+    let cargo = bin.path().join("cargo");
+    fs::write(
+        &cargo,
+        "#!/bin/sh\necho 'warning: unused manifest key' >&2\necho 'error: failed to select a version' >&2\nexit 101\n",
+    )
+    .unwrap();
+    fs::set_permissions(&cargo, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("THANKS_STARS_FIXTURES_DIR", fixtures.path())
+        .env("THANKS_STARS_CARGO_BIN", bin.path().join("not-cargo"))
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--dry-run", "--cargo-bin"])
+        .arg(&cargo);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/serde-rs/serde via Cargo.toml",
+        ))
+        .stderr(predicate::str::contains(format!(
+            "Warning: `{} metadata` failed (error: failed to select a version)",
+            cargo.display()
+        )));
+}
+
+#[test]
+fn environment_sets_the_project_path_and_ecosystems() {
+    let project = node_project_with_dep();
+    fs::write(
+        project.path().join("go.mod"),
+        "module example.com/app\n\nrequire github.com/pkg/errors v0.9.1\n",
+    )
+    .unwrap();
+    let elsewhere = tempdir().unwrap();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .env("THANKS_STARS_PATH", project.path())
+        .current_dir(elsewhere.path())
+        .args(["run", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/example/dep via package.json",
+        ))
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/pkg/errors via go.mod",
+        ));
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .env("THANKS_STARS_PATH", project.path())
+        .env("THANKS_STARS_ONLY", "go")
+        .current_dir(elsewhere.path())
+        .args(["run", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/pkg/errors via go.mod",
+        ))
+        .stderr(predicate::str::contains("example/dep").not());
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .env("THANKS_STARS_ONLY", "go")
+        .current_dir(project.path())
+        .args(["run", "--dry-run", "--only", "node"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("example/dep"))
+        .stderr(predicate::str::contains("pkg/errors").not());
+}
+
+#[test]
+fn ledger_is_shared_between_projects() {
+    let projects = [node_project_with_dep(), node_project_with_dep()];
+    let ledger_dir = tempdir().unwrap();
+    let ledger = ledger_dir.path().join("stars.jsonl");
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    for (index, project) in projects.iter().enumerate() {
+        let mut cmd = thanks_stars();
+        cmd.env("THANKS_STARS_API_BASE", server.base_url())
+            .env("GITHUB_TOKEN", "cli-token")
+            .env("NO_COLOR", "1")
+            .current_dir(project.path())
+            .args(["run", "--yes", "--ledger"])
+            .arg(&ledger);
+        let skipped = predicate::str::contains(
+            "🚫 Skipped 1 repository already starred according to the ledger.",
+        );
+        if index == 0 {
+            cmd.assert().success().stderr(skipped.not());
+        } else {
+            cmd.assert().success().stderr(skipped);
+        }
+    }
+
+    star.assert_calls(1);
+    let contents = fs::read_to_string(&ledger).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(entry["repository"], "example/dep");
+    assert!(entry["starred_at"].as_u64().unwrap() > 0);
+    assert!(entry["project"]
+        .as_str()
+        .unwrap()
+        .ends_with(projects[0].path().file_name().unwrap().to_str().unwrap()));
+}
+
+#[test]
+fn fail_on_unresolved_lists_unresolved_dependencies() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1", "elsewhere": "^1" } }).to_string(),
+    )
+    .unwrap();
+    for (name, repository) in [
+        ("dep", "https://github.com/example/dep"),
+        ("elsewhere", "https://gitlab.com/example/elsewhere"),
+    ] {
+        let dep_dir = project.path().join("node_modules").join(name);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": repository }).to_string(),
+        )
+        .unwrap();
+    }
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": true}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--fail-on-unresolved"]);
+
+    cmd.assert().code(5).stderr(predicate::str::contains(
+        "1 dependency did not resolve to a GitHub repository: elsewhere",
+    ));
 }
 
 #[test]
-fn run_command_stars_dependencies() {
+fn run_command_dry_run_skips_starring() {
     let project = tempdir().unwrap();
     fs::write(
         project.path().join("package.json"),
@@ -56,78 +1171,165 @@ fn run_command_stars_dependencies() {
         then.status(204);
     });
 
-    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    let mut cmd = thanks_stars();
     cmd.env("THANKS_STARS_API_BASE", server.base_url())
         .env("GITHUB_TOKEN", "cli-token")
         .env("NO_COLOR", "1")
         .current_dir(project.path())
-        .arg("run");
+        .arg("run")
+        .arg("--dry-run");
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains(
-            "⭐ Starred https://github.com/example/dep via package.json",
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/example/dep via package.json",
         ))
-        .stdout(predicate::str::contains(
-            "✨ Completed! ⭐ Starred 1 repository.",
+        .stderr(predicate::str::contains(
+            "✨ Dry run complete! ⭐ 1 repository would be starred.",
         ));
 
-    mock.assert();
+    assert_eq!(mock.calls(), 0);
     graphql.assert();
 }
 
 #[test]
-fn run_command_dry_run_skips_starring() {
-    let project = tempdir().unwrap();
+fn ascii_flag_prints_no_multibyte_glyphs() {
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+
+    let mut cmd = thanks_stars();
+    let output = cmd
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--dry-run", "--ascii"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("[would star] https://github.com/example/dep via package.json"));
+    assert!(stderr.contains("Dry run complete! 1 repository would be starred."));
+    assert!(stderr.is_ascii(), "unexpected glyphs in {stderr:?}");
+}
+
+#[test]
+fn show_stars_flag_prints_stargazer_counts() {
+    let project = node_project_with_dep();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false, "stargazerCount": 12345}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--dry-run", "--show-stars"]);
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "⭐ Would star https://github.com/example/dep (⭐ 12.3k) via package.json",
+    ));
+    graphql.assert_calls(1);
+}
+
+#[test]
+fn group_by_flag_prints_sections_per_ecosystem() {
+    let project = node_project_with_dep();
     fs::write(
-        project.path().join("package.json"),
-        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+        project.path().join("go.mod"),
+        "module example.com/app\n\nrequire github.com/pkg/errors v0.9.1\n",
     )
     .unwrap();
-    let dep_dir = project.path().join("node_modules/dep");
-    fs::create_dir_all(&dep_dir).unwrap();
+
+    let server = httpmock::MockServer::start();
+    mock_graphql_unstarred(&server);
+
+    let mut cmd = thanks_stars();
+    let output = cmd
+        .env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["run", "--dry-run", "--group-by", "ecosystem"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let go = stderr.find("go (1)\n  ⭐ Would star https://github.com/pkg/errors via go.mod");
+    let node =
+        stderr.find("node (1)\n  ⭐ Would star https://github.com/example/dep via package.json");
+    assert!(
+        go.is_some() && node.is_some(),
+        "missing sections in {stderr:?}"
+    );
+    assert!(go < node);
+}
+
+#[test]
+fn new_only_flag_hides_already_starred_repositories() {
+    let project = tempdir().unwrap();
     fs::write(
-        dep_dir.join("package.json"),
-        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+        project.path().join("package.json"),
+        json!({ "dependencies": { "new-dep": "^1.0.0", "old-dep": "^1.0.0" } }).to_string(),
     )
     .unwrap();
+    for (dep, repo) in [("new-dep", "fresh"), ("old-dep", "known")] {
+        let dep_dir = project.path().join("node_modules").join(dep);
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(
+            dep_dir.join("package.json"),
+            json!({ "repository": format!("https://github.com/example/{repo}") }).to_string(),
+        )
+        .unwrap();
+    }
 
     let server = httpmock::MockServer::start();
-    let graphql = server.mock(|when, then| {
+    server.mock(|when, then| {
         when.method(POST)
             .path("/graphql")
-            .header("authorization", "token cli-token");
+            .json_body_includes(r#"{"variables": {"name": "fresh"}}"#);
         then.status(200).json_body(json!({
             "data": {"repository": {"viewerHasStarred": false}}
         }));
     });
-
-    let mock = server.mock(|when, then| {
-        when.method(PUT)
-            .path("/user/starred/example/dep")
-            .header("authorization", "token cli-token");
-        then.status(204);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .json_body_includes(r#"{"variables": {"name": "known"}}"#);
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": true}}
+        }));
     });
 
-    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    let mut cmd = thanks_stars();
     cmd.env("THANKS_STARS_API_BASE", server.base_url())
         .env("GITHUB_TOKEN", "cli-token")
         .env("NO_COLOR", "1")
         .current_dir(project.path())
-        .arg("run")
-        .arg("--dry-run");
+        .args(["run", "--dry-run", "--new-only"]);
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains(
-            "⭐ Would star https://github.com/example/dep via package.json",
+        .stderr(predicate::str::contains(
+            "⭐ Would star https://github.com/example/fresh via package.json",
         ))
-        .stdout(predicate::str::contains(
-            "✨ Dry run complete! ⭐ 1 repository would be starred.",
+        .stderr(predicate::str::contains("example/known").not())
+        .stderr(predicate::str::contains(
+            "🙈 Hid 1 already-starred repository.",
+        ))
+        .stderr(predicate::str::contains(
+            "⭐ 1 repository would be starred, ✅ 1 already starred.",
         ));
-
-    assert_eq!(mock.calls(), 0);
-    graphql.assert();
 }
 
 #[test]
@@ -163,7 +1365,7 @@ fn default_command_accepts_dry_run_flag() {
         then.status(204);
     });
 
-    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    let mut cmd = thanks_stars();
     cmd.env("THANKS_STARS_API_BASE", server.base_url())
         .env("GITHUB_TOKEN", "cli-token")
         .env("NO_COLOR", "1")
@@ -172,10 +1374,10 @@ fn default_command_accepts_dry_run_flag() {
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains(
             "⭐ Would star https://github.com/example/dep via package.json",
         ))
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains(
             "✨ Dry run complete! ⭐ 1 repository would be starred.",
         ));
 
@@ -215,7 +1417,7 @@ fn run_command_reports_already_starred() {
             .header("authorization", "token cli-token");
     });
 
-    let mut cmd = Command::cargo_bin("thanks-stars").unwrap();
+    let mut cmd = thanks_stars();
     cmd.env("THANKS_STARS_API_BASE", server.base_url())
         .env("GITHUB_TOKEN", "cli-token")
         .env("NO_COLOR", "1")
@@ -224,13 +1426,277 @@ fn run_command_reports_already_starred() {
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains(
             "✅ Already starred https://github.com/example/dep (already starred) via package.json",
         ))
-        .stdout(predicate::str::contains(
+        .stderr(predicate::str::contains(
             "✨ Completed! ✅ All 1 repository were already starred.",
         ));
 
     star_mock.assert_calls(0);
     graphql.assert();
 }
+
+#[test]
+fn run_command_cache_skips_repeated_star_checks() {
+    let project = tempdir().unwrap();
+    let cache_dir = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/user");
+        then.status(200).json_body(json!({ "login": "Octocat" }));
+    });
+    let graphql = server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": true}}
+        }));
+    });
+
+    for _ in 0..2 {
+        let mut cmd = thanks_stars();
+        cmd.env("THANKS_STARS_API_BASE", server.base_url())
+            .env("THANKS_STARS_CACHE_DIR", cache_dir.path())
+            .env("GITHUB_TOKEN", "cli-token")
+            .env("NO_COLOR", "1")
+            .current_dir(project.path())
+            .arg("run")
+            .arg("--cache");
+        cmd.assert().success().stderr(predicate::str::contains(
+            "✅ All 1 repository were already starred.",
+        ));
+    }
+
+    graphql.assert_calls(1);
+    assert!(cache_dir.path().join("starred-octocat.json").exists());
+}
+
+#[test]
+fn run_command_writes_csv_report_in_dry_run() {
+    let project = tempdir().unwrap();
+    let output = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    let report_path = output.path().join("thanks.csv");
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("--dry-run")
+        .arg("--report")
+        .arg(&report_path);
+
+    cmd.assert().success();
+
+    let contents = fs::read_to_string(report_path).unwrap();
+    assert_eq!(
+        contents,
+        "owner,name,url,via,already_starred,archived,source_url\n\
+         example,dep,https://github.com/example/dep,package.json,false,false,https://github.com/example/dep\n"
+    );
+}
+
+#[test]
+fn verbose_flag_logs_skipped_dependencies() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0", "not-installed": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+
+    for (verbose, expect_log) in [(false, false), (true, true)] {
+        let mut cmd = thanks_stars();
+        cmd.env("THANKS_STARS_API_BASE", server.base_url())
+            .env("GITHUB_TOKEN", "cli-token")
+            .env("NO_COLOR", "1")
+            .current_dir(project.path())
+            .arg("--dry-run");
+        if verbose {
+            cmd.arg("-v");
+        }
+
+        let log = predicate::str::contains("skipping: not installed in node_modules")
+            .and(predicate::str::contains("not-installed"));
+        if expect_log {
+            cmd.assert().success().stderr(log);
+        } else {
+            cmd.assert().success().stderr(log.not());
+        }
+    }
+}
+
+#[test]
+fn quiet_flag_prints_only_the_summary() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/example/dep");
+        then.status(204);
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("--quiet");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("⭐ Starred https://github.com/example/dep").not())
+        .stderr(predicate::str::contains(
+            "✨ Completed! ⭐ Starred 1 repository.",
+        ));
+
+    star.assert();
+}
+
+#[test]
+fn timeout_flag_reports_slow_github_responses() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("package.json"),
+        json!({ "dependencies": { "dep": "^1.0.0" } }).to_string(),
+    )
+    .unwrap();
+    let dep_dir = project.path().join("node_modules/dep");
+    fs::create_dir_all(&dep_dir).unwrap();
+    fs::write(
+        dep_dir.join("package.json"),
+        json!({ "repository": "https://github.com/example/dep" }).to_string(),
+    )
+    .unwrap();
+
+    let server = httpmock::MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200)
+            .delay(std::time::Duration::from_secs(5))
+            .json_body(json!({
+                "data": {"repository": {"viewerHasStarred": false}}
+            }));
+    });
+
+    let started = std::time::Instant::now();
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "cli-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .args(["--timeout", "1", "--retries", "0"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "GitHub did not respond within 1 seconds",
+        ))
+        .stderr(predicate::str::contains("--timeout"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn run_command_reads_token_from_file() {
+    let project = node_project_with_dep();
+    let secrets = tempdir().unwrap();
+    let token_file = secrets.path().join("github-token");
+    fs::write(&token_file, "file-token\n").unwrap();
+
+    let server = httpmock::MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .header("authorization", "token file-token");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": true}}
+        }));
+    });
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN", "env-token")
+        .env("NO_COLOR", "1")
+        .current_dir(project.path())
+        .arg("--token-file")
+        .arg(&token_file);
+
+    cmd.assert().success();
+    graphql.assert();
+
+    let mut cmd = thanks_stars();
+    cmd.env("THANKS_STARS_API_BASE", server.base_url())
+        .env("GITHUB_TOKEN_FILE", secrets.path().join("missing"))
+        .env("NO_COLOR", "1")
+        .current_dir(project.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to read GitHub token file"));
+}
@@ -1,6 +1,8 @@
 use httpmock::prelude::*;
 use serde_json::json;
-use thanks_stars::github::{GitHubApi, GitHubClient, GitHubError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thanks_stars::github::{GitHubApi, GitHubClient, GitHubError, Sleeper};
 
 #[test]
 fn stars_repository_successfully() {
@@ -18,6 +20,29 @@ fn stars_repository_successfully() {
     mock.assert();
 }
 
+#[test]
+fn enterprise_url_keeps_only_the_origin_and_path() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/ghe/api/v3/user/starred/owner/repo")
+            .header("authorization", "token test-token");
+        then.status(204);
+    });
+
+    let github_url = server.base_url().replacen("://", "://someone:secret@", 1);
+    let client = GitHubClient::for_github_url("test-token", &format!("{github_url}/ghe/")).unwrap();
+    client.star("owner", "repo").unwrap();
+    mock.assert();
+
+    for suffix in ["/ghe?tab=stars", "/ghe#top"] {
+        let err = GitHubClient::for_github_url("test-token", &format!("{github_url}{suffix}"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, GitHubError::InvalidUrl { .. }), "{err:?}");
+    }
+}
+
 #[test]
 fn surfaces_api_errors() {
     let server = MockServer::start();
@@ -53,6 +78,48 @@ fn viewer_has_starred_returns_flag() {
     mock.assert();
 }
 
+#[test]
+fn repository_status_reports_archived_repositories() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .body_includes("isArchived");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false, "isArchived": true}}
+        }));
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    let status = client.repository_status("owner", "repo").unwrap();
+    assert!(status.is_archived);
+    assert!(!status.viewer_has_starred);
+    mock.assert();
+}
+
+#[test]
+fn repository_status_reports_the_stargazer_count() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .body_includes("stargazerCount");
+        then.status(200).json_body(json!({
+            "data": {"repository": {
+                "viewerHasStarred": true,
+                "isArchived": false,
+                "stargazerCount": 12345
+            }}
+        }));
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    let status = client.repository_status("owner", "repo").unwrap();
+    assert!(status.viewer_has_starred);
+    assert_eq!(status.stargazer_count, Some(12345));
+    mock.assert();
+}
+
 #[test]
 fn viewer_has_starred_surfaces_errors() {
     let server = MockServer::start();
@@ -71,3 +138,247 @@ fn viewer_has_starred_surfaces_errors() {
         other => panic!("unexpected error: {other:?}"),
     }
 }
+
+#[test]
+fn current_user_returns_login() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/user")
+            .header("authorization", "token test-token");
+        then.status(200).json_body(json!({ "login": "octocat" }));
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    let login = client.current_user().unwrap();
+    assert_eq!(login, "octocat");
+    mock.assert();
+}
+
+#[test]
+fn current_user_surfaces_unauthorized() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/user");
+        then.status(401)
+            .json_body(json!({ "message": "Bad credentials" }));
+    });
+
+    let client = GitHubClient::with_base_url("bad-token", server.base_url()).unwrap();
+    let err = client.current_user().unwrap_err();
+
+    match err {
+        GitHubError::Api { status, .. } => assert_eq!(status, 401),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn redacts_token_echoed_in_error_body() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/repo");
+        then.status(400)
+            .body("bad request; Authorization: token secret-token (secret-token)");
+    });
+
+    let client = GitHubClient::with_base_url("secret-token", server.base_url()).unwrap();
+    let err = client.star("owner", "repo").unwrap_err();
+
+    assert!(!err.to_string().contains("secret-token"));
+    match err {
+        GitHubError::Api { body, .. } => {
+            assert_eq!(body, "bad request; Authorization: token *** (***)");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn reports_missing_scope_when_star_returns_not_found() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/repo");
+        then.status(404)
+            .header("x-oauth-scopes", "read:user, gist")
+            .header("x-accepted-oauth-scopes", "public_repo, repo")
+            .json_body(json!({ "message": "Not Found" }));
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    let err = client.star("owner", "repo").unwrap_err();
+
+    match err {
+        GitHubError::InsufficientScope {
+            repository,
+            granted_scopes,
+        } => {
+            assert_eq!(repository, "owner/repo");
+            assert_eq!(granted_scopes.as_deref(), Some("read:user, gist"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn keeps_not_found_when_token_has_star_scope() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/missing");
+        then.status(404)
+            .header("x-oauth-scopes", "public_repo")
+            .body("Not Found");
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    let err = client.star("owner", "missing").unwrap_err();
+
+    assert!(matches!(err, GitHubError::Api { status: 404, .. }));
+}
+
+/// Remembers how long the client asked to wait instead of waiting.
+#[derive(Clone, Default)]
+struct RecordingSleeper(Arc<Mutex<Vec<Duration>>>);
+
+impl Sleeper for RecordingSleeper {
+    fn sleep(&self, duration: Duration) {
+        self.0.lock().unwrap().push(duration);
+    }
+}
+
+#[test]
+fn waits_out_secondary_rate_limits_before_giving_up() {
+    let server = MockServer::start();
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/repo");
+        then.status(403).header("retry-after", "42").json_body(json!({
+            "message": "You have exceeded a secondary rate limit. Please wait a few minutes before you try again."
+        }));
+    });
+
+    let sleeper = RecordingSleeper::default();
+    let client = GitHubClient::with_base_url("test-token", server.base_url())
+        .unwrap()
+        .with_retry(2)
+        .with_sleeper(sleeper.clone());
+    let err = client.star("owner", "repo").unwrap_err();
+
+    match err {
+        GitHubError::SecondaryRateLimit { retry_after } => {
+            assert_eq!(retry_after, Duration::from_secs(42));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    star.assert_calls(3);
+    assert_eq!(*sleeper.0.lock().unwrap(), vec![Duration::from_secs(42); 2]);
+}
+
+#[test]
+fn retries_server_errors_with_doubling_backoff() {
+    let server = MockServer::start();
+    let star = server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/repo");
+        then.status(502).body("bad gateway");
+    });
+
+    let sleeper = RecordingSleeper::default();
+    let client = GitHubClient::with_base_url("test-token", server.base_url())
+        .unwrap()
+        .with_retry(3)
+        .with_sleeper(sleeper.clone());
+    let err = client.star("owner", "repo").unwrap_err();
+
+    assert!(matches!(err, GitHubError::Api { status: 502, .. }));
+    star.assert_calls(4);
+    let delays = sleeper.0.lock().unwrap().clone();
+    let bases = [
+        Duration::from_millis(500),
+        Duration::from_secs(1),
+        Duration::from_secs(2),
+    ];
+    assert_eq!(delays.len(), bases.len());
+    for (delay, base) in delays.into_iter().zip(bases) {
+        assert!(delay >= base && delay <= base * 3 / 2, "{delay:?}");
+    }
+}
+
+#[test]
+fn extra_headers_are_sent_with_every_request() {
+    let server = MockServer::start();
+    let graphql = server.mock(|when, then| {
+        when.method(POST)
+            .path("/graphql")
+            .header("proxy-authorization", "Bearer proxy-secret");
+        then.status(200).json_body(json!({
+            "data": {"repository": {"viewerHasStarred": false}}
+        }));
+    });
+    let star = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/user/starred/owner/repo")
+            .header("proxy-authorization", "Bearer proxy-secret");
+        then.status(204);
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url())
+        .unwrap()
+        .with_header("Proxy-Authorization", " Bearer proxy-secret")
+        .unwrap();
+    assert!(!client.viewer_has_starred("owner", "repo").unwrap());
+    client.star("owner", "repo").unwrap();
+    graphql.assert();
+    star.assert();
+
+    let err = GitHubClient::new("test-token")
+        .unwrap()
+        .with_header("Bad Header", "value")
+        .err()
+        .unwrap();
+    assert!(matches!(err, GitHubError::InvalidHeader { name, .. } if name == "Bad Header"));
+}
+
+#[test]
+fn requests_go_through_the_configured_proxy() {
+    let proxy = MockServer::start();
+    let mock = proxy.mock(|when, then| {
+        when.method(PUT)
+            .host("github.invalid")
+            .path("/user/starred/owner/repo");
+        then.status(204);
+    });
+
+    let client = GitHubClient::with_base_url("test-token", "http://github.invalid")
+        .unwrap()
+        .with_proxy(&proxy.base_url())
+        .unwrap();
+    client.star("owner", "repo").unwrap();
+    mock.assert();
+}
+
+#[test]
+fn usage_counts_requests_and_reads_the_rate_limit_budget() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST).path("/graphql");
+        then.status(200)
+            .header("x-ratelimit-remaining", "4998")
+            .header("x-ratelimit-reset", "1700000000")
+            .json_body(json!({
+                "data": {"repository": {"viewerHasStarred": false}}
+            }));
+    });
+    server.mock(|when, then| {
+        when.method(PUT).path("/user/starred/owner/repo");
+        then.status(204).header("x-ratelimit-remaining", "4997");
+    });
+
+    let client = GitHubClient::with_base_url("test-token", server.base_url()).unwrap();
+    assert_eq!(client.usage().unwrap().calls, 0);
+    client.viewer_has_starred("owner", "repo").unwrap();
+    client.star("owner", "repo").unwrap();
+
+    let usage = client.usage().unwrap();
+    assert_eq!(usage.calls, 2);
+    assert_eq!(usage.rate_limit_remaining, Some(4997));
+    assert_eq!(usage.rate_limit_reset, Some(1_700_000_000));
+}